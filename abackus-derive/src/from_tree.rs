@@ -0,0 +1,170 @@
+//! `#[derive(FromTree)]`: map `abackus::Tree`s into structs and enums.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Fields, Ident, LitStr};
+
+// Options set with #[abackus(rule = "...", symbol = "...", lexeme = "...")]
+#[derive(Default)]
+struct Attrs {
+    rule: Option<LitStr>,
+    symbol: Option<LitStr>,
+    lexeme: Option<LitStr>,
+}
+
+impl Attrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut parsed = Attrs::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("abackus")) {
+            attr.parse_nested_meta(|meta| {
+                let slot = match () {
+                    _ if meta.path.is_ident("rule") => &mut parsed.rule,
+                    _ if meta.path.is_ident("symbol") => &mut parsed.symbol,
+                    _ if meta.path.is_ident("lexeme") => &mut parsed.lexeme,
+                    _ => return Err(meta.error("expected `rule`, `symbol` or `lexeme`")),
+                };
+                *slot = Some(meta.value()?.parse()?);
+                Ok(())
+            })?;
+        }
+        Ok(parsed)
+    }
+}
+
+// MonthName -> month_name
+fn snake_case(ident: &Ident) -> String {
+    let mut out = String::new();
+    for (idx, c) in ident.to_string().chars().enumerate() {
+        if c.is_uppercase() && idx > 0 {
+            out.push('_');
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+// Expression building fields (named or positional) out of `tree`. Single
+// field tuples are `transparent` wrappers of the tree unless they map to a
+// rule, then they're built from its only item (eg: expr in '(' expr ')').
+fn fields(path: TokenStream, context: &str, fields: &Fields, transparent: bool)
+        -> syn::Result<TokenStream> {
+    match fields {
+        Fields::Named(named) => {
+            let mut inits = Vec::new();
+            for field in &named.named {
+                let ident = field.ident.as_ref().unwrap();
+                let symbol = Attrs::parse(&field.attrs)?.symbol
+                    .map_or(ident.to_string(), |s| s.value());
+                let context = format!("{}.{}", context, ident);
+                inits.push(quote! {
+                    #ident: ::abackus::FromTree::from_matches(
+                        #symbol, &tree.children_named(#symbol))
+                        .map_err(|e| format!("{}: {}", #context, e))?
+                });
+            }
+            Ok(quote! { #path { #( #inits, )* } })
+        },
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 && transparent => Ok(quote! {
+            #path(::abackus::FromTree::from_tree(tree)
+                .map_err(|e| format!("{}: {}", #context, e))?)
+        }),
+        Fields::Unnamed(unnamed) => {
+            let count = unnamed.unnamed.len();
+            let inits = (0..count).map(|idx| {
+                let context = format!("{}.{}", context, idx);
+                quote! {
+                    ::abackus::FromTree::from_tree(items[#idx])
+                        .map_err(|e| format!("{}: {}", #context, e))?
+                }
+            });
+            Ok(quote! {{
+                let items = tree.items();
+                if items.len() != #count {
+                    return Err(format!("{}: expected {} items but found {} in {}",
+                                       #context, #count, items.len(), tree.describe()));
+                }
+                #path( #( #inits, )* )
+            }})
+        },
+        Fields::Unit => Ok(path),
+    }
+}
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let context = name.to_string();
+    let attrs = Attrs::parse(&input.attrs)?;
+    // narrow the tree down to the rule the type maps to
+    let find_rule = attrs.rule.as_ref().map(|rule| quote! {
+        let tree = tree.find_rule(#rule).map_err(|e| format!("{}: {}", #context, e))?;
+    });
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let transparent = attrs.rule.is_none();
+            let build = fields(quote!(#name), &context, &data.fields, transparent)?;
+            quote! { Ok(#build) }
+        },
+        Data::Enum(data) => {
+            let mut attempts = Vec::new();
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let context = format!("{}::{}", name, ident);
+                let attrs = Attrs::parse(&variant.attrs)?;
+                let transparent = attrs.rule.is_none();
+                let build = fields(quote!(#name::#ident), &context, &variant.fields,
+                                   transparent)?;
+                attempts.push(match (&variant.fields, attrs.rule) {
+                    // a full rule label (`head -> a b`) or the head of a rule
+                    (_, Some(rule)) => quote! {
+                        let mut found = Some(tree);
+                        while let Some(tree) = found {
+                            match tree {
+                                ::abackus::Tree::Node(label, _)
+                                    if label == #rule || tree.symbol() == #rule =>
+                                    return Ok(#build),
+                                ::abackus::Tree::Node(_, subtrees) if subtrees.len() == 1 =>
+                                    found = subtrees.first(),
+                                _ => found = None,
+                            }
+                        }
+                    },
+                    (Fields::Unit, None) => {
+                        let lexeme = attrs.lexeme.map_or(snake_case(ident), |l| l.value());
+                        quote! {
+                            if tree.lexeme().ok() == Some(#lexeme) {
+                                return Ok(#build);
+                            }
+                        }
+                    },
+                    (Fields::Unnamed(f), None) if f.unnamed.len() == 1 => {
+                        let symbol = attrs.symbol.map_or(snake_case(ident), |s| s.value());
+                        quote! {
+                            let found = tree.children_named(#symbol);
+                            if let Some(tree) = found.first().copied()
+                                    .or_else(|| tree.find_rule(#symbol).ok()) {
+                                return Ok(#build);
+                            }
+                        }
+                    },
+                    _ => return Err(syn::Error::new_spanned(variant,
+                        "variants with several fields need #[abackus(rule = \"...\")]")),
+                });
+            }
+            quote! {
+                #( { #attempts } )*
+                Err(format!("{}: no variant matches {}", #context, tree.describe()))
+            }
+        },
+        Data::Union(_) => return Err(syn::Error::new_spanned(
+            &input, "FromTree can't be derived for unions")),
+    };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::abackus::FromTree for #name #ty_generics #where_clause {
+            fn from_tree(tree: &::abackus::Tree) -> Result<Self, String> {
+                #find_rule
+                #body
+            }
+        }
+    })
+}
@@ -0,0 +1,159 @@
+#![deny(warnings)]
+
+//! Compile-time EBNF grammars for [abackus](https://crates.io/crates/abackus).
+//!
+//! `ebnf!` parses the grammar while compiling, reports grammar errors as
+//! compile errors and generates an enum with one variant per grammar rule.
+//! `#[derive(FromTree)]` maps parse trees into structs and enums.
+
+mod from_tree;
+mod lower;
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, DeriveInput, Ident, LitStr, Token, Visibility};
+
+// ebnf!(pub Name, "start", "grammar")
+struct EbnfInput {
+    vis: Visibility,
+    name: Ident,
+    start: LitStr,
+    grammar: LitStr,
+}
+
+impl Parse for EbnfInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        let name = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let start = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let grammar = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(EbnfInput { vis, name, start, grammar })
+    }
+}
+
+/// Build a grammar at compile time from its EBNF description.
+///
+/// ```ignore
+/// abackus_derive::ebnf!(pub Arith, "expr", r#"
+///     expr := expr '+' num | num ;
+/// "#);
+///
+/// let parser = Arith::into_parser(abackus::ParserBuilder::default()
+///     .plug_terminal("num", |n| n.parse::<f64>().is_ok()))?;
+/// ```
+///
+/// Generates `enum Arith` with a variant per rule (`Expr0`, `Expr1`, ...)
+/// which maps to/from the rule labels used by `EarleyForest::action`.
+/// Names that have no rules (eg: `num`) must be plugged as terminals.
+/// The generated code refers to the `abackus` and `earlgrey` crates.
+#[proc_macro]
+pub fn ebnf(input: TokenStream) -> TokenStream {
+    let EbnfInput { vis, name, start, grammar } = parse_macro_input!(input as EbnfInput);
+    let lowered = match lower::lower(&start.value(), &grammar.value()) {
+        Ok(lowered) => lowered,
+        Err(e) if e.contains("Start symbol") || e.starts_with("Missing Symbol") =>
+            return syn::Error::new(start.span(), e).to_compile_error().into(),
+        Err(e) => return syn::Error::new(grammar.span(), e).to_compile_error().into(),
+    };
+
+    // Number alternatives of each head to name the variants
+    let mut variants = Vec::new();
+    for (idx, (head, _)) in lowered.rules.iter().enumerate() {
+        let n = lowered.rules[..idx].iter().filter(|(h, _)| h == head).count();
+        variants.push(format_ident!("{}", lower::variant_name(head, n)));
+    }
+    for (idx, v) in variants.iter().enumerate() {
+        if variants[..idx].contains(v) {
+            let e = format!("Rule heads collide on variant name {}", v);
+            return syn::Error::new(grammar.span(), e).to_compile_error().into();
+        }
+    }
+    let labels: Vec<_> = lowered.rules.iter()
+        .map(|(head, spec)| format!("{} -> {}", head, spec.join(" ")))
+        .collect();
+    let nonterms = &lowered.nonterms;
+    let literals = &lowered.literals;
+    let heads = lowered.rules.iter().map(|(head, _)| head);
+    let specs = lowered.rules.iter().map(|(_, spec)| spec);
+
+    quote! {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #vis enum #name {
+            #( #[doc = #labels] #variants, )*
+        }
+
+        impl #name {
+            /// Start symbol of the grammar
+            pub const START: &'static str = #start;
+
+            /// All rules of the grammar
+            pub const RULES: &'static [#name] = &[ #( #name::#variants, )* ];
+
+            /// Rule label as used by `EarleyForest::action`
+            pub fn rule(&self) -> &'static str {
+                match self { #( #name::#variants => #labels, )* }
+            }
+
+            /// Find the variant for a rule label
+            pub fn from_rule(rule: &str) -> Option<Self> {
+                match rule { #( #labels => Some(#name::#variants), )* _ => None }
+            }
+
+            /// Register the grammar's symbols and rules. Pre-existing
+            /// symbols (eg: plugged terminals) are left untouched.
+            pub fn grammar_builder(gb: &mut ::earlgrey::GrammarBuilder) {
+                #( gb.quiet_nonterm(#nonterms); )*
+                #( gb.quiet_literal(#literals, #literals, |s| s == #literals); )*
+                #({
+                    let spec: &[&str] = &[ #( #specs ),* ];
+                    gb.quiet_rule(#heads, spec);
+                })*
+            }
+
+            /// Build a parser on top of a (possibly pre-plugged) ParserBuilder
+            pub fn into_parser(mut builder: ::abackus::ParserBuilder)
+                    -> Result<::earlgrey::EarleyParser, String> {
+                Self::grammar_builder(&mut builder.0);
+                Ok(::earlgrey::EarleyParser::new(builder.0.into_grammar(#start)?))
+            }
+        }
+    }.into()
+}
+
+/// Map `abackus::Tree`s into a struct or enum by rule and symbol names.
+///
+/// ```ignore
+/// #[derive(FromTree)]
+/// #[abackus(rule = "comp_grain")]
+/// struct CompGrain { n: u32, grain: Grain }
+///
+/// #[derive(FromTree)]
+/// enum Grain { Day, Week, #[abackus(lexeme = "mo")] Month }
+///
+/// let trees = parser("3 days".split_whitespace())?;
+/// let grain = CompGrain::from_tree(&trees[0])?;
+/// ```
+///
+/// - `#[abackus(rule = "head")]` on a type checks the tree derives `head`,
+///   looking through single child rules that wrap it.
+/// - Named fields are built from the children (or `@tags`) with the same
+///   name, `#[abackus(symbol = "x")]` renames them. Children inside groups,
+///   optionals and repetitions are found too. `Option` fields may be
+///   missing and `Vec` fields collect all matches or flatten a list.
+/// - Tuple fields are built from the children that aren't literals. Single
+///   field tuples without a `rule` are transparent wrappers of the tree.
+/// - Enum variants are tried in order: unit variants match a token
+///   (`lexeme`, defaults to the snake_case name), single field variants a
+///   child `symbol` (defaults to the snake_case name) and any variant can
+///   match a rule head or full label with `rule = "head -> a b"`.
+#[proc_macro_derive(FromTree, attributes(abackus))]
+pub fn derive_from_tree(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_tree::derive(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
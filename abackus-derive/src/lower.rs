@@ -0,0 +1,107 @@
+#![deny(warnings)]
+
+use abackus::{statements, ParserBuilder};
+use earlgrey::GrammarBuilder;
+
+/// A grammar lowered into the symbols and rules that
+/// `ParserBuilder::parse_grammar` registers on a `GrammarBuilder`.
+#[derive(Debug, Default)]
+pub struct Lowered {
+    // non-terminals: rule heads and plug points (names without rules)
+    pub nonterms: Vec<String>,
+    // quoted literals, they match only their own lexeme
+    pub literals: Vec<String>,
+    pub rules: Vec<(String, Vec<String>)>,
+}
+
+fn push_unique(v: &mut Vec<String>, name: &str) {
+    if !v.iter().any(|n| n == name) {
+        v.push(name.to_string());
+    }
+}
+
+pub fn lower(start: &str, grammar: &str) -> Result<Lowered, String> {
+    let grammar = ParserBuilder::parse_grammar(GrammarBuilder::default(), grammar)
+        .map_err(|e| locate_error(grammar).unwrap_or(e))?
+        .into_grammar(start)?;
+    if !grammar.rules.iter().any(|rule| rule.head == start) {
+        return Err(format!("Start symbol '{}' has no rules", start));
+    }
+    let mut lowered = Lowered::default();
+    for rule in &grammar.rules {
+        push_unique(&mut lowered.nonterms, &rule.head);
+        for symbol in &rule.spec {
+            match symbol.terminal() {
+                Some((name, _)) => push_unique(&mut lowered.literals, name),
+                None => push_unique(&mut lowered.nonterms, symbol.name()),
+            }
+        }
+        let spec = rule.spec.iter().map(|s| s.name().to_string()).collect();
+        lowered.rules.push((rule.head.clone(), spec));
+    }
+    Ok(lowered)
+}
+
+// Parse statements one by one to find the first broken rule
+fn locate_error(grammar: &str) -> Option<String> {
+    statements(grammar).into_iter().find_map(|(offset, stmt)| {
+        ParserBuilder::parse_grammar(GrammarBuilder::default(), stmt).err().map(|e| {
+            let line = grammar[..offset].matches('\n').count() + 1;
+            format!("line {}: {}: `{}`", line, e, stmt)
+        })
+    })
+}
+
+/// Name of the enum variant for the n-th alternative of `head`.
+/// eg: "day_ordinal" -> DayOrdinal0, "@opt_the" -> TagOptThe0, "<Uniq-3>" -> Uniq3_0
+pub fn variant_name(head: &str, n: usize) -> String {
+    let mut name = String::new();
+    if head.starts_with('@') {
+        name.push_str("Tag");
+    }
+    for word in head.split(|c: char| !c.is_alphanumeric()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.extend(chars);
+        }
+    }
+    if name.ends_with(|c: char| c.is_ascii_digit()) {
+        name.push('_');
+    }
+    format!("{}{}", name, n)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{lower, variant_name};
+
+    #[test]
+    fn lowering() {
+        let l = lower("expr", "expr := expr '+' num | num ;").unwrap();
+        assert_eq!(l.nonterms, vec!["expr", "num"]);
+        assert_eq!(l.literals, vec!["+"]);
+        assert_eq!(l.rules, vec![
+            ("expr".to_string(), vec!["expr".to_string(), "+".to_string(), "num".to_string()]),
+            ("expr".to_string(), vec!["num".to_string()]),
+        ]);
+    }
+
+    #[test]
+    fn lowering_errors() {
+        let err = lower("a", "a := 'x' ;\n\nb := := c ;").unwrap_err();
+        assert!(err.starts_with("line 3: "), "{}", err);
+        assert_eq!(lower("b", "a := b ;").unwrap_err(), "Start symbol 'b' has no rules");
+        assert_eq!(lower("c", "a := b ;").unwrap_err(), "Missing Symbol: c");
+    }
+
+    #[test]
+    fn variant_names() {
+        assert_eq!(variant_name("expr", 0), "Expr0");
+        assert_eq!(variant_name("day_ordinal", 2), "DayOrdinal2");
+        assert_eq!(variant_name("@opt_the", 0), "TagOptThe0");
+        assert_eq!(variant_name("<Uniq-3>", 1), "Uniq3_1");
+    }
+}
@@ -0,0 +1,58 @@
+use abackus::ParserBuilder;
+use earlgrey::EarleyForest;
+
+abackus_derive::ebnf!(Arith, "expr", r#"
+    expr := expr ('+' | '-') term | term ;
+    term := num | '(' expr ')' ;
+"#);
+
+fn parser() -> earlgrey::EarleyParser {
+    Arith::into_parser(ParserBuilder::default()
+        .plug_terminal("num", |n| n.parse::<f64>().is_ok()))
+        .unwrap()
+}
+
+#[test]
+fn rule_labels() {
+    assert_eq!(Arith::START, "expr");
+    assert_eq!(Arith::Expr0.rule(), "expr -> expr <Uniq-3> term");
+    assert_eq!(Arith::Term1.rule(), "term -> ( expr )");
+    assert_eq!(Arith::from_rule("expr -> term"), Some(Arith::Expr1));
+    assert_eq!(Arith::from_rule("expr -> num"), None);
+    for rule in Arith::RULES {
+        assert_eq!(Arith::from_rule(rule.rule()), Some(*rule));
+    }
+}
+
+#[test]
+fn same_rules_as_runtime() {
+    // without plugs symbols are registered in the same order
+    let runtime = ParserBuilder::default()
+        .into_parser("expr", r#"
+            expr := expr ('+' | '-') term | term ;
+            term := num | '(' expr ')' ;
+        "#)
+        .unwrap();
+    let runtime: Vec<_> = runtime.grammar.rules.iter().map(|r| r.to_string()).collect();
+    let compiled: Vec<_> = Arith::RULES.iter().map(|r| r.rule()).collect();
+    assert_eq!(runtime, compiled);
+}
+
+#[test]
+fn exhaustive_actions() {
+    let mut ev = EarleyForest::new(|sym, tok| match sym {
+        "num" => tok.parse::<f64>().unwrap(),
+        _ => if tok == "-" { -1.0 } else { 1.0 },
+    });
+    for rule in Arith::RULES {
+        let rule = *rule;
+        ev.action(rule.rule(), move |n| match rule {
+            Arith::Expr0 => n[0] + n[1] * n[2],
+            Arith::Expr1 | Arith::Term0 | Arith::Uniq3_0 | Arith::Uniq3_1 => n[0],
+            Arith::Term1 => n[1],
+        });
+    }
+    let parser = parser();
+    let tokens = ["3", "-", "(", "1", "+", "4", ")"];
+    assert_eq!(ev.eval(&parser.parse(tokens.iter()).unwrap()), Ok(-2.0));
+}
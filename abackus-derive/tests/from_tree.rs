@@ -0,0 +1,101 @@
+use abackus::{FromTree, ParserBuilder, Tree};
+use abackus_derive::FromTree;
+
+const TIMESPAN: &str = r#"
+    timespan := comp_grain { 'and' comp_grain } ;
+    comp_grain := [n] grain ['ago'] @ago ;
+    grain := 'day' | 'week' | named ;
+"#;
+
+#[derive(FromTree, Debug, PartialEq)]
+#[abackus(rule = "timespan")]
+struct Timespan {
+    #[abackus(symbol = "comp_grain")]
+    parts: Vec<CompGrain>,
+}
+
+#[derive(FromTree, Debug, PartialEq)]
+#[abackus(rule = "comp_grain")]
+struct CompGrain {
+    n: Option<u32>,
+    grain: Grain,
+    ago: Option<String>,
+}
+
+#[derive(FromTree, Debug, PartialEq)]
+#[abackus(rule = "grain")]
+enum Grain {
+    Day,
+    #[abackus(lexeme = "week")]
+    Wk,
+    Named(String),
+}
+
+fn parse(input: &str) -> Vec<Tree> {
+    let parser = ParserBuilder::default()
+        .plug_terminal("n", |n| n.chars().all(|c| c.is_ascii_digit()))
+        .plug_terminal("named", |n| n == "fortnight" || n == "month")
+        .treeficator(TIMESPAN, "timespan");
+    parser(input.split_whitespace()).unwrap()
+}
+
+#[test]
+fn structs_and_enums() {
+    let trees = parse("3 day and week ago and 2 fortnight");
+    assert_eq!(trees.len(), 1);
+    assert_eq!(Timespan::from_tree(&trees[0]).unwrap(), Timespan { parts: vec![
+        CompGrain { n: Some(3), grain: Grain::Day, ago: None },
+        CompGrain { n: None, grain: Grain::Wk, ago: Some("ago".to_string()) },
+        CompGrain { n: Some(2), grain: Grain::Named("fortnight".to_string()), ago: None },
+    ]});
+}
+
+#[test]
+fn shape_errors() {
+    let trees = parse("3 day and week");
+    let err = CompGrain::from_tree(&trees[0]).unwrap_err();
+    assert!(err.starts_with("CompGrain: expected 'comp_grain' but found 'timespan -> "), "{}", err);
+
+    let trees = parse("99999999999 day");
+    assert_eq!(Timespan::from_tree(&trees[0]).unwrap_err(),
+               "Timespan.parts: [0]: CompGrain.n: can't read '99999999999' as u32: \
+                number too large to fit in target type");
+
+    #[derive(FromTree, Debug)]
+    #[allow(dead_code)]
+    enum Unit { Second, Minute }
+    let trees = parse("week");
+    assert_eq!(Unit::from_tree(&trees[0]).unwrap_err(),
+               "Unit: no variant matches 'timespan -> comp_grain <Uniq-5>'");
+}
+
+#[derive(FromTree, Debug, PartialEq)]
+enum Expr {
+    #[abackus(rule = "expr -> expr + term")]
+    Add { expr: Box<Expr>, term: Box<Expr> },
+    #[abackus(rule = "term -> ( expr )")]
+    Paren(Box<Expr>),
+    #[abackus(rule = "term")]
+    Num(f64),
+}
+
+#[derive(FromTree, Debug, PartialEq)]
+#[abackus(rule = "pair")]
+struct Pair(u32, Expr);
+
+#[test]
+fn rule_variants_and_tuples() {
+    let parser = ParserBuilder::default()
+        .plug_terminal("num", |n| n.parse::<f64>().is_ok())
+        .treeficator(r#"
+            pair := num ',' expr ;
+            expr := expr '+' term | term ;
+            term := num | '(' expr ')' ;
+        "#, "pair");
+    let trees = parser("1 , 2 + ( 3 + 4 )".split_whitespace()).unwrap();
+    let num = |n| Box::new(Expr::Num(n));
+    assert_eq!(Pair::from_tree(&trees[0]).unwrap(), Pair(1, Expr::Add {
+        expr: num(2.0),
+        term: Box::new(Expr::Paren(Box::new(Expr::Add { expr: num(3.0), term: num(4.0) }))),
+    }));
+}
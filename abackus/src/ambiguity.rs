@@ -0,0 +1,111 @@
+#![deny(warnings)]
+
+use crate::treeficator::Tree;
+use std::fmt;
+
+/// Smallest span of tokens where some parse trees disagree,
+/// with the competing ways of deriving it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    pub start: usize,
+    pub end: usize,
+    // rule at the root of each derivation, followed by how it splits the
+    // span when the same rule is used by several derivations
+    pub alternatives: Vec<String>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let alternatives: Vec<_> = self.alternatives.iter()
+            .map(|a| format!("`{}`", a))
+            .collect();
+        write!(f, "span tokens {}..{}: {}", self.start, self.end, alternatives.join(" vs "))
+    }
+}
+
+// Number of tokens a tree spans
+fn width(tree: &Tree) -> usize {
+    match tree {
+        Tree::Leaf(..) => 1,
+        Tree::Node(_, subtrees) => subtrees.iter().map(width).sum(),
+    }
+}
+
+fn rule(tree: &Tree) -> &str {
+    match tree {
+        Tree::Leaf(name, _) => name,
+        Tree::Node(rule, _) => rule,
+    }
+}
+
+// Token count covered by each child, these tell nestings of a rule apart
+fn split(tree: &Tree) -> Vec<usize> {
+    match tree {
+        Tree::Leaf(..) => Vec::new(),
+        Tree::Node(_, subtrees) => subtrees.iter().map(width).collect(),
+    }
+}
+
+fn diverge(trees: &[&Tree], start: usize, out: &mut Vec<Divergence>) {
+    let mut shapes: Vec<(&str, Vec<usize>)> = Vec::new();
+    for tree in trees {
+        let shape = (rule(tree), split(tree));
+        if !shapes.contains(&shape) {
+            shapes.push(shape);
+        }
+    }
+    if shapes.len() > 1 {
+        // show the split only for rules that appear with different ones
+        let alternatives = shapes.iter().map(|(rule, split)| {
+            match shapes.iter().filter(|(r, _)| r == rule).count() {
+                1 => rule.to_string(),
+                _ => {
+                    let mut offset = start;
+                    let spans: Vec<_> = split.iter().map(|w| {
+                        offset += w;
+                        format!("{}..{}", offset - w, offset)
+                    }).collect();
+                    format!("{} ({})", rule, spans.join(" "))
+                },
+            }
+        }).collect();
+        let end = start + trees.first().map_or(0, |t| width(t));
+        out.push(Divergence { start, end, alternatives });
+        return;
+    }
+    // same rule and split on every tree, look for the children that differ
+    let mut offset = start;
+    for (idx, w) in shapes.remove(0).1.into_iter().enumerate() {
+        let mut children: Vec<&Tree> = Vec::new();
+        for tree in trees {
+            if let Tree::Node(_, subtrees) = tree {
+                if !children.contains(&&subtrees[idx]) {
+                    children.push(&subtrees[idx]);
+                }
+            }
+        }
+        if children.len() > 1 {
+            diverge(&children, offset, out);
+        }
+        offset += w;
+    }
+}
+
+impl Tree {
+    // Smallest spans where the trees (parses of the same input) diverge.
+    // Independent ambiguities in different parts of the input are
+    // reported separately, sorted by position.
+    pub fn divergences(trees: &[Tree]) -> Vec<Divergence> {
+        let mut unique: Vec<&Tree> = Vec::new();
+        for tree in trees {
+            if !unique.contains(&tree) {
+                unique.push(tree);
+            }
+        }
+        let mut out = Vec::new();
+        if unique.len() > 1 {
+            diverge(&unique, 0, &mut out);
+        }
+        out
+    }
+}
@@ -0,0 +1,75 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use crate::ambiguity::Divergence;
+use crate::treeficator::Tree;
+
+#[test]
+fn unambiguous() {
+    let parser = ParserBuilder::default()
+        .plug_terminal("num", |n| n.parse::<u32>().is_ok())
+        .treeficator("expr := expr '+' num | num ;", "expr");
+    let trees = parser("1 + 2 + 3".split_whitespace()).unwrap();
+    assert_eq!(Tree::divergences(&trees), Vec::new());
+}
+
+#[test]
+fn competing_rules() {
+    let g = r#"
+        date := 'on' when ;
+        when := comp_seq | named_seq ;
+        comp_seq := sequence ;
+        named_seq := month day_ordinal ;
+        sequence := month num ;
+        day_ordinal := num ;
+    "#;
+    let parser = ParserBuilder::default()
+        .plug_terminal("month", |m| m == "may")
+        .plug_terminal("num", |n| n.parse::<u32>().is_ok())
+        .treeficator(g, "date");
+    let trees = parser("on may 4".split_whitespace()).unwrap();
+    let divergences = Tree::divergences(&trees);
+    assert_eq!(divergences.len(), 1);
+    let mut alternatives = divergences[0].alternatives.clone();
+    alternatives.sort();
+    assert_eq!(Divergence { alternatives, ..divergences[0].clone() }, Divergence {
+        start: 1, end: 3,
+        alternatives: vec!["when -> comp_seq".to_string(), "when -> named_seq".to_string()],
+    });
+}
+
+#[test]
+fn nesting() {
+    let parser = ParserBuilder::default()
+        .plug_terminal("num", |n| n.parse::<u32>().is_ok())
+        .treeficator("s := 'x' expr ; expr := expr '+' expr | num ;", "s");
+    let trees = parser("x 1 + 2 + 3".split_whitespace()).unwrap();
+    let divergences = Tree::divergences(&trees);
+    assert_eq!(divergences.len(), 1);
+    let mut alternatives = divergences[0].alternatives.clone();
+    alternatives.sort();
+    assert_eq!(alternatives, vec![
+        "expr -> expr + expr (1..2 2..3 3..6)",
+        "expr -> expr + expr (1..4 4..5 5..6)",
+    ]);
+    assert_eq!((divergences[0].start, divergences[0].end), (1, 6));
+}
+
+#[test]
+fn independent_ambiguities() {
+    let g = r#"
+        s := pair ',' pair ;
+        pair := a | b ;
+        a := 'x' ;
+        b := 'x' ;
+    "#;
+    let parser = ParserBuilder::default().treeficator(g, "s");
+    let trees = parser(["x", ",", "x"].iter()).unwrap();
+    assert_eq!(trees.len(), 4);
+    let divergences = Tree::divergences(&trees);
+    let spans: Vec<_> = divergences.iter().map(|d| (d.start, d.end)).collect();
+    assert_eq!(spans, vec![(0, 1), (2, 3)]);
+    let text = divergences[1].to_string();
+    assert!(text == "span tokens 2..3: `pair -> a` vs `pair -> b`"
+            || text == "span tokens 2..3: `pair -> b` vs `pair -> a`", "{}", text);
+}
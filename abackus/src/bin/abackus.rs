@@ -0,0 +1,238 @@
+#![deny(warnings)]
+
+use abackus::{Dialect, GrammarTests, GrammarTokenizer, Lint, ParserBuilder, Tree};
+use std::io::{self, BufRead};
+use std::path::Path;
+
+const USAGE: &str = "\
+usage: abackus check <grammar.ebnf> <start> [-t terminal]... [-s samples]
+       abackus fmt <grammar.ebnf>
+       abackus parse <grammar.ebnf> <start> [-t terminal[=lexeme,...]]... [input]
+       abackus explain <grammar.ebnf> <start> [-t terminal[=lexeme,...]]... [input]
+       abackus test <file.tests> [start] [-t terminal[=lexeme,...]]...
+       abackus gen <grammar.ebnf> <start> [-t terminal=lexeme,...]... [-n count | -a length]
+
+  -d  notation of the grammar for parse, explain, test and gen: abackus (default),
+      iso, w3c or abnf
+  -t  name to plug as a terminal, matching any token or only the listed lexemes
+  -s  file with sample inputs (one per line) checked for ambiguities
+  -n  number of random sentences to generate (default 10)
+  -a  generate all sentences up to this number of tokens
+  inputs are split using the grammar's literals and terminals,
+  they're read from stdin if not provided";
+
+struct Args {
+    grammar: String,
+    start: String,
+    terminals: Vec<(String, Option<Vec<String>>)>,
+    dialect: Dialect,
+    samples: Option<String>,
+    count: usize,
+    all: Option<usize>,
+    input: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item=String>) -> Result<Args, String> {
+    let mut positional = Vec::new();
+    let mut terminals = Vec::new();
+    let mut dialect = Dialect::default();
+    let mut samples = None;
+    let mut count = 10;
+    let mut all = None;
+    let number = |n: Option<String>| n.and_then(|n| n.parse().ok())
+        .ok_or("expected a number");
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-t" => {
+                let t = args.next().ok_or("missing terminal after -t")?;
+                terminals.push(match t.split_once('=') {
+                    Some((name, lexemes)) => (name.to_string(), Some(
+                        lexemes.split(',').map(|l| l.to_string()).collect())),
+                    None => (t, None),
+                });
+            },
+            "-d" => dialect = args.next().ok_or("missing dialect after -d")?.parse()?,
+            "-s" => samples = Some(args.next().ok_or("missing file after -s")?),
+            "-n" => count = number(args.next())?,
+            "-a" => all = Some(number(args.next())?),
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    Ok(Args {
+        grammar: positional.next().ok_or("missing grammar file")?,
+        start: positional.next().unwrap_or_default(),
+        terminals,
+        dialect,
+        samples,
+        count,
+        all,
+        input: positional.collect(),
+    })
+}
+
+// Load a grammar file resolving imports relative to its directory,
+// other dialects don't have imports
+fn load(file: &str, dialect: Dialect) -> Result<String, String> {
+    if dialect != Dialect::Abackus {
+        return std::fs::read_to_string(file).map_err(|e| format!("{}: {}", file, e));
+    }
+    let path = Path::new(file);
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    let name = path.file_name().ok_or(format!("bad grammar file {}", file))?;
+    ParserBuilder::load_grammar(base, &name.to_string_lossy())
+}
+
+fn builder(args: &Args) -> ParserBuilder {
+    let pb = ParserBuilder::default().dialect(args.dialect);
+    args.terminals.iter().fold(pb, |pb, (name, lexemes)| {
+        let lexemes = lexemes.clone();
+        pb.plug_terminal(name.as_str(), move |tok| match &lexemes {
+            Some(lexemes) => lexemes.iter().any(|l| l == tok),
+            None => true,
+        })
+    })
+}
+
+fn tokenizer(args: &Args, grammar: &str) -> Result<GrammarTokenizer, String> {
+    let parser = builder(args).into_parser(&args.start, grammar)?;
+    Ok(GrammarTokenizer::new(&parser.grammar))
+}
+
+fn inputs(args: &Args) -> Result<Vec<String>, String> {
+    if !args.input.is_empty() {
+        return Ok(vec![args.input.join(" ")]);
+    }
+    io::stdin().lock().lines()
+        .map(|l| l.map_err(|e| e.to_string()))
+        .collect()
+}
+
+fn check(args: &Args) -> Result<bool, String> {
+    let grammar = load(&args.grammar, args.dialect)?;
+    let terminals: Vec<_> = args.terminals.iter().map(|(t, _)| t.as_str()).collect();
+    let mut lints = ParserBuilder::check_grammar(&grammar, &args.start, &terminals)?;
+    if let Some(file) = &args.samples {
+        let samples = std::fs::read_to_string(file)
+            .map_err(|e| format!("{}: {}", file, e))?;
+        let tokenizer = tokenizer(args, &grammar)?;
+        let parser = builder(args).treeficator(&grammar, &args.start);
+        for (lineno, input) in samples.lines().enumerate() {
+            match parser(tokenizer.tokenize(input)) {
+                Ok(trees) => lints.extend(Lint::ambiguity(input, &trees)),
+                Err(e) => eprintln!("{}:{}: {}", file, lineno + 1, e),
+            }
+        }
+    }
+    for lint in &lints {
+        println!("{}: {}", args.grammar, lint);
+    }
+    Ok(lints.is_empty())
+}
+
+fn parse(args: &Args) -> Result<bool, String> {
+    let grammar = load(&args.grammar, args.dialect)?;
+    let inputs = inputs(args)?;
+    let tokenizer = tokenizer(args, &grammar)?;
+    let parser = builder(args).sexprificator(&grammar, &args.start);
+    let mut ok = true;
+    for input in &inputs {
+        match parser(tokenizer.tokenize(input)) {
+            Ok(trees) => for t in trees { println!("{}", t.print()); },
+            Err(e) => { ok = false; println!("{}: {}", input, e) },
+        }
+    }
+    Ok(ok)
+}
+
+// Show where the parses of ambiguous inputs diverge
+fn explain(args: &Args) -> Result<bool, String> {
+    let grammar = load(&args.grammar, args.dialect)?;
+    let inputs = inputs(args)?;
+    let tokenizer = tokenizer(args, &grammar)?;
+    let tokenized: Vec<Vec<_>> = inputs.iter()
+        .map(|input| tokenizer.tokenize(input).collect())
+        .collect();
+    let parser = builder(args).treeficator(&grammar, &args.start);
+    let mut ok = true;
+    for (input, tokens) in inputs.iter().zip(&tokenized) {
+        match parser(tokens.iter()) {
+            Ok(trees) => for d in Tree::divergences(&trees) {
+                ok = false;
+                println!("{}: {} over '{}'", input, d, tokens[d.start..d.end].join(" "));
+            },
+            Err(e) => { ok = false; println!("{}: {}", input, e) },
+        }
+    }
+    Ok(ok)
+}
+
+// Run a grammar test file, its grammar is relative to the file
+fn test(args: &Args) -> Result<bool, String> {
+    let text = std::fs::read_to_string(&args.grammar)
+        .map_err(|e| format!("{}: {}", args.grammar, e))?;
+    let tests = GrammarTests::parse(&text).map_err(|e| format!("{}: {}", args.grammar, e))?;
+    let start = match (&tests.start, args.start.is_empty()) {
+        (_, false) => &args.start,
+        (Some(start), true) => start,
+        (None, true) => return Err(format!("{}: missing start symbol", args.grammar)),
+    };
+    let file = tests.grammar.as_ref()
+        .ok_or(format!("{}: missing 'grammar:' header", args.grammar))?;
+    let base = Path::new(&args.grammar).parent().unwrap_or_else(|| Path::new(""));
+    let grammar = load(&base.join(file).to_string_lossy(), args.dialect)?;
+    let report = builder(args).run_tests(&grammar, start, &text)?;
+    println!("{}", report);
+    Ok(report.is_ok())
+}
+
+fn generate(args: &Args) -> Result<bool, String> {
+    let grammar = load(&args.grammar, args.dialect)?;
+    let generator = builder(args).into_generator(&args.start, &grammar)?;
+    let mut generator = args.terminals.iter()
+        .fold(generator, |g, (name, lexemes)| match lexemes {
+            Some(lexemes) => g.samples(name, lexemes),
+            None => g,
+        });
+    let sentences = match args.all {
+        Some(length) => generator.enumerate(length)?,
+        None => (0..args.count).map(|_| generator.generate())
+            .collect::<Result<_, _>>()?,
+    };
+    for sentence in sentences {
+        println!("{}", sentence.join(" "));
+    }
+    Ok(true)
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let cmd = args.next().unwrap_or_default();
+    let result = parse_args(args)
+        .map_err(|e| format!("{}\n{}", e, USAGE))
+        .and_then(|args| match cmd.as_str() {
+        // lints and formatting only know abackus' own notation
+        "check" | "fmt" if args.dialect != Dialect::Abackus =>
+            Err(format!("-d isn't supported by {}\n{}", cmd, USAGE)),
+        "check" if !args.start.is_empty() => check(&args),
+        "parse" if !args.start.is_empty() => parse(&args),
+        "explain" if !args.start.is_empty() => explain(&args),
+        "test" => test(&args),
+        "gen" if !args.start.is_empty() => generate(&args),
+        "fmt" => {
+            let grammar = std::fs::read_to_string(&args.grammar)
+                .map_err(|e| format!("{}: {}", args.grammar, e))?;
+            print!("{}", ParserBuilder::format_grammar(&grammar)?);
+            Ok(true)
+        },
+        _ => Err(USAGE.to_string()),
+    });
+    match result {
+        Ok(true) => (),
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        },
+    }
+}
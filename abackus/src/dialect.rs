@@ -0,0 +1,666 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use earlgrey::GrammarBuilder;
+use std::str::FromStr;
+
+/// Notations a grammar can be written in. They all lower to the same rules,
+/// literals are matched as whole tokens like in abackus' own notation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dialect {
+    // `rule := a [b] {c} ('d' | e) ;` with '#' comments and @tags
+    #[default]
+    Abackus,
+    // ISO/IEC 14977: `rule = a, [b], {c}, 3 * d ;` with (* comments *)
+    Iso14977,
+    // W3C/XML: `Rule ::= a b? c* d+ [a-z] #x20` with /* comments */
+    W3c,
+    // RFC 5234: `rule = a [b] *c 2d %x41-5A` with ';' comments
+    Abnf,
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "abackus" | "ebnf" => Ok(Dialect::Abackus),
+            "iso" | "iso14977" => Ok(Dialect::Iso14977),
+            "w3c" | "xml" => Ok(Dialect::W3c),
+            "abnf" => Ok(Dialect::Abnf),
+            _ => Err(format!("Unknown grammar dialect: {}", s)),
+        }
+    }
+}
+
+// Single character terminal (eg: [a-z], [^"\], %x41-5A)
+#[derive(Clone, Debug, PartialEq)]
+struct Chars {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl Chars {
+    // Name of the terminal in W3C notation
+    fn name(&self) -> String {
+        let show = |c: char| match c.is_ascii_graphic() && !"[]^-#".contains(c) {
+            true => c.to_string(),
+            false => format!("#x{:X}", c as u32),
+        };
+        let ranges: String = self.ranges.iter().map(|&(lo, hi)| match lo == hi {
+            true => show(lo),
+            false => format!("{}-{}", show(lo), show(hi)),
+        }).collect();
+        format!("[{}{}]", if self.negated { "^" } else { "" }, ranges)
+    }
+
+    fn matches(&self, c: char) -> bool {
+        self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != self.negated
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    Name(String),
+    Define,
+    // text and whether it's case sensitive
+    Str(String, bool),
+    Chars(Chars),
+    Num(usize),
+    Op(char),
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Name(String),
+    Str(String, bool),
+    Chars(Chars),
+    Seq(Vec<Expr>),
+    Alt(Vec<Expr>),
+    // min and max (if bounded) number of repetitions
+    Repeat(usize, Option<usize>, Box<Expr>),
+}
+
+struct Lexer {
+    dialect: Dialect,
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Lexer {
+    fn peek(&self, ahead: usize) -> Option<char> {
+        self.chars.get(self.pos + ahead).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek(0)?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error<T>(&self, msg: &str) -> Result<T, String> {
+        Err(format!("line {}: {}", self.line, msg))
+    }
+
+    // Skip input until (and including) `end`
+    fn skip_past(&mut self, end: &str) -> Result<(), String> {
+        let line = self.line;
+        let end: Vec<char> = end.chars().collect();
+        while self.peek(0).is_some() {
+            if self.chars[self.pos..].starts_with(&end) {
+                self.pos += end.len();
+                return Ok(());
+            }
+            self.bump();
+        }
+        Err(format!("line {}: unterminated comment", line))
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), String> {
+        loop {
+            match (self.dialect, self.peek(0), self.peek(1)) {
+                (_, Some(c), _) if c.is_whitespace() => { self.bump(); },
+                (Dialect::W3c, Some('/'), Some('*')) => self.skip_past("*/")?,
+                (Dialect::Iso14977, Some('('), Some('*')) => self.skip_past("*)")?,
+                (Dialect::Abnf, Some(';'), _) => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                },
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn name_char(&self, ahead: usize) -> bool {
+        self.peek(ahead).is_some_and(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    // Names may have inner hyphens (ABNF, W3C) or spaces (ISO 14977 meta
+    // identifiers, which are joined with '_')
+    fn name(&mut self) -> String {
+        let mut name = String::new();
+        loop {
+            while self.name_char(0) {
+                name.push(self.bump().unwrap());
+            }
+            let gap = match self.dialect {
+                Dialect::Iso14977 => self.chars[self.pos..].iter()
+                    .take_while(|c| **c == ' ' || **c == '\t').count(),
+                _ if self.peek(0) == Some('-') => 1,
+                _ => 0,
+            };
+            if gap == 0 || !self.name_char(gap) {
+                return name;
+            }
+            let sep = self.peek(0).filter(|c| *c == '-').unwrap_or('_');
+            name.push(sep);
+            self.pos += gap;
+        }
+    }
+
+    fn number(&mut self, radix: u32) -> Result<u32, String> {
+        let mut digits = String::new();
+        while self.peek(0).is_some_and(|c| c.is_digit(radix)) {
+            digits.push(self.bump().unwrap());
+        }
+        u32::from_str_radix(&digits, radix).or_else(|_| self.error("expected a number"))
+    }
+
+    fn char_code(&mut self, radix: u32) -> Result<char, String> {
+        let code = self.number(radix)?;
+        char::from_u32(code).map_or_else(|| self.error("bad character code"), Ok)
+    }
+
+    fn quoted(&mut self, quote: char, sensitive: bool) -> Result<Tok, String> {
+        let line = self.line;
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                Some(c) if c == quote => return Ok(Tok::Str(text, sensitive)),
+                Some(c) => text.push(c),
+                None => return Err(format!("line {}: unterminated string", line)),
+            }
+        }
+    }
+
+    // ABNF numeric values: %x41, %x41-5A or %x0D.0A
+    fn num_val(&mut self) -> Result<Tok, String> {
+        let radix = match self.bump().map(|c| c.to_ascii_lowercase()) {
+            Some('x') => 16,
+            Some('d') => 10,
+            Some('b') => 2,
+            Some('s') if self.peek(0) == Some('"') => {
+                self.bump();
+                return self.quoted('"', true);
+            },
+            Some('i') if self.peek(0) == Some('"') => {
+                self.bump();
+                return self.quoted('"', false);
+            },
+            _ => return self.error("bad numeric value"),
+        };
+        let first = self.char_code(radix)?;
+        if self.peek(0) == Some('-') {
+            self.bump();
+            let last = self.char_code(radix)?;
+            return Ok(Tok::Chars(Chars { ranges: vec![(first, last)], negated: false }));
+        }
+        let mut text = first.to_string();
+        while self.peek(0) == Some('.') {
+            self.bump();
+            text.push(self.char_code(radix)?);
+        }
+        Ok(Tok::Str(text, true))
+    }
+
+    // W3C character class: [a-zA-Z], [^"\] or [#x20-#x7E]
+    fn char_class(&mut self) -> Result<Tok, String> {
+        let line = self.line;
+        let negated = self.peek(0) == Some('^');
+        if negated {
+            self.bump();
+        }
+        let mut ranges = Vec::new();
+        loop {
+            let lo = match self.bump() {
+                Some(']') if !ranges.is_empty() => break,
+                Some('#') if self.peek(0) == Some('x') => { self.bump(); self.char_code(16)? },
+                Some(c) => c,
+                None => return Err(format!("line {}: unterminated character class", line)),
+            };
+            let hi = match (self.peek(0), self.peek(1)) {
+                (Some('-'), Some(c)) if c != ']' => {
+                    self.bump();
+                    match self.bump() {
+                        Some('#') if self.peek(0) == Some('x') => {
+                            self.bump();
+                            self.char_code(16)?
+                        },
+                        Some(c) => c,
+                        None => unreachable!(),
+                    }
+                },
+                _ => lo,
+            };
+            ranges.push((lo, hi));
+        }
+        Ok(Tok::Chars(Chars { ranges, negated }))
+    }
+
+    fn next_token(&mut self) -> Result<Option<Tok>, String> {
+        self.skip_trivia()?;
+        let c = match self.peek(0) {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        if c.is_alphabetic() || c == '_' {
+            return Ok(Some(Tok::Name(self.name())));
+        }
+        if c.is_ascii_digit() {
+            return Ok(Some(Tok::Num(self.number(10)? as usize)));
+        }
+        self.bump();
+        let tok = match (self.dialect, c, self.peek(0)) {
+            (Dialect::Abnf, '"', _) => self.quoted(c, false)?,
+            (_, '"' | '\'', _) => self.quoted(c, true)?,
+            (Dialect::Abnf, '%', _) => self.num_val()?,
+            (Dialect::Abnf, '=', Some('/')) => { self.bump(); Tok::Define },
+            (Dialect::Abnf | Dialect::Iso14977, '=', _) => Tok::Define,
+            (Dialect::Abnf, '<', _) => return self.error("prose values aren't supported"),
+            (Dialect::Abnf, '/' | '*' | '(' | ')' | '[' | ']', _) => Tok::Op(c),
+            (Dialect::W3c, ':', Some(':')) if self.peek(1) == Some('=') => {
+                self.pos += 2;
+                Tok::Define
+            },
+            (Dialect::W3c, '#', Some('x')) => {
+                self.bump();
+                Tok::Str(self.char_code(16)?.to_string(), true)
+            },
+            (Dialect::W3c, '[', _) => self.char_class()?,
+            (Dialect::W3c, '|' | '?' | '*' | '+' | '(' | ')' | '-', _) => Tok::Op(c),
+            (Dialect::Iso14977, '(', Some('/')) => { self.bump(); Tok::Op('[') },
+            (Dialect::Iso14977, '/', Some(')')) => { self.bump(); Tok::Op(']') },
+            (Dialect::Iso14977, '(', Some(':')) => { self.bump(); Tok::Op('{') },
+            (Dialect::Iso14977, ':', Some(')')) => { self.bump(); Tok::Op('}') },
+            (Dialect::Iso14977, '?', _) => return self.error("special sequences aren't supported"),
+            (Dialect::Iso14977, '|' | '/' | '!', _) => Tok::Op('|'),
+            (Dialect::Iso14977, ';' | '.', _) => Tok::Op(';'),
+            (Dialect::Iso14977, ',' | '*' | '-' | '(' | ')' | '[' | ']' | '{' | '}', _) =>
+                Tok::Op(c),
+            _ => return self.error(&format!("unexpected '{}'", c)),
+        };
+        Ok(Some(tok))
+    }
+}
+
+fn tokens(dialect: Dialect, grammar: &str) -> Result<Vec<(usize, Tok)>, String> {
+    let mut lexer = Lexer { dialect, chars: grammar.chars().collect(), pos: 0, line: 1 };
+    let mut tokens = Vec::new();
+    loop {
+        lexer.skip_trivia()?;
+        let line = lexer.line;
+        match lexer.next_token()? {
+            Some(tok) => tokens.push((line, tok)),
+            None => return Ok(tokens),
+        }
+    }
+}
+
+struct Parser {
+    dialect: Dialect,
+    tokens: Vec<(usize, Tok)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self, ahead: usize) -> Option<&Tok> {
+        self.tokens.get(self.pos + ahead).map(|(_, t)| t)
+    }
+
+    fn error<T>(&self, msg: &str) -> Result<T, String> {
+        match self.tokens.get(self.pos) {
+            Some((line, tok)) => Err(format!("line {}: {} at {:?}", line, msg, tok)),
+            None => Err(format!("{} at end of grammar", msg)),
+        }
+    }
+
+    fn accept(&mut self, op: char) -> bool {
+        let found = self.peek(0) == Some(&Tok::Op(op));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, op: char) -> Result<(), String> {
+        match self.accept(op) {
+            true => Ok(()),
+            false => self.error(&format!("expected '{}'", op)),
+        }
+    }
+
+    fn rules(&mut self) -> Result<Vec<(String, Expr)>, String> {
+        let mut rules = Vec::new();
+        while let Some(tok) = self.peek(0).cloned() {
+            let head = match (tok, self.peek(1)) {
+                (Tok::Name(head), Some(Tok::Define)) => head,
+                _ => return self.error("expected a rule definition"),
+            };
+            self.pos += 2;
+            rules.push((head, self.alternation()?));
+            if self.dialect == Dialect::Iso14977 {
+                self.expect(';')?;
+            }
+        }
+        Ok(rules)
+    }
+
+    fn alternation(&mut self) -> Result<Expr, String> {
+        let separator = if self.dialect == Dialect::Abnf { '/' } else { '|' };
+        let mut alternatives = vec![self.sequence()?];
+        while self.accept(separator) {
+            alternatives.push(self.sequence()?);
+        }
+        Ok(match alternatives.len() {
+            1 => alternatives.remove(0),
+            _ => Expr::Alt(alternatives),
+        })
+    }
+
+    // Whether the next token begins a term (rule heads end sequences)
+    fn at_term(&self) -> bool {
+        match (self.dialect, self.peek(0)) {
+            (_, Some(Tok::Name(_))) => self.peek(1) != Some(&Tok::Define),
+            (_, Some(Tok::Str(..) | Tok::Chars(_) | Tok::Num(_) | Tok::Op('('))) => true,
+            (Dialect::Abnf, Some(Tok::Op('[' | '*'))) => true,
+            (Dialect::Iso14977, Some(Tok::Op('[' | '{'))) => true,
+            _ => false,
+        }
+    }
+
+    fn sequence(&mut self) -> Result<Expr, String> {
+        let mut terms = Vec::new();
+        if self.dialect == Dialect::Iso14977 {
+            if self.at_term() {
+                terms.push(self.term()?);
+                while self.accept(',') {
+                    terms.push(self.term()?);
+                }
+            }
+        } else {
+            while self.at_term() {
+                terms.push(self.term()?);
+            }
+            if terms.is_empty() {
+                return self.error("expected an expression");
+            }
+        }
+        Ok(match terms.len() {
+            1 => terms.remove(0),
+            _ => Expr::Seq(terms),
+        })
+    }
+
+    fn count(&mut self) -> Option<usize> {
+        match self.peek(0) {
+            Some(&Tok::Num(n)) => { self.pos += 1; Some(n) },
+            _ => None,
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        match self.dialect {
+            Dialect::Abnf => {
+                // repetitions: n, *, n*, *m and n*m
+                let min = self.count();
+                let (min, max) = match self.accept('*') {
+                    true => (min.unwrap_or(0), self.count()),
+                    false if min.is_some() => (min.unwrap(), min),
+                    false => return self.primary(),
+                };
+                Ok(Expr::Repeat(min, max, Box::new(self.primary()?)))
+            },
+            Dialect::Iso14977 => {
+                let term = match (self.peek(0), self.peek(1)) {
+                    (Some(&Tok::Num(n)), Some(Tok::Op('*'))) => {
+                        self.pos += 2;
+                        Expr::Repeat(n, Some(n), Box::new(self.primary()?))
+                    },
+                    _ => self.primary()?,
+                };
+                match self.accept('-') {
+                    true => self.error("exceptions aren't supported"),
+                    false => Ok(term),
+                }
+            },
+            _ => {
+                let mut term = self.primary()?;
+                loop {
+                    term = match self.peek(0) {
+                        Some(Tok::Op('?')) => Expr::Repeat(0, Some(1), Box::new(term)),
+                        Some(Tok::Op('*')) => Expr::Repeat(0, None, Box::new(term)),
+                        Some(Tok::Op('+')) => Expr::Repeat(1, None, Box::new(term)),
+                        Some(Tok::Op('-')) => return self.error("exceptions aren't supported"),
+                        _ => return Ok(term),
+                    };
+                    self.pos += 1;
+                }
+            },
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let tok = match self.peek(0) {
+            Some(tok) => tok.clone(),
+            None => return self.error("expected an expression"),
+        };
+        self.pos += 1;
+        match tok {
+            Tok::Name(name) => Ok(Expr::Name(name)),
+            Tok::Str(text, sensitive) => Ok(Expr::Str(text, sensitive)),
+            Tok::Chars(chars) => Ok(Expr::Chars(chars)),
+            Tok::Op('(') => {
+                let body = self.alternation()?;
+                self.expect(')')?;
+                Ok(body)
+            },
+            Tok::Op('[') if self.dialect != Dialect::W3c => {
+                let body = self.alternation()?;
+                self.expect(']')?;
+                Ok(Expr::Repeat(0, Some(1), Box::new(body)))
+            },
+            Tok::Op('{') if self.dialect == Dialect::Iso14977 => {
+                let body = self.alternation()?;
+                self.expect('}')?;
+                Ok(Expr::Repeat(0, None, Box::new(body)))
+            },
+            _ => {
+                self.pos -= 1;
+                self.error("expected an expression")
+            },
+        }
+    }
+}
+
+// RFC 5234 appendix B.1, added when used but not defined by a grammar
+const ABNF_CORE_RULES: &str = r#"
+    ALPHA = %x41-5A / %x61-7A
+    BIT = "0" / "1"
+    CHAR = %x01-7F
+    CR = %x0D
+    CRLF = CR LF
+    CTL = %x00-1F / %x7F
+    DIGIT = %x30-39
+    DQUOTE = %x22
+    HEXDIG = DIGIT / "A" / "B" / "C" / "D" / "E" / "F"
+    HTAB = %x09
+    LF = %x0A
+    LWSP = *(WSP / CRLF WSP)
+    OCTET = %x00-FF
+    SP = %x20
+    VCHAR = %x21-7E
+    WSP = SP / HTAB
+"#;
+
+fn names<'a>(expr: &'a Expr, out: &mut Vec<&'a str>) {
+    match expr {
+        Expr::Name(name) => out.push(name),
+        Expr::Seq(exprs) | Expr::Alt(exprs) => exprs.iter().for_each(|e| names(e, out)),
+        Expr::Repeat(_, _, e) => names(e, out),
+        Expr::Str(..) | Expr::Chars(_) => (),
+    }
+}
+
+fn with_core_rules(mut rules: Vec<(String, Expr)>) -> Result<Vec<(String, Expr)>, String> {
+    let core = Parser {
+        dialect: Dialect::Abnf,
+        tokens: tokens(Dialect::Abnf, ABNF_CORE_RULES)?,
+        pos: 0,
+    }.rules()?;
+    loop {
+        let mut used = Vec::new();
+        rules.iter().for_each(|(_, expr)| names(expr, &mut used));
+        let missing: Vec<_> = core.iter()
+            .filter(|(head, _)| used.contains(&head.as_str()))
+            .filter(|(head, _)| !rules.iter().any(|(h, _)| h == head))
+            .cloned()
+            .collect();
+        if missing.is_empty() {
+            return Ok(rules);
+        }
+        rules.extend(missing);
+    }
+}
+
+// Adds rules for expressions to the grammar, nested groups and
+// repetitions get auxiliary symbols like in abackus' own notation
+struct Lowering {
+    gb: GrammarBuilder,
+    // rule names, literals spelled the same get a quoted terminal name
+    names: Vec<String>,
+}
+
+impl Lowering {
+    fn aux(&mut self, alternatives: Vec<Vec<String>>) -> String {
+        let aux = self.gb.unique_symbol_name();
+        self.gb.quiet_nonterm(&aux);
+        for rule in alternatives {
+            self.gb.quiet_rule(&aux, &rule);
+        }
+        aux
+    }
+
+    fn alternatives(&mut self, expr: &Expr) -> Vec<Vec<String>> {
+        match expr {
+            Expr::Alt(exprs) => exprs.iter().flat_map(|e| self.alternatives(e)).collect(),
+            _ => vec![self.symbols(expr)],
+        }
+    }
+
+    fn literal(&self, text: &str) -> String {
+        match self.names.iter().any(|n| n == text) {
+            true => format!("\"{}\"", text),
+            false => text.to_string(),
+        }
+    }
+
+    fn symbols(&mut self, expr: &Expr) -> Vec<String> {
+        match expr {
+            Expr::Name(name) => {
+                self.gb.quiet_nonterm(name);
+                vec![name.clone()]
+            },
+            Expr::Str(text, _) if text.is_empty() => Vec::new(),
+            Expr::Str(text, true) => {
+                let (name, lexeme) = (self.literal(text), text.clone());
+                self.gb.quiet_literal(&name, text, move |s| s == lexeme);
+                vec![name]
+            },
+            Expr::Str(text, false) => {
+                let (name, lexeme) = (self.literal(text), text.to_lowercase());
+                self.gb.quiet_literal(&name, text, move |s| s.to_lowercase() == lexeme);
+                vec![name]
+            },
+            Expr::Chars(chars) => {
+                let name = chars.name();
+                let chars = chars.clone();
+                self.gb.quiet_terminal(&name, move |s| {
+                    let mut cs = s.chars();
+                    matches!((cs.next(), cs.next()), (Some(c), None) if chars.matches(c))
+                });
+                vec![name]
+            },
+            Expr::Seq(exprs) => exprs.iter().flat_map(|e| self.symbols(e)).collect(),
+            Expr::Alt(_) => {
+                let alternatives = self.alternatives(expr);
+                vec![self.aux(alternatives)]
+            },
+            Expr::Repeat(min, max, expr) => {
+                let mut symbols: Vec<String> = (0..*min)
+                    .flat_map(|_| self.symbols(expr)).collect();
+                match max {
+                    // aux -> <e> | expr aux ;
+                    None => {
+                        let body = self.alternatives(expr);
+                        let aux = self.gb.unique_symbol_name();
+                        self.gb.quiet_nonterm(&aux);
+                        for mut rule in body {
+                            rule.push(aux.clone());
+                            self.gb.quiet_rule(&aux, &rule);
+                        }
+                        self.gb.quiet_rule::<_, String>(&aux, &[]);
+                        symbols.push(aux);
+                    },
+                    // nested optionals, aux -> <e> | expr [aux'] ;
+                    Some(max) if max > min => {
+                        let mut rest = Vec::new();
+                        for _ in *min..*max {
+                            let mut body = self.alternatives(expr);
+                            body.iter_mut().for_each(|rule| rule.extend(rest.clone()));
+                            body.push(Vec::new());
+                            rest = vec![self.aux(body)];
+                        }
+                        symbols.extend(rest);
+                    },
+                    Some(_) => (),
+                }
+                symbols
+            },
+        }
+    }
+}
+
+impl Dialect {
+    // Parse a grammar in this notation into a builder where we can plug
+    // terminal matchers
+    pub fn parse_grammar(self, gb: GrammarBuilder, grammar: &str)
+            -> Result<GrammarBuilder, String> {
+        if self == Dialect::Abackus {
+            return ParserBuilder::parse_grammar(gb, grammar);
+        }
+        let mut rules = Parser { dialect: self, tokens: tokens(self, grammar)?, pos: 0 }
+            .rules()
+            .map_err(|e| format!("Failed to parse user grammar: {}", e))?;
+        if self == Dialect::Abnf {
+            rules = with_core_rules(rules)?;
+        }
+        let mut used = Vec::new();
+        rules.iter().for_each(|(_, expr)| names(expr, &mut used));
+        let names = rules.iter().map(|(head, _)| head.as_str()).chain(used)
+            .map(|n| n.to_string()).collect();
+        let mut lowering = Lowering { gb, names };
+        for (head, _) in &rules {
+            lowering.gb.quiet_nonterm(head);
+        }
+        for (head, expr) in &rules {
+            for rule in lowering.alternatives(expr) {
+                lowering.gb.quiet_rule(head, &rule);
+            }
+        }
+        Ok(lowering.gb)
+    }
+}
@@ -0,0 +1,241 @@
+#![deny(warnings)]
+
+use crate::dialect::Dialect;
+use crate::ebnf::ParserBuilder;
+
+// JSON from https://www.json.org in W3C notation
+const JSON_W3C: &str = r#"
+/* a JSON text is a value surrounded by optional white space */
+json     ::= ws value ws
+value    ::= object | array | string | number | 'true' | 'false' | 'null'
+object   ::= '{' ws ( member ( ws ',' ws member )* )? ws '}'
+member   ::= string ws ':' ws value
+array    ::= '[' ws ( value ( ws ',' ws value )* )? ws ']'
+string   ::= '"' char* '"'
+char     ::= [^"\#x0-#x1F] | '\' escape
+escape   ::= ["\/bfnrt] | 'u' hex hex hex hex
+hex      ::= [0-9a-fA-F]
+number   ::= '-'? int frac? exp?
+int      ::= '0' | [1-9] [0-9]*
+frac     ::= '.' [0-9]+
+exp      ::= [eE] [-+]? [0-9]+
+ws       ::= [#x20#x9#xA#xD]*
+"#;
+
+// JSON from RFC 8259 (verbatim except for the prose)
+const JSON_ABNF: &str = r#"
+JSON-text = ws value ws
+
+begin-array     = ws %x5B ws  ; [ left square bracket
+begin-object    = ws %x7B ws  ; { left curly bracket
+end-array       = ws %x5D ws  ; ] right square bracket
+end-object      = ws %x7D ws  ; } right curly bracket
+name-separator  = ws %x3A ws  ; : colon
+value-separator = ws %x2C ws  ; , comma
+
+ws = *(
+        %x20 /              ; Space
+        %x09 /              ; Horizontal tab
+        %x0A /              ; Line feed or New line
+        %x0D )              ; Carriage return
+
+value = false / null / true / object / array / number / string
+false = %x66.61.6c.73.65   ; false
+null  = %x6e.75.6c.6c      ; null
+true  = %x74.72.75.65      ; true
+
+object = begin-object [ member *( value-separator member ) ]
+         end-object
+member = string name-separator value
+
+array = begin-array [ value *( value-separator value ) ] end-array
+
+number = [ minus ] int [ frac ] [ exp ]
+decimal-point = %x2E       ; .
+digit1-9 = %x31-39         ; 1-9
+e = %x65 / %x45            ; e E
+exp = e [ minus / plus ] 1*DIGIT
+frac = decimal-point 1*DIGIT
+int = zero / ( digit1-9 *DIGIT )
+minus = %x2D               ; -
+plus = %x2B                ; +
+zero = %x30                ; 0
+
+string = quotation-mark *char quotation-mark
+char = unescaped /
+    escape (
+        %x22 /          ; "    quotation mark  U+0022
+        %x5C /          ; \    reverse solidus U+005C
+        %x2F /          ; /    solidus         U+002F
+        %x62 /          ; b    backspace       U+0008
+        %x66 /          ; f    form feed       U+000C
+        %x6E /          ; n    line feed       U+000A
+        %x72 /          ; r    carriage return U+000D
+        %x74 /          ; t    tab             U+0009
+        %x75 4HEXDIG )  ; uXXXX                U+XXXX
+escape = %x5C              ; \
+quotation-mark = %x22      ; "
+unescaped = %x20-21 / %x23-5B / %x5D-10FFFF
+"#;
+
+const JSON_VALID: &[&str] = &[
+    r#"{"a":[1,-2.5e3,true,null],"b":{}}"#,
+    "[ 1 , 2 ]",
+    "[]",
+    r#""x\né""#,
+    r#"{"k": false, "l": [0.5, 10E+2]}"#,
+];
+
+const JSON_INVALID: &[&str] = &[
+    "[1,]",
+    r#"{"a"}"#,
+    "01",
+    "tru",
+    r#""a"#,
+    "-",
+];
+
+#[test]
+fn json_w3c_and_abnf() {
+    let w3c = ParserBuilder::default()
+        .dialect(Dialect::W3c)
+        .into_str_parser("json", JSON_W3C)
+        .unwrap();
+    let abnf = ParserBuilder::default()
+        .dialect(Dialect::Abnf)
+        .into_str_parser("JSON-text", JSON_ABNF)
+        .unwrap();
+    for input in JSON_VALID {
+        assert!(w3c.parse_str(input).is_ok(), "w3c rejected {}", input);
+        assert!(abnf.parse_str(input).is_ok(), "abnf rejected {}", input);
+    }
+    for input in JSON_INVALID {
+        assert!(w3c.parse_str(input).is_err(), "w3c accepted {}", input);
+        assert!(abnf.parse_str(input).is_err(), "abnf accepted {}", input);
+    }
+}
+
+#[test]
+fn json_unambiguous() {
+    let input: Vec<_> = r#"{"a": [1, 2]}"#.chars().map(|c| c.to_string()).collect();
+    let parser = ParserBuilder::default()
+        .dialect(Dialect::W3c)
+        .treeficator(JSON_W3C, "json");
+    let trees = parser(input.iter()).unwrap();
+    assert_eq!(trees.len(), 1);
+}
+
+// Internet date/time format from RFC 3339
+const DATE_TIME: &str = r#"
+date-fullyear   = 4DIGIT
+date-month      = 2DIGIT  ; 01-12
+date-mday       = 2DIGIT  ; 01-28, 01-29, 01-30, 01-31 based on
+                          ; month/year
+time-hour       = 2DIGIT  ; 00-23
+time-minute     = 2DIGIT  ; 00-59
+time-second     = 2DIGIT  ; 00-58, 00-59, 00-60 based on leap second
+                          ; rules
+time-secfrac    = "." 1*DIGIT
+time-numoffset  = ("+" / "-") time-hour ":" time-minute
+time-offset     = "Z" / time-numoffset
+
+partial-time    = time-hour ":" time-minute ":" time-second
+                  [time-secfrac]
+full-date       = date-fullyear "-" date-month "-" date-mday
+full-time       = partial-time time-offset
+
+date-time       = full-date "T" full-time
+"#;
+
+#[test]
+fn abnf_date_time() {
+    let parser = ParserBuilder::default()
+        .dialect(Dialect::Abnf)
+        .into_parser("date-time", DATE_TIME)
+        .unwrap();
+    let chars = |s: &str| s.chars().map(|c| c.to_string()).collect::<Vec<_>>();
+    for ok in ["1985-04-12T23:20:50.52Z", "1996-12-19t16:39:57-08:00"] {
+        assert!(parser.parse(chars(ok).iter()).is_ok(), "{}", ok);
+    }
+    for bad in ["85-04-12T23:20:50Z", "1985-04-12T23:20:50", "1985-04-12T23:20:50.Z"] {
+        assert!(parser.parse(chars(bad).iter()).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn abnf_repetitions() {
+    let g = r#"
+        pair = 2*3%d97 %s"B" ; case sensitive
+        pair =/ %x0D.0A
+    "#;
+    let parser = ParserBuilder::default()
+        .dialect(Dialect::Abnf)
+        .into_parser("pair", g)
+        .unwrap();
+    assert!(parser.parse(["a", "a", "B"].iter()).is_ok());
+    assert!(parser.parse(["a", "a", "a", "B"].iter()).is_ok());
+    assert!(parser.parse(["a", "B"].iter()).is_err());
+    assert!(parser.parse(["a", "a", "a", "a", "B"].iter()).is_err());
+    assert!(parser.parse(["a", "a", "b"].iter()).is_err());
+    assert!(parser.parse(["\r\n"].iter()).is_ok());
+}
+
+#[test]
+fn iso14977() {
+    let g = r#"
+        (* a simple program syntax, from the ISO 14977 wikipedia page *)
+        program = 'PROGRAM', identifier,
+                  'BEGIN',
+                  { assignment, ";" },
+                  'END.' ;
+        identifier = alphabetic character, { alphabetic character | digit } ;
+        number = [ "-" ], digit, { digit } ;
+        assignment = identifier, ":=", ( number | identifier ) ;
+        alphabetic character = "A" | "B" | "C" | "D" | "E" | "F" ;
+        digit = "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" ;
+        pair = 2 * digit .
+    "#;
+    let parser = ParserBuilder::default()
+        .dialect(Dialect::Iso14977)
+        .into_str_parser("program", g)
+        .unwrap();
+    assert!(parser.parse_str("PROGRAM CAFE BEGIN A := 3 ; B := A ; END.").is_ok());
+    assert!(parser.parse_str("PROGRAM CAFE BEGIN A := -12 ; END.").is_ok());
+    assert!(parser.parse_str("PROGRAM 1CAFE BEGIN END.").is_err());
+    let parser = ParserBuilder::default()
+        .dialect(Dialect::Iso14977)
+        .into_parser("pair", g)
+        .unwrap();
+    assert!(parser.parse(["4", "2"].iter()).is_ok());
+    assert!(parser.parse(["4"].iter()).is_err());
+}
+
+#[test]
+fn unsupported() {
+    let err = |dialect: Dialect, g: &str| ParserBuilder::default()
+        .dialect(dialect)
+        .into_parser("a", g)
+        .err()
+        .unwrap();
+    assert_eq!(err(Dialect::Abnf, "a = <prose>"), "line 1: prose values aren't supported");
+    assert_eq!(err(Dialect::Iso14977, "a = ? any ? ;"),
+               "line 1: special sequences aren't supported");
+    assert_eq!(err(Dialect::W3c, "a ::= b\n  c ::= [a-z] - 'x'"),
+               "Failed to parse user grammar: line 2: exceptions aren't supported at Op('-')");
+    assert_eq!(err(Dialect::W3c, "a ::= 'b' )"),
+               "Failed to parse user grammar: line 1: expected a rule definition at Op(')')");
+    assert_eq!("abnf".parse::<Dialect>(), Ok(Dialect::Abnf));
+    assert!("yacc".parse::<Dialect>().is_err());
+}
+
+#[test]
+fn from_grammar_builder() {
+    let mut gb = earlgrey::GrammarBuilder::default();
+    gb.quiet_terminal("num", |n| n.parse::<u32>().is_ok());
+    let parser = ParserBuilder::from(gb)
+        .dialect(Dialect::W3c)
+        .into_str_parser("pair", "pair ::= num ',' num")
+        .unwrap();
+    assert!(parser.parse_str("12,3").is_ok());
+    assert!(parser.parse_str("12,").is_err());
+}
@@ -1,5 +1,6 @@
 #![deny(warnings)]
 
+use crate::dialect::Dialect;
 use lexers::EbnfTokenizer;
 use earlgrey::{
     Grammar, GrammarBuilder,
@@ -62,8 +63,33 @@ macro_rules! debug {
 }
 
 
+// Pre-plugged GrammarBuilder and the dialect of grammars, set with `dialect`.
+// Build it with `new` or `From<GrammarBuilder>`.
 #[derive(Default)]
-pub struct ParserBuilder(pub GrammarBuilder);
+pub struct ParserBuilder(pub GrammarBuilder, Dialect);
+
+impl From<GrammarBuilder> for ParserBuilder {
+    fn from(gb: GrammarBuilder) -> Self {
+        ParserBuilder::new(gb)
+    }
+}
+
+impl ParserBuilder {
+    pub fn new(gb: GrammarBuilder) -> Self {
+        ParserBuilder(gb, Dialect::default())
+    }
+
+    // Notation of the grammars this builder reads (abackus' own by default)
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.1 = dialect;
+        self
+    }
+
+    // Parse a user grammar in the selected dialect
+    pub fn into_builder(self, grammar: &str) -> Result<GrammarBuilder, String> {
+        self.1.parse_grammar(self.0, grammar)
+    }
+}
 
 #[derive(Clone,Debug)]
 enum G {Body(Vec<Vec<String>>), Part(Vec<String>), Atom(String), Nop}
@@ -93,7 +119,7 @@ impl ParserBuilder {
                     debug!("Adding terminal {:?}", token);
                     let tok = token.to_string();
                     gb.borrow_mut()
-                        .quiet_terminal(token, move |s| s == tok);
+                        .quiet_literal(token, token, move |s| s == tok);
                 },
                 _ => ()
             }
@@ -257,8 +283,7 @@ impl ParserBuilder {
             // Use EBNF parser to parse the user provided grammar
             let state = ebnf
                 .parse(EbnfTokenizer::new(user_grammar_spec.chars()))
-                .unwrap_or_else(|_| panic!("Failed to parse user grammar. {}",
-                                           user_grammar_spec));
+                .map_err(|e| format!("Failed to parse user grammar: {}", e))?;
             // Forge user's grammar builder by executing semantic actions
             if ev.eval_all(&state)?.len() != 1 {
                 panic!("BUG: EBNF grammar shouldn't be ambiguous!");
@@ -272,15 +297,13 @@ impl ParserBuilder {
     pub fn plug_terminal<N, F>(mut self, name: N, pred: F) -> Self
             where N: Into<String>, F: 'static + Fn(&str)->bool {
         self.0.quiet_terminal(&name.into(), pred);
-        ParserBuilder(self.0)
+        self
     }
 
     // Build a parser for the provided grammar in EBNF syntax
     pub fn into_parser(self, start: &str, grammar: &str)
             -> Result<EarleyParser, String> {
-        let user_grammar =
-            ParserBuilder::parse_grammar(self.0, grammar)?
-                .into_grammar(start)?;
+        let user_grammar = self.into_builder(grammar)?.into_grammar(start)?;
         Ok(EarleyParser::new(user_grammar))
     }
 }
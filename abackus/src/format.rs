@@ -0,0 +1,136 @@
+#![deny(warnings)]
+
+use crate::ebnf::{ebnf_grammar, ParserBuilder};
+use crate::imports::{import_path, statements};
+use lexers::EbnfTokenizer;
+use earlgrey::{EarleyParser, EarleyForest};
+
+// Rules longer than this are split one alternative per line
+const MAX_WIDTH: usize = 80;
+
+#[derive(Clone, Debug)]
+enum F {Tok(String), Body(Vec<String>), Part(String)}
+
+macro_rules! pull {
+    ($p:path, $e:expr) => (match $e {
+        $p(value) => value,
+        n => panic!("Bad pull match={:?}", n)
+    })
+}
+
+fn quote(lexeme: &str) -> String {
+    match lexeme.contains('\'') {
+        true => format!("\"{}\"", lexeme),
+        false => format!("'{}'", lexeme),
+    }
+}
+
+fn format_rule(head: &str, body: &[String]) -> String {
+    let one_line = format!("{} := {} ;", head, body.join(" | "));
+    if body.len() == 1 || one_line.chars().count() <= MAX_WIDTH {
+        return one_line;
+    }
+    let pad = " ".repeat(head.chars().count() + 1);
+    format!("{} := {}\n{};", head, body.join(&format!("\n{}| ", pad)), pad)
+}
+
+// Evaluator that renders each rule of the EBNF grammar in canonical form
+fn formatter() -> EarleyForest<'static, F> {
+    let mut ev = EarleyForest::new(|_, tok| F::Tok(tok.to_string()));
+    ev.action("<RuleList> -> <RuleList> <Rule>", |mut n| {
+        let mut rules = pull!(F::Body, n.remove(0));
+        rules.push(pull!(F::Tok, n.remove(0)));
+        F::Body(rules)
+    });
+    ev.action("<RuleList> -> <Rule>", |mut n| F::Body(vec![pull!(F::Tok, n.remove(0))]));
+    ev.action("<Rule> -> <Id> := <Body> ;", |mut n| {
+        let head = pull!(F::Tok, n.remove(0));
+        F::Tok(format_rule(&head, &pull!(F::Body, n.remove(1))))
+    });
+    ev.action("<Body> -> <Body> | <Part>", |mut n| {
+        let mut body = pull!(F::Body, n.remove(0));
+        body.push(pull!(F::Part, n.remove(1)));
+        F::Body(body)
+    });
+    ev.action("<Body> -> <Part>", |mut n| F::Body(vec![pull!(F::Part, n.remove(0))]));
+    ev.action("<Part> -> <Part> <Atom>", |mut n| {
+        let part = pull!(F::Part, n.remove(0));
+        F::Part(format!("{} {}", part, pull!(F::Tok, n.remove(0))))
+    });
+    ev.action("<Part> -> <Atom>", |mut n| F::Part(pull!(F::Tok, n.remove(0))));
+    ev.action("<Atom> -> <Id>", |mut n| n.remove(0));
+    ev.action("<Atom> -> ' <Chars> '", |mut n| F::Tok(quote(&pull!(F::Tok, n.remove(1)))));
+    ev.action("<Atom> -> \" <Chars> \"", |mut n| F::Tok(quote(&pull!(F::Tok, n.remove(1)))));
+    for (open, close) in [("(", ")"), ("[", "]"), ("{", "}")] {
+        ev.action(&format!("<Atom> -> {} <Body> {}", open, close), move |mut n| {
+            F::Tok(format!("{}{}{}", open, pull!(F::Body, n.remove(1)).join(" | "), close))
+        });
+        ev.action(&format!("<Atom> -> {} <Body> {} @<Tag>", open, close), move |mut n| {
+            let body = pull!(F::Body, n.remove(1)).join(" | ");
+            F::Tok(format!("{}{}{} {}", open, body, close, pull!(F::Tok, n.remove(2))))
+        });
+    }
+    ev
+}
+
+// Comments (from '#' to end of line) found in some grammar text
+fn comments(text: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut quote = None;
+    for (idx, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => {
+                let end = text[idx..].find('\n').map_or(text.len(), |e| idx + e);
+                found.push(text[idx..end].trim_end());
+                found.extend(comments(&text[end..]));
+                break;
+            }
+            None => (),
+        }
+    }
+    found
+}
+
+impl ParserBuilder {
+    // Pretty print a grammar in canonical form. Comments are kept above the
+    // rule they precede (or are part of) and blank lines between rules too.
+    pub fn format_grammar(grammar: &str) -> Result<String, String> {
+        let parser = EarleyParser::new(ebnf_grammar());
+        let ev = formatter();
+        let mut out = String::new();
+        let mut last = 0;
+        for (offset, stmt) in statements(grammar) {
+            let gap = &grammar[last..offset];
+            last = offset + stmt.len();
+            if !out.is_empty() && gap.matches('\n').count() > 1 {
+                out.push('\n');
+            }
+            for comment in comments(gap).into_iter().chain(comments(stmt)) {
+                out.push_str(comment);
+                out.push('\n');
+            }
+            if let Some(import) = import_path(stmt) {
+                out.push_str(&format!("@import \"{}\";\n", import?));
+                continue;
+            }
+            let state = parser.parse(EbnfTokenizer::new(stmt.chars()))
+                .map_err(|e| format!("Failed to parse user grammar: {}: `{}`", e, stmt))?;
+            for rule in pull!(F::Body, ev.eval(&state)?) {
+                out.push_str(&rule);
+                out.push('\n');
+            }
+        }
+        let trailing = comments(&grammar[last..]);
+        if !trailing.is_empty() && !out.is_empty() {
+            out.push('\n');
+        }
+        for comment in trailing {
+            out.push_str(comment);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,57 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use earlgrey::GrammarBuilder;
+
+#[test]
+fn canonical_form() {
+    let g = r#"
+        # arithmetic
+        expr   := expr ("+"|'-') term   # sums
+               | term ;
+        term := num|'(' expr ')' ;
+
+
+        arg := b { "," b } @x [ "i" ]@y;
+    "#;
+    let expected = concat!(
+        "# arithmetic\n",
+        "# sums\n",
+        "expr := expr ('+' | '-') term | term ;\n",
+        "term := num | '(' expr ')' ;\n",
+        "\n",
+        "arg := b {',' b} @x ['i'] @y ;\n");
+    let formatted = ParserBuilder::format_grammar(g).unwrap();
+    assert_eq!(formatted, expected);
+    // formatting is idempotent
+    assert_eq!(ParserBuilder::format_grammar(&formatted).unwrap(), expected);
+}
+
+#[test]
+fn long_rules() {
+    let g = r#"time := 'today' | 'tomorrow' | 'yesterday' | 'on' weekday | named_seq | "o'clock" ;"#;
+    let expected = concat!(
+        "time := 'today'\n",
+        "     | 'tomorrow'\n",
+        "     | 'yesterday'\n",
+        "     | 'on' weekday\n",
+        "     | named_seq\n",
+        "     | \"o'clock\"\n",
+        "     ;\n");
+    assert_eq!(ParserBuilder::format_grammar(g).unwrap(), expected);
+}
+
+#[test]
+fn same_grammar() {
+    let g = "row := \"a\" [ \"b\" ]@x (\"0\" | \"1\")@y [ \"c\" ]@z;";
+    let formatted = ParserBuilder::format_grammar(g).unwrap();
+    assert_eq!(formatted, "row := 'a' ['b'] @x ('0' | '1') @y ['c'] @z ;\n");
+    let rules = |g: &str| ParserBuilder::parse_grammar(GrammarBuilder::default(), g)
+        .unwrap().into_grammar("row").unwrap()
+        .rules.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+    assert_eq!(rules(g), rules(&formatted));
+
+    let formatted = ParserBuilder::format_grammar("@import 'x.ebnf' ;").unwrap();
+    assert_eq!(formatted, "@import \"x.ebnf\";\n");
+    assert!(ParserBuilder::format_grammar("a := := b ;").is_err());
+}
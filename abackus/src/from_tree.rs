@@ -0,0 +1,195 @@
+#![deny(warnings)]
+
+use crate::treeficator::Tree;
+
+/// Conversion of parse trees into typed values, see `abackus_derive::FromTree`
+/// to derive it for structs and enums.
+pub trait FromTree: Sized {
+    fn from_tree(tree: &Tree) -> Result<Self, String>;
+
+    // Build from all subtrees found for a struct field named `symbol`.
+    // Types like Option or Vec override this to accept zero or many.
+    fn from_matches(symbol: &str, trees: &[&Tree]) -> Result<Self, String> {
+        match trees {
+            [tree] => Self::from_tree(tree),
+            [] => Err(format!("missing '{}'", symbol)),
+            _ => Err(format!("expected one '{}' but found {}", symbol, trees.len())),
+        }
+    }
+}
+
+// Auxiliary symbols from groups, optionals and repetitions
+fn is_aux(symbol: &str) -> bool {
+    symbol.starts_with("<Uniq-")
+}
+
+impl Tree {
+    // Symbol the tree derives: a rule head or a terminal name
+    pub fn symbol(&self) -> &str {
+        match self {
+            Tree::Leaf(name, _) => name,
+            Tree::Node(rule, _) => rule.split(" -> ").next().unwrap_or(rule),
+        }
+    }
+
+    // Literals are terminals whose name is the lexeme they matched
+    fn is_literal(&self) -> bool {
+        matches!(self, Tree::Leaf(name, lexeme) if name == lexeme)
+    }
+
+    // Short description used in error messages
+    pub fn describe(&self) -> String {
+        match self {
+            Tree::Leaf(name, lexeme) if name == lexeme => format!("'{}'", lexeme),
+            Tree::Leaf(name, lexeme) => format!("{} '{}'", name, lexeme),
+            Tree::Node(rule, _) => format!("'{}'", rule),
+        }
+    }
+
+    // Find the subtree for `head`, looking through rules with a single child
+    // (eg: expr -> term) that wrap it
+    pub fn find_rule(&self, head: &str) -> Result<&Tree, String> {
+        let mut tree = self;
+        loop {
+            if tree.symbol() == head {
+                return Ok(tree);
+            }
+            match tree {
+                Tree::Node(_, subtrees) if subtrees.len() == 1 => tree = &subtrees[0],
+                _ => return Err(format!("expected '{}' but found {}", head, self.describe())),
+            }
+        }
+    }
+
+    // Subtrees for `symbol` (or the tag `@symbol`) among the children,
+    // looking into groups, optionals and repetitions
+    pub fn children_named(&self, symbol: &str) -> Vec<&Tree> {
+        let tag = format!("@{}", symbol);
+        let mut found = Vec::new();
+        let mut pending: Vec<&Tree> = match self {
+            Tree::Node(_, subtrees) => subtrees.iter().rev().collect(),
+            Tree::Leaf(..) => Vec::new(),
+        };
+        while let Some(tree) = pending.pop() {
+            match tree {
+                _ if tree.symbol() == symbol || tree.symbol() == tag => found.push(tree),
+                Tree::Node(_, subtrees) if is_aux(tree.symbol()) =>
+                    pending.extend(subtrees.iter().rev()),
+                _ => (),
+            }
+        }
+        found
+    }
+
+    // Children that aren't literals, flattening repetitions and lists like
+    // `list := list ',' item | item`
+    pub fn items(&self) -> Vec<&Tree> {
+        let mut found = Vec::new();
+        self.collect_items(self.symbol(), &mut found);
+        found
+    }
+
+    fn collect_items<'a>(&'a self, head: &str, found: &mut Vec<&'a Tree>) {
+        match self {
+            Tree::Node(_, subtrees) => for tree in subtrees {
+                match tree {
+                    _ if tree.is_literal() => (),
+                    Tree::Node(..) if tree.symbol() == head || is_aux(tree.symbol()) =>
+                        tree.collect_items(tree.symbol(), found),
+                    _ => found.push(tree),
+                }
+            },
+            Tree::Leaf(..) => found.push(self),
+        }
+    }
+
+    // The lexeme of a tree that wraps a single token
+    pub fn lexeme(&self) -> Result<&str, String> {
+        let mut tree = self;
+        loop {
+            match tree {
+                Tree::Leaf(_, lexeme) => return Ok(lexeme),
+                Tree::Node(_, subtrees) if subtrees.len() == 1 => tree = &subtrees[0],
+                _ => return Err(format!("expected a single token but found {}",
+                                        self.describe())),
+            }
+        }
+    }
+}
+
+impl FromTree for Tree {
+    fn from_tree(tree: &Tree) -> Result<Self, String> {
+        Ok(tree.clone())
+    }
+}
+
+impl FromTree for String {
+    fn from_tree(tree: &Tree) -> Result<Self, String> {
+        tree.lexeme().map(|l| l.to_string())
+    }
+}
+
+macro_rules! from_lexeme {
+    ($($t:ty),*) => ($(
+        impl FromTree for $t {
+            fn from_tree(tree: &Tree) -> Result<Self, String> {
+                let lexeme = tree.lexeme()?;
+                lexeme.parse().map_err(|e| format!(
+                    "can't read '{}' as {}: {}", lexeme, stringify!($t), e))
+            }
+        }
+    )*)
+}
+
+from_lexeme!(bool, char, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+impl<T: FromTree> FromTree for Box<T> {
+    fn from_tree(tree: &Tree) -> Result<Self, String> {
+        T::from_tree(tree).map(Box::new)
+    }
+
+    fn from_matches(symbol: &str, trees: &[&Tree]) -> Result<Self, String> {
+        T::from_matches(symbol, trees).map(Box::new)
+    }
+}
+
+impl<T: FromTree> FromTree for Option<T> {
+    // Empty optionals (eg: `[x]` that matched nothing) are None
+    fn from_tree(tree: &Tree) -> Result<Self, String> {
+        match tree {
+            Tree::Node(_, subtrees) if subtrees.is_empty() => Ok(None),
+            _ => T::from_tree(tree).map(Some),
+        }
+    }
+
+    fn from_matches(symbol: &str, trees: &[&Tree]) -> Result<Self, String> {
+        match trees {
+            [] => Ok(None),
+            [tree] => Self::from_tree(tree),
+            _ => T::from_matches(symbol, trees).map(Some),
+        }
+    }
+}
+
+impl<T: FromTree> FromTree for Vec<T> {
+    fn from_tree(tree: &Tree) -> Result<Self, String> {
+        tree.items().into_iter()
+            .enumerate()
+            .map(|(idx, t)| T::from_tree(t).map_err(|e| format!("[{}]: {}", idx, e)))
+            .collect()
+    }
+
+    // A field that shows up several times (eg: `{item}`) collects them all,
+    // a single recursive list (eg: `args := args ',' expr | expr`) is flattened
+    fn from_matches(_: &str, trees: &[&Tree]) -> Result<Self, String> {
+        match trees {
+            [list @ Tree::Node(_, subtrees)]
+                if subtrees.iter().any(|t| t.symbol() == list.symbol()) =>
+                Self::from_tree(list),
+            _ => trees.iter()
+                .enumerate()
+                .map(|(idx, t)| T::from_tree(t).map_err(|e| format!("[{}]: {}", idx, e)))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use crate::from_tree::FromTree;
+use crate::treeficator::Tree;
+
+// Hand written version of what abackus_derive::FromTree generates
+#[derive(Debug, PartialEq)]
+struct Call {
+    name: String,
+    args: Vec<f64>,
+    flag: Option<String>,
+}
+
+impl FromTree for Call {
+    fn from_tree(tree: &Tree) -> Result<Self, String> {
+        let tree = tree.find_rule("call").map_err(|e| format!("Call: {}", e))?;
+        Ok(Call {
+            name: FromTree::from_matches("name", &tree.children_named("name"))
+                .map_err(|e| format!("Call.name: {}", e))?,
+            args: FromTree::from_matches("args", &tree.children_named("args"))
+                .map_err(|e| format!("Call.args: {}", e))?,
+            flag: FromTree::from_matches("flag", &tree.children_named("flag"))
+                .map_err(|e| format!("Call.flag: {}", e))?,
+        })
+    }
+}
+
+fn parse(input: &str) -> Vec<Tree> {
+    ParserBuilder::default()
+        .plug_terminal("name", |n| n.chars().all(char::is_alphabetic))
+        .plug_terminal("num", |n| n.parse::<f64>().is_ok())
+        .treeficator(r#"
+            stmt := call ;
+            call := name '(' [args] ')' ['!'] @flag ;
+            args := args ',' num | num ;
+        "#, "stmt")(input.split_whitespace())
+        .unwrap()
+}
+
+#[test]
+fn helpers() {
+    let trees = parse("f ( 1 , 2 , 3 ) !");
+    let call = trees[0].find_rule("call").unwrap();
+    assert_eq!(call.symbol(), "call");
+    assert_eq!(call.children_named("name")[0].lexeme(), Ok("f"));
+    let args = call.children_named("args");
+    assert_eq!(args.len(), 1);
+    let items: Vec<_> = args[0].items().iter().map(|t| t.lexeme().unwrap()).collect();
+    assert_eq!(items, vec!["1", "2", "3"]);
+    assert_eq!(call.children_named("flag")[0].symbol(), "@flag");
+    assert!(call.lexeme().is_err());
+    assert!(trees[0].find_rule("args").is_err());
+}
+
+#[test]
+fn options_and_lists() {
+    assert_eq!(Call::from_tree(&parse("f ( 1 , 2 ) !")[0]), Ok(Call {
+        name: "f".to_string(), args: vec![1.0, 2.0], flag: Some("!".to_string()),
+    }));
+    assert_eq!(Call::from_tree(&parse("g ( 4 )")[0]), Ok(Call {
+        name: "g".to_string(), args: vec![4.0], flag: None,
+    }));
+    assert_eq!(Call::from_tree(&parse("h ( )")[0]), Ok(Call {
+        name: "h".to_string(), args: Vec::new(), flag: None,
+    }));
+    assert_eq!(Option::<u32>::from_tree(&parse("f ( )")[0]),
+               Err("expected a single token but found 'stmt -> call'".to_string()));
+}
+
+#[test]
+fn errors() {
+    let trees = parse("f ( 1.5 )");
+    let call = trees[0].find_rule("call").unwrap();
+    assert_eq!(u32::from_matches("num", &call.children_named("args")),
+               Err("can't read '1.5' as u32: invalid digit found in string".to_string()));
+    assert_eq!(u32::from_matches("num", &[]), Err("missing 'num'".to_string()));
+    assert_eq!(String::from_matches("x", &[call, call]),
+               Err("expected one 'x' but found 2".to_string()));
+}
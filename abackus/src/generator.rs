@@ -0,0 +1,208 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use earlgrey::Grammar;
+use std::collections::{BTreeSet, HashMap};
+
+/// Generates sentences (token sequences) accepted by a grammar.
+/// Plugged terminals can't be generated from their predicates, they
+/// need a list of sample lexemes to pick from.
+pub struct Generator {
+    grammar: Grammar,
+    // min number of expansions to reach only terminals from a non-terminal
+    heights: HashMap<String, usize>,
+    weights: HashMap<String, f64>,
+    samples: HashMap<String, Vec<String>>,
+    max_depth: usize,
+    state: u64,
+}
+
+// Height of a rule is 1 + the height of its tallest non-terminal
+fn rule_height<'a>(nonterms: impl Iterator<Item=&'a str>,
+                   heights: &HashMap<String, usize>) -> Option<usize> {
+    nonterms
+        .map(|n| heights.get(n).copied())
+        .try_fold(0, |h, n| n.map(|n| h.max(n)))
+        .map(|h| h + 1)
+}
+
+impl Generator {
+    pub fn new(grammar: Grammar) -> Self {
+        let mut heights = HashMap::new();
+        loop {
+            let mut changed = false;
+            for rule in &grammar.rules {
+                let nonterms = rule.spec.iter().filter_map(|s| s.nonterm());
+                match (rule_height(nonterms, &heights), heights.get(&rule.head)) {
+                    (Some(h), Some(&current)) if h >= current => (),
+                    (Some(h), _) => {
+                        heights.insert(rule.head.clone(), h);
+                        changed = true;
+                    },
+                    (None, _) => (),
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        Generator {
+            grammar,
+            heights,
+            weights: HashMap::new(),
+            samples: HashMap::new(),
+            max_depth: 16,
+            state: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    // Relative weight of a rule (eg: "expr -> expr + term"), defaults to 1
+    pub fn weight(mut self, rule: &str, weight: f64) -> Self {
+        self.weights.insert(rule.to_string(), weight);
+        self
+    }
+
+    // Lexemes to use for a plugged terminal
+    pub fn samples<S: AsRef<str>>(mut self, terminal: &str, samples: &[S]) -> Self {
+        self.samples.insert(terminal.to_string(),
+                            samples.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    // Past this depth only the rules that terminate the quickest are used
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    // Seed for the random generator, same seed yields the same sentences
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.state = seed.max(1);
+        self
+    }
+
+    // xorshift64* in [0, 1)
+    fn random(&mut self) -> f64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let r = self.state.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        (r >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Lexemes a terminal can produce (samples are checked with its predicate)
+    fn lexemes(&self, name: &str, pred: &dyn Fn(&str) -> bool)
+            -> Result<Vec<String>, String> {
+        // literals were recorded when lowering the grammar
+        if let Some(text) = self.grammar.literal(name) {
+            return Ok(vec![text.to_string()]);
+        }
+        match self.samples.get(name) {
+            Some(samples) if !samples.is_empty() => {
+                if let Some(bad) = samples.iter().find(|s| !pred(s)) {
+                    return Err(format!("Sample '{}' rejected by terminal: {}", bad, name));
+                }
+                Ok(samples.clone())
+            },
+            _ => Err(format!("Missing samples for terminal: {}", name)),
+        }
+    }
+
+    fn expand(&mut self, symbol: &str, depth: usize, out: &mut Vec<String>)
+            -> Result<(), String> {
+        let height = match self.heights.get(symbol) {
+            Some(height) => *height,
+            None => {
+                // blame names without rules (eg: unplugged terminals)
+                let culprit = self.grammar.rules.iter()
+                    .flat_map(|r| r.spec.iter().filter_map(|s| s.nonterm()))
+                    .find(|n| !self.grammar.rules.iter().any(|r| r.head == *n))
+                    .unwrap_or(symbol);
+                return Err(format!("Can't generate from non-terminal: {}", culprit));
+            },
+        };
+        let choices: Vec<(usize, f64)> = self.grammar.rules.iter().enumerate()
+            .filter(|(_, r)| r.head == symbol)
+            .filter(|(_, r)| depth < self.max_depth || rule_height(
+                r.spec.iter().filter_map(|s| s.nonterm()), &self.heights) == Some(height))
+            .map(|(idx, r)| (idx, *self.weights.get(&r.to_string()).unwrap_or(&1.0)))
+            .filter(|(_, w)| *w > 0.0)
+            .collect();
+        let total: f64 = choices.iter().map(|(_, w)| w).sum();
+        if choices.is_empty() {
+            return Err(format!("No rules with weight left for: {}", symbol));
+        }
+        let mut pick = self.random() * total;
+        let mut chosen = choices[choices.len() - 1].0;
+        for (idx, weight) in choices {
+            if pick < weight {
+                chosen = idx;
+                break;
+            }
+            pick -= weight;
+        }
+        let rule = self.grammar.rules[chosen].clone();
+        for symbol in &rule.spec {
+            match symbol.terminal() {
+                Some((name, pred)) => {
+                    let lexemes = self.lexemes(name, pred)?;
+                    let idx = (self.random() * lexemes.len() as f64) as usize;
+                    out.push(lexemes[idx].clone());
+                },
+                None => self.expand(symbol.name(), depth + 1, out)?,
+            }
+        }
+        Ok(())
+    }
+
+    // Generate a random sentence from the start symbol
+    pub fn generate(&mut self) -> Result<Vec<String>, String> {
+        let mut out = Vec::new();
+        let start = self.grammar.start.clone();
+        self.expand(&start, 0, &mut out)?;
+        Ok(out)
+    }
+
+    // All sentences of up to `max_len` tokens, shortest first
+    pub fn enumerate(&self, max_len: usize) -> Result<Vec<Vec<String>>, String> {
+        let mut lang: HashMap<&str, BTreeSet<Vec<String>>> = HashMap::new();
+        loop {
+            let mut changed = false;
+            for rule in &self.grammar.rules {
+                let mut sentences = BTreeSet::from([Vec::new()]);
+                for symbol in &rule.spec {
+                    let options: Vec<Vec<String>> = match symbol.terminal() {
+                        Some((name, pred)) => self.lexemes(name, pred)?
+                            .into_iter().map(|l| vec![l]).collect(),
+                        None => lang.get(symbol.name())
+                            .map_or(Vec::new(), |l| l.iter().cloned().collect()),
+                    };
+                    sentences = sentences.iter().flat_map(|prefix| options.iter()
+                        .filter(|o| prefix.len() + o.len() <= max_len)
+                        .map(move |o| [prefix.as_slice(), o].concat()))
+                        .collect();
+                }
+                let known = lang.entry(rule.head.as_str()).or_default();
+                for sentence in sentences {
+                    changed |= known.insert(sentence);
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        let mut sentences: Vec<_> = lang.remove(self.grammar.start.as_str())
+            .unwrap_or_default().into_iter().collect();
+        sentences.sort_by_key(|s| s.len());
+        Ok(sentences)
+    }
+}
+
+impl ParserBuilder {
+    // Build a sentence generator for the provided grammar in EBNF syntax
+    pub fn into_generator(self, start: &str, grammar: &str) -> Result<Generator, String> {
+        let grammar = self.into_builder(grammar)?
+            .into_grammar(start)?;
+        Ok(Generator::new(grammar))
+    }
+}
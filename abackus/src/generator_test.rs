@@ -0,0 +1,109 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+
+static ARITH: &str = r#"
+    expr := expr ('+' | '-') term | term ;
+    term := num | '(' expr ')' ;
+"#;
+
+fn builder() -> ParserBuilder {
+    ParserBuilder::default().plug_terminal("num", |n| n.parse::<u32>().is_ok())
+}
+
+#[test]
+fn random_sentences_parse() {
+    let parser = builder().into_parser("expr", ARITH).unwrap();
+    let mut generator = builder().into_generator("expr", ARITH).unwrap()
+        .samples("num", &["0", "1", "42"])
+        .max_depth(6)
+        .seed(7);
+    for _ in 0..200 {
+        let sentence = generator.generate().unwrap();
+        assert!(parser.parse(sentence.iter()).is_ok(), "{:?}", sentence);
+    }
+}
+
+#[test]
+fn seeded() {
+    let generate = |seed| {
+        let mut g = builder().into_generator("expr", ARITH).unwrap()
+            .samples("num", &["1", "2"]).seed(seed);
+        (0..20).map(|_| g.generate().unwrap().join(" ")).collect::<Vec<_>>()
+    };
+    assert_eq!(generate(3), generate(3));
+    assert_ne!(generate(3), generate(4));
+}
+
+#[test]
+fn depth_and_weights() {
+    // past max_depth only the shortest way out is taken
+    let mut g = builder().into_generator("expr", ARITH).unwrap()
+        .samples("num", &["1"]).max_depth(0);
+    assert_eq!(g.generate().unwrap(), vec!["1"]);
+
+    // zero weight disables a rule
+    let mut g = builder().into_generator("expr", ARITH).unwrap()
+        .samples("num", &["1"])
+        .weight("term -> num", 0.0)
+        .max_depth(0);
+    assert_eq!(g.generate().unwrap_err(), "No rules with weight left for: term");
+
+    // heavy recursion still terminates once past max_depth, the recursive
+    // rule's label has the lowered name of the ('+' | '-') group
+    let grammar = builder().into_builder(ARITH).unwrap().into_grammar("expr").unwrap();
+    let recursive = grammar.rules.iter()
+        .find(|r| r.head == "expr" && r.spec.len() == 3 && r.spec[0].name() == "expr")
+        .unwrap().to_string();
+    let mut g = builder().into_generator("expr", ARITH).unwrap()
+        .samples("num", &["1"])
+        .weight(&recursive, 1000.0)
+        .weight("term -> ( expr )", 1000.0)
+        .max_depth(5);
+    for _ in 0..20 {
+        let sentence = g.generate().unwrap();
+        assert!(sentence.len() > 1 && sentence.len() < 1000, "{:?}", sentence);
+    }
+}
+
+#[test]
+fn samples_required() {
+    let mut g = builder().into_generator("expr", ARITH).unwrap();
+    assert_eq!(g.generate().unwrap_err(), "Missing samples for terminal: num");
+    let mut g = builder().into_generator("expr", ARITH).unwrap()
+        .samples("num", &["one"]);
+    assert_eq!(g.generate().unwrap_err(), "Sample 'one' rejected by terminal: num");
+    let mut g = ParserBuilder::default().into_generator("expr", ARITH).unwrap();
+    assert_eq!(g.generate().unwrap_err(), "Can't generate from non-terminal: num");
+}
+
+#[test]
+fn exhaustive() {
+    let g = builder().into_generator("expr", ARITH).unwrap()
+        .samples("num", &["1", "2"]);
+    let sentences: Vec<_> = g.enumerate(3).unwrap().into_iter()
+        .map(|s| s.join(" ")).collect();
+    assert_eq!(sentences, vec![
+        "1", "2",
+        "( 1 )", "( 2 )", "1 + 1", "1 + 2", "1 - 1", "1 - 2",
+        "2 + 1", "2 + 2", "2 - 1", "2 - 2",
+    ]);
+    let parser = builder().into_parser("expr", ARITH).unwrap();
+    for sentence in g.enumerate(5).unwrap() {
+        assert!(parser.parse(sentence.iter()).is_ok(), "{:?}", sentence);
+    }
+}
+
+#[test]
+fn identifier_like_terminals() {
+    // 'id' accepts its own name but is plugged, samples are used for it
+    let g = ParserBuilder::default()
+        .plug_terminal("id", |w| w.chars().all(char::is_alphabetic))
+        .into_generator("call", "call := id '(' id ')' ;").unwrap()
+        .samples("id", &["f"]);
+    assert_eq!(g.enumerate(4).unwrap(), vec![vec!["f", "(", "f", ")"]]);
+    let mut g = ParserBuilder::default()
+        .plug_terminal("id", |w| w.chars().all(char::is_alphabetic))
+        .into_generator("call", "call := id '(' id ')' ;").unwrap();
+    assert_eq!(g.generate().unwrap_err(), "Missing samples for terminal: id");
+}
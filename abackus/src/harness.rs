@@ -0,0 +1,289 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use crate::tokenizer::GrammarTokenizer;
+use crate::treeficator::{tree_builder, Tree};
+use std::collections::HashMap;
+use std::fmt;
+
+// Quote atoms that would break the s-expression
+fn atom(text: &str) -> String {
+    match text.is_empty() || text.contains(|c: char| c.is_whitespace() || "()\"".contains(c)) {
+        true => format!("{:?}", text),
+        false => text.to_string(),
+    }
+}
+
+impl Tree {
+    // Compact s-expression like `(expr (expr (term 1)) + (term 2))`.
+    // Leaves show their lexeme and auxiliary symbols (groups, optionals and
+    // repetitions) are spliced into their parent.
+    pub fn sexpr(&self) -> String {
+        let mut parts = Vec::new();
+        self.sexpr_parts(&mut parts);
+        parts.join(" ")
+    }
+
+    fn sexpr_parts(&self, parts: &mut Vec<String>) {
+        match self {
+            Tree::Leaf(_, lexeme) => parts.push(atom(lexeme)),
+            Tree::Node(_, subtrees) if self.symbol().starts_with("<Uniq-") =>
+                subtrees.iter().for_each(|t| t.sexpr_parts(parts)),
+            Tree::Node(_, subtrees) => {
+                let mut inner = vec![atom(self.symbol())];
+                subtrees.iter().for_each(|t| t.sexpr_parts(&mut inner));
+                parts.push(format!("({})", inner.join(" ")));
+            },
+        }
+    }
+}
+
+// Re-print a hand written s-expression with the spacing `Tree::sexpr` uses
+fn normalize(sexpr: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut depth = 0i32;
+    let mut chars = sexpr.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            _ if c.is_whitespace() => continue,
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => (),
+        }
+        if depth < 0 {
+            return Err(format!("unbalanced ')' in {}", sexpr));
+        }
+        if !out.is_empty() && c != ')' && !out.ends_with('(') {
+            out.push(' ');
+        }
+        match c {
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some(c) => text.push(c),
+                        None => return Err(format!("unterminated string in {}", sexpr)),
+                    }
+                }
+                out.push_str(&atom(&text));
+            },
+            '(' | ')' => out.push(c),
+            _ => {
+                out.push(c);
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()\"".contains(c) {
+                        break;
+                    }
+                    out.push(c);
+                    chars.next();
+                }
+            },
+        }
+    }
+    match depth {
+        0 => Ok(out),
+        _ => Err(format!("unbalanced '(' in {}", sexpr)),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expect {
+    Reject,
+    // expected trees (normalized) and number of parses
+    Trees(Vec<String>, Option<usize>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestCase {
+    pub line: usize,
+    pub input: String,
+    pub expect: Expect,
+}
+
+/// Grammar test file. Each case is an input line starting with '>' followed
+/// by what to expect: trees as s-expressions (see `Tree::sexpr`), `reject`
+/// or `trees: N` for the number of parses. Lines with `grammar:` and
+/// `start:` before the first case tell the command line what to test.
+///
+/// ```text
+/// grammar: arith.ebnf
+/// start: expr
+///
+/// > 1 + 2
+/// (expr (expr (term 1)) + (term 2))
+///
+/// > 1 +
+/// reject
+///
+/// > 1 + 2 + 3
+/// trees: 2
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GrammarTests {
+    pub grammar: Option<String>,
+    pub start: Option<String>,
+    pub cases: Vec<TestCase>,
+}
+
+impl GrammarTests {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut tests = GrammarTests::default();
+        // trees may span several lines until their parens balance
+        let mut pending = String::new();
+        let mut pending_line = 0;
+        for (lineno, line) in text.lines().enumerate().map(|(n, l)| (n + 1, l.trim())) {
+            let err = |e: String| Err(format!("line {}: {}", lineno, e));
+            if pending.is_empty() && (line.is_empty() || line.starts_with('#')) {
+                continue;
+            }
+            if let Some(input) = line.strip_prefix('>').filter(|_| pending.is_empty()) {
+                tests.cases.push(TestCase {
+                    line: lineno,
+                    input: input.trim().to_string(),
+                    expect: Expect::Trees(Vec::new(), None),
+                });
+                continue;
+            }
+            let case = match tests.cases.last_mut() {
+                Some(case) => case,
+                None => match line.split_once(':') {
+                    Some(("grammar", file)) => {
+                        tests.grammar = Some(file.trim().to_string());
+                        continue;
+                    },
+                    Some(("start", start)) => {
+                        tests.start = Some(start.trim().to_string());
+                        continue;
+                    },
+                    _ => return err(format!("expected a header or '>' but found {}", line)),
+                },
+            };
+            match &mut case.expect {
+                Expect::Trees(trees, _) if !pending.is_empty() || line.starts_with('(') => {
+                    if pending.is_empty() {
+                        pending_line = lineno;
+                    }
+                    pending.push(' ');
+                    pending.push_str(line);
+                    if pending.matches('(').count() <= pending.matches(')').count() {
+                        trees.push(normalize(&pending)
+                            .map_err(|e| format!("line {}: {}", pending_line, e))?);
+                        pending.clear();
+                    }
+                },
+                Expect::Trees(trees, None) if line == "reject" && trees.is_empty() =>
+                    case.expect = Expect::Reject,
+                Expect::Trees(_, count @ None) if line.starts_with("trees:") => {
+                    match line["trees:".len()..].trim().parse() {
+                        Ok(n) => *count = Some(n),
+                        Err(_) => return err(format!("bad tree count in {}", line)),
+                    }
+                },
+                _ => return err(format!("unexpected {}", line)),
+            }
+        }
+        if !pending.is_empty() {
+            return Err(format!("line {}: unbalanced '(' in{}", pending_line, pending));
+        }
+        Ok(tests)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestFailure {
+    pub line: usize,
+    pub input: String,
+    // what went wrong, with '-' expected and '+' actual trees
+    pub diff: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TestReport {
+    pub passed: usize,
+    pub failures: Vec<TestFailure>,
+}
+
+impl TestReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    // Panic with the report if any case failed (for use in #[test])
+    pub fn assert_ok(&self) {
+        assert!(self.is_ok(), "\n{}", self);
+    }
+}
+
+impl fmt::Display for TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for failure in &self.failures {
+            writeln!(f, "line {}: > {}", failure.line, failure.input)?;
+            for line in failure.diff.lines() {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        write!(f, "{} passed, {} failed", self.passed, self.failures.len())
+    }
+}
+
+// Compare expected and actual trees, empty if they match
+fn diff(expected: &[String], count: Option<usize>, actual: &[String]) -> String {
+    let mut lines = Vec::new();
+    if let Some(n) = count.filter(|n| *n != actual.len()) {
+        lines.push(format!("expected {} trees but got {}", n, actual.len()));
+    }
+    if expected.is_empty() {
+        return lines.join("\n");
+    }
+    // count occurrences, ambiguous parses may repeat after splicing
+    let mut unmatched: HashMap<&str, i32> = HashMap::new();
+    expected.iter().for_each(|t| *unmatched.entry(t).or_default() += 1);
+    actual.iter().for_each(|t| *unmatched.entry(t).or_default() -= 1);
+    for tree in expected.iter().filter(|t| unmatched[t.as_str()] > 0) {
+        lines.push(format!("- {}", tree));
+    }
+    for tree in actual.iter().filter(|t| unmatched[t.as_str()] < 0) {
+        lines.push(format!("+ {}", tree));
+    }
+    lines.dedup();
+    lines.join("\n")
+}
+
+impl ParserBuilder {
+    // Run grammar tests (see `GrammarTests`) against a grammar. Inputs are
+    // tokenized with the grammar's own terminals (see `GrammarTokenizer`).
+    pub fn run_tests(self, grammar: &str, start: &str, tests: &str)
+            -> Result<TestReport, String> {
+        let tests = GrammarTests::parse(tests)?;
+        let parser = self.into_parser(start, grammar)?;
+        let tokenizer = GrammarTokenizer::new(&parser.grammar);
+        let builder = tree_builder(&parser.grammar);
+        let mut report = TestReport::default();
+        for case in tests.cases {
+            let result = parser.parse(tokenizer.tokenize(&case.input))
+                .and_then(|state| builder.eval_all(&state));
+            let diff = match (&case.expect, result) {
+                (Expect::Reject, Err(_)) => String::new(),
+                (Expect::Reject, Ok(trees)) => {
+                    let parses = trees.iter().map(|t| format!("+ {}", t.sexpr()));
+                    std::iter::once("expected a rejection".to_string())
+                        .chain(parses).collect::<Vec<_>>().join("\n")
+                },
+                (Expect::Trees(..), Err(e)) => format!("rejected: {}", e),
+                (Expect::Trees(expected, count), Ok(trees)) => {
+                    let actual: Vec<_> = trees.iter().map(|t| t.sexpr()).collect();
+                    diff(expected, *count, &actual)
+                },
+            };
+            match diff.is_empty() {
+                true => report.passed += 1,
+                false => report.failures.push(TestFailure {
+                    line: case.line, input: case.input, diff,
+                }),
+            }
+        }
+        Ok(report)
+    }
+}
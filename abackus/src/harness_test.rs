@@ -0,0 +1,91 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use crate::harness::{Expect, GrammarTests, TestCase, TestFailure};
+
+fn builder() -> ParserBuilder {
+    ParserBuilder::default().plug_terminal("num", |n| n.parse::<u32>().is_ok())
+}
+
+#[test]
+fn parse_test_file() {
+    let tests = GrammarTests::parse(r#"
+        start: expr
+        > 1
+        (expr
+          (term 1))
+        trees: 1
+        > x
+        reject
+    "#).unwrap();
+    assert_eq!(tests.grammar, None);
+    assert_eq!(tests.start.as_deref(), Some("expr"));
+    assert_eq!(tests.cases, vec![
+        TestCase {
+            line: 3, input: "1".to_string(),
+            expect: Expect::Trees(vec!["(expr (term 1))".to_string()], Some(1)),
+        },
+        TestCase { line: 7, input: "x".to_string(), expect: Expect::Reject },
+    ]);
+    assert_eq!(GrammarTests::parse("> 1\nreject\n(expr").unwrap_err(),
+               "line 3: unexpected (expr");
+    assert_eq!(GrammarTests::parse("> 1\n(expr (term 1)").unwrap_err(),
+               "line 2: unbalanced '(' in (expr (term 1)");
+    assert_eq!(GrammarTests::parse("1 + 2").unwrap_err(),
+               "line 1: expected a header or '>' but found 1 + 2");
+}
+
+#[test]
+fn run_test_file() {
+    let grammar = include_str!("tests/arith.ebnf");
+    let tests = include_str!("tests/arith.tests");
+    let report = builder().run_tests(grammar, "expr", tests).unwrap();
+    report.assert_ok();
+    assert_eq!(report.passed, 4);
+}
+
+#[test]
+fn report_diffs() {
+    let grammar = include_str!("tests/arith.ebnf");
+    let report = builder().run_tests(grammar, "expr", r#"
+        > 1 + 2
+        (expr (expr (term (@neg) 1)) + (term (@neg) 3))
+        > 2 ^ 3 ^ 4
+        trees: 1
+        > 1 +
+        (expr)
+        > 1
+        reject
+    "#).unwrap();
+    assert_eq!(report.passed, 0);
+    assert_eq!(report.failures[0], TestFailure {
+        line: 2,
+        input: "1 + 2".to_string(),
+        diff: "- (expr (expr (term (@neg) 1)) + (term (@neg) 3))\n\
+               + (expr (expr (term (@neg) 1)) + (term (@neg) 2))".to_string(),
+    });
+    assert_eq!(report.failures[1].diff, "expected 1 trees but got 2");
+    assert!(report.failures[2].diff.starts_with("rejected: "));
+    assert_eq!(report.failures[3].diff,
+               "expected a rejection\n+ (expr (term (@neg) 1))");
+    let text = report.to_string();
+    assert!(text.starts_with("line 2: > 1 + 2\n  - (expr"), "{}", text);
+    assert!(text.ends_with("0 passed, 4 failed"), "{}", text);
+}
+
+#[test]
+fn identifier_like_terminals() {
+    // 'word' accepts its own name, inputs still split into words and '+'
+    let report = ParserBuilder::default()
+        .plug_terminal("word", |w| w.chars().all(char::is_alphabetic))
+        .run_tests("s := word '+' word ;", "s", r#"
+            > abc+def
+            (s abc + def)
+            > word + word
+            (s word + word)
+            > abc+
+            reject
+        "#).unwrap();
+    report.assert_ok();
+    assert_eq!(report.passed, 3);
+}
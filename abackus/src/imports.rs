@@ -0,0 +1,132 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use earlgrey::{EarleyParser, GrammarBuilder};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+
+// Split grammar into ';' terminated statements (skipping quotes and comments).
+// Each statement comes with the byte offset of its first token. Public for
+// abackus-derive only.
+#[doc(hidden)]
+pub fn statements(grammar: &str) -> Vec<(usize, &str)> {
+    let mut stmts = Vec::new();
+    let mut begin = None;
+    let mut quote = None;
+    let mut comment = false;
+    for (idx, c) in grammar.char_indices() {
+        if comment {
+            comment = c != '\n';
+            continue;
+        }
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '#' => comment = true,
+            _ if c.is_whitespace() => (),
+            _ if begin.is_none() => begin = Some(idx),
+            _ => (),
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            ';' => if let Some(b) = begin.take() {
+                stmts.push((b, &grammar[b..=idx]));
+            },
+            _ => (),
+        }
+    }
+    if let Some(b) = begin {
+        stmts.push((b, &grammar[b..]));
+    }
+    stmts
+}
+
+// Extract the file name from an `@import "file";` statement
+pub(crate) fn import_path(stmt: &str) -> Option<Result<&str, String>> {
+    let target = stmt.strip_prefix("@import")?
+        .trim_end_matches(';').trim();
+    let unquoted = target.strip_prefix('"').and_then(|t| t.strip_suffix('"'))
+        .or_else(|| target.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')));
+    Some(unquoted.ok_or(format!("bad import target {}", target)))
+}
+
+// Collects statements from a grammar file and its imports
+struct Loader<'a> {
+    base: &'a Path,
+    stack: Vec<PathBuf>,
+    loaded: Vec<PathBuf>,
+    heads: HashMap<String, (PathBuf, usize)>,
+    grammar: String,
+}
+
+impl<'a> Loader<'a> {
+    fn load(&mut self, file: &str, from: Option<&str>) -> Result<(), String> {
+        let path = self.base.join(file);
+        let from = from.map_or(String::new(), |f| format!("{}: ", f));
+        if self.stack.contains(&path) {
+            let chain = self.stack.iter().chain(Some(&path))
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>().join(" -> ");
+            return Err(format!("{}import cycle: {}", from, chain));
+        }
+        if self.loaded.contains(&path) {
+            return Ok(());
+        }
+        let text = std::fs::read_to_string(&path).map_err(
+            |e| format!("{}can't read {}: {}", from, path.display(), e))?;
+        self.stack.push(path.clone());
+        for (offset, stmt) in statements(&text) {
+            let line = text[..offset].matches('\n').count() + 1;
+            let here = format!("{}:{}", path.display(), line);
+            if let Some(import) = import_path(stmt) {
+                let import = import.map_err(|e| format!("{}: {}", here, e))?;
+                self.load(import, Some(&here))?;
+                continue;
+            }
+            ParserBuilder::parse_grammar(GrammarBuilder::default(), stmt)
+                .map_err(|e| format!("{}: {}", here, e))?;
+            // rules for the same head can only live in a single file
+            let head = stmt.split(":=").next().unwrap_or_default().trim();
+            match self.heads.get(head) {
+                Some((first, l)) if *first != path =>
+                    return Err(format!("{}: duplicate rule '{}', first defined at {}:{}",
+                                       here, head, first.display(), l)),
+                Some(_) => (),
+                None => { self.heads.insert(head.to_string(), (path.clone(), line)); },
+            }
+            self.grammar.push_str(stmt);
+            self.grammar.push('\n');
+        }
+        self.stack.pop();
+        self.loaded.push(path);
+        Ok(())
+    }
+}
+
+impl ParserBuilder {
+    // Read a grammar file resolving `@import "other.ebnf";` directives.
+    // Imported files are looked up relative to `base` and included once.
+    pub fn load_grammar<P: AsRef<Path>>(base: P, file: &str) -> Result<String, String> {
+        let mut loader = Loader {
+            base: base.as_ref(),
+            stack: Vec::new(),
+            loaded: Vec::new(),
+            heads: HashMap::new(),
+            grammar: String::new(),
+        };
+        loader.load(file, None)?;
+        Ok(loader.grammar)
+    }
+
+    // Build a parser for a grammar split across multiple files
+    pub fn from_files<P: AsRef<Path>>(self, base: P, file: &str, start: &str)
+            -> Result<EarleyParser, String> {
+        let grammar = ParserBuilder::load_grammar(base, file)?;
+        self.into_parser(start, &grammar)
+    }
+}
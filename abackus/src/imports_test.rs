@@ -0,0 +1,53 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use crate::imports::statements;
+
+fn fixtures() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests")
+}
+
+#[test]
+fn split_statements() {
+    let g = "a := 'x;y' b ; # c := d;\n  b := \"#\" ;\n@import \"c\";";
+    assert_eq!(statements(g), vec![
+        (0, "a := 'x;y' b ;"),
+        (27, "b := \"#\" ;"),
+        (38, "@import \"c\";"),
+    ]);
+}
+
+#[test]
+fn load_with_imports() {
+    // units.ebnf is imported twice but only included once
+    let grammar = ParserBuilder::load_grammar(fixtures(), "time.ebnf").unwrap();
+    assert_eq!(grammar, concat!(
+        "unit := 'h' | 'm' | 's' ;\n",
+        "num := num digit | digit ;\n",
+        "digit := '0' | '1' | '2' ;\n",
+        "duration := num unit | num ;\n"));
+
+    let parser = ParserBuilder::default()
+        .from_files(fixtures(), "time.ebnf", "duration")
+        .unwrap();
+    assert!(parser.parse("1 0 h".split_whitespace()).is_ok());
+    assert!(parser.parse("2 1".split_whitespace()).is_ok());
+    assert!(parser.parse("h 1".split_whitespace()).is_err());
+}
+
+#[test]
+fn import_errors() {
+    let err = ParserBuilder::load_grammar(fixtures(), "cycle_a.ebnf").unwrap_err();
+    assert!(err.ends_with("cycle_b.ebnf:1: import cycle: {0}/cycle_a.ebnf -> {0}/cycle_b.ebnf -> {0}/cycle_a.ebnf"
+                          .replace("{0}", &fixtures().display().to_string()).as_str()), "{}", err);
+
+    let err = ParserBuilder::load_grammar(fixtures(), "dup.ebnf").unwrap_err();
+    assert!(err.ends_with("dup.ebnf:3: duplicate rule 'unit', first defined at {}/units.ebnf:1"
+                          .replace("{}", &fixtures().display().to_string()).as_str()), "{}", err);
+
+    let err = ParserBuilder::load_grammar(fixtures(), "broken.ebnf").unwrap_err();
+    assert!(err.contains("broken.ebnf:4: Failed to parse user grammar"), "{}", err);
+
+    let err = ParserBuilder::load_grammar(fixtures(), "missing.ebnf").unwrap_err();
+    assert!(err.contains("missing.ebnf:2: can't read"), "{}", err);
+}
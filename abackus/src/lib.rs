@@ -2,8 +2,44 @@
 
 mod ebnf;
 mod treeficator;
+mod imports;
+mod format;
+mod lint;
+mod tokenizer;
+mod generator;
+mod ambiguity;
+mod dialect;
+mod from_tree;
+mod harness;
 pub use crate::ebnf::ParserBuilder;
 pub use crate::treeficator::{Tree, Sexpr};
+pub use crate::lint::Lint;
+pub use crate::tokenizer::{GrammarTokenizer, StrParser};
+pub use crate::generator::Generator;
+pub use crate::ambiguity::Divergence;
+pub use crate::dialect::Dialect;
+pub use crate::from_tree::FromTree;
+pub use crate::harness::{Expect, GrammarTests, TestCase, TestFailure, TestReport};
+#[doc(hidden)]
+pub use crate::imports::statements;
 
 #[cfg(test)]
 mod ebnf_test;
+#[cfg(test)]
+mod imports_test;
+#[cfg(test)]
+mod format_test;
+#[cfg(test)]
+mod lint_test;
+#[cfg(test)]
+mod tokenizer_test;
+#[cfg(test)]
+mod generator_test;
+#[cfg(test)]
+mod ambiguity_test;
+#[cfg(test)]
+mod dialect_test;
+#[cfg(test)]
+mod from_tree_test;
+#[cfg(test)]
+mod harness_test;
@@ -0,0 +1,125 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use crate::treeficator::Tree;
+use earlgrey::GrammarBuilder;
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Lint {
+    // A name without rules that isn't plugged as a terminal
+    Unplugged(String),
+    // A terminal plugged into the builder that the grammar never uses
+    UnusedTerminal(String),
+    // A rule head that can't be reached from the start symbol
+    Unreachable(String),
+    // Input with multiple parses and the rules that differ across them
+    Ambiguous { input: String, trees: usize, rules: Vec<String> },
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Lint::Unplugged(name) =>
+                write!(f, "'{}' has no rules and isn't plugged as a terminal", name),
+            Lint::UnusedTerminal(name) =>
+                write!(f, "terminal '{}' is never used", name),
+            Lint::Unreachable(head) =>
+                write!(f, "rule '{}' is unreachable from the start symbol", head),
+            Lint::Ambiguous { input, trees, rules } if rules.is_empty() =>
+                write!(f, "'{}' has {} parses, same rules nested differently",
+                       input, trees),
+            Lint::Ambiguous { input, trees, rules } =>
+                write!(f, "'{}' has {} parses, competing rules: {}",
+                       input, trees, rules.join(", ")),
+        }
+    }
+}
+
+fn collect_rules(tree: &Tree, rules: &mut HashSet<String>) {
+    if let Tree::Node(rule, subtrees) = tree {
+        rules.insert(rule.clone());
+        for t in subtrees {
+            collect_rules(t, rules);
+        }
+    }
+}
+
+impl Lint {
+    // Report an ambiguity if there's more than one tree for an input.
+    // Competing rules are those used by some trees but not by all of them.
+    pub fn ambiguity(input: &str, trees: &[Tree]) -> Option<Lint> {
+        if trees.len() < 2 {
+            return None;
+        }
+        let per_tree: Vec<HashSet<String>> = trees.iter().map(|t| {
+            let mut rules = HashSet::new();
+            collect_rules(t, &mut rules);
+            rules
+        }).collect();
+        let mut rules: Vec<String> = per_tree.iter()
+            .flat_map(|r| r.iter())
+            .filter(|r| !per_tree.iter().all(|t| t.contains(*r)))
+            .cloned()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        rules.sort();
+        Some(Lint::Ambiguous { input: input.to_string(), trees: trees.len(), rules })
+    }
+}
+
+impl ParserBuilder {
+    // Static checks on a grammar. `terminals` are the names that will
+    // be plugged into the builder before building the parser.
+    pub fn check_grammar(grammar: &str, start: &str, terminals: &[&str])
+            -> Result<Vec<Lint>, String> {
+        let grammar = ParserBuilder::parse_grammar(GrammarBuilder::default(), grammar)?
+            .into_grammar(start)?;
+        let heads: HashSet<&str> = grammar.rules.iter()
+            .map(|r| r.head.as_str()).collect();
+        let mut lints = Vec::new();
+        let mut used = Vec::new();
+        for symbol in grammar.rules.iter().flat_map(|r| r.spec.iter()) {
+            if let Some(name) = symbol.nonterm() {
+                if !used.contains(&name) {
+                    used.push(name);
+                }
+            }
+        }
+        for name in &used {
+            if !heads.contains(name) && !terminals.contains(name) {
+                lints.push(Lint::Unplugged(name.to_string()));
+            }
+        }
+        for name in terminals {
+            if !used.contains(name) {
+                lints.push(Lint::UnusedTerminal(name.to_string()));
+            }
+        }
+        // Walk rules from the start symbol to find unreachable ones
+        let mut reached = HashSet::from([grammar.start.as_str()]);
+        let mut pending = vec![grammar.start.as_str()];
+        while let Some(head) = pending.pop() {
+            for rule in grammar.rules.iter().filter(|r| r.head == head) {
+                for name in rule.spec.iter().filter_map(|s| s.nonterm()) {
+                    if reached.insert(name) {
+                        pending.push(name);
+                    }
+                }
+            }
+        }
+        let mut unreachable = Vec::new();
+        for rule in &grammar.rules {
+            // auxiliary and tagged rules are reported through their parent
+            let head = rule.head.as_str();
+            if !reached.contains(head) && !head.starts_with(['<', '@'])
+                    && !unreachable.contains(&head) {
+                unreachable.push(head);
+                lints.push(Lint::Unreachable(head.to_string()));
+            }
+        }
+        Ok(lints)
+    }
+}
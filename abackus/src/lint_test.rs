@@ -0,0 +1,54 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use crate::lint::Lint;
+
+#[test]
+fn static_checks() {
+    let g = r#"
+        expr := expr '+' term | term ;
+        term := num | '(' expr ')' | vaiable ;
+        unused := expr ['x'] @tag ;
+    "#;
+    let lints = ParserBuilder::check_grammar(g, "expr", &["num", "variable"]).unwrap();
+    assert_eq!(lints, vec![
+        Lint::Unplugged("vaiable".to_string()),
+        Lint::UnusedTerminal("variable".to_string()),
+        Lint::Unreachable("unused".to_string()),
+    ]);
+    assert_eq!(lints[0].to_string(),
+               "'vaiable' has no rules and isn't plugged as a terminal");
+    let clean = ParserBuilder::check_grammar(g, "unused", &["num", "vaiable"]).unwrap();
+    assert!(clean.is_empty(), "{:?}", clean);
+}
+
+#[test]
+fn ambiguity() {
+    let g = r#"
+        expr := expr '+' expr | num ;
+    "#;
+    let parser = ParserBuilder::default()
+        .plug_terminal("num", |n| n.parse::<u32>().is_ok())
+        .treeficator(g, "expr");
+    let trees = parser("1 + 2".split_whitespace()).unwrap();
+    assert_eq!(Lint::ambiguity("1 + 2", &trees), None);
+
+    let trees = parser("1 + 2 + 3".split_whitespace()).unwrap();
+    // both trees use the same rules, only nesting differs
+    let lint = Lint::ambiguity("1 + 2 + 3", &trees).unwrap();
+    assert_eq!(lint, Lint::Ambiguous {
+        input: "1 + 2 + 3".to_string(), trees: 2, rules: Vec::new()
+    });
+    assert_eq!(lint.to_string(), "'1 + 2 + 3' has 2 parses, same rules nested differently");
+
+    let g = r#"
+        s := a | b ;
+        a := 'x' ;
+        b := 'x' ;
+    "#;
+    let parser = ParserBuilder::default().treeficator(g, "s");
+    let trees = parser(["x"].iter()).unwrap();
+    let lint = Lint::ambiguity("x", &trees).unwrap();
+    assert_eq!(lint.to_string(),
+               "'x' has 2 parses, competing rules: a -> x, b -> x, s -> a, s -> b");
+}
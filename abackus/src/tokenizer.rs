@@ -0,0 +1,115 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use earlgrey::{EarleyParser, Grammar, ParseTrees};
+
+/// Longest-match tokenizer derived from the terminals of a grammar.
+/// Literals (eg: 'o''clock') match their own text, even across spaces.
+/// Plugged terminals match the longest whitespace-free run they accept,
+/// up to a literal starting with punctuation.
+#[derive(Clone, Debug)]
+pub struct GrammarTokenizer {
+    grammar: Grammar,
+    // length in chars and position (rule, symbol) of each literal, sorted
+    // longest first
+    literals: Vec<(usize, (usize, usize))>,
+    // position (rule, symbol) of each plugged terminal in the grammar
+    plugged: Vec<(usize, usize)>,
+}
+
+impl GrammarTokenizer {
+    pub fn new(grammar: &Grammar) -> Self {
+        let mut names = Vec::new();
+        let mut literals = Vec::new();
+        let mut plugged = Vec::new();
+        for (r, rule) in grammar.rules.iter().enumerate() {
+            for (s, symbol) in rule.spec.iter().enumerate() {
+                let name = match symbol.terminal() {
+                    Some((name, _)) if !names.contains(&name) => name,
+                    _ => continue,
+                };
+                names.push(name);
+                // literals were recorded when lowering the grammar
+                match grammar.literal(name) {
+                    Some(text) => literals.push((text.chars().count(), (r, s))),
+                    None => plugged.push((r, s)),
+                }
+            }
+        }
+        literals.sort_by_key(|&(len, _)| std::cmp::Reverse(len));
+        GrammarTokenizer { grammar: grammar.clone(), literals, plugged }
+    }
+
+    fn pred(&self, (r, s): (usize, usize)) -> &dyn Fn(&str) -> bool {
+        let (_, pred) = self.grammar.rules[r].spec[s].terminal()
+            .expect("BUG: tokenizer symbol isn't a terminal");
+        pred
+    }
+
+    fn accepts(&self, lexeme: &str) -> bool {
+        self.plugged.iter().any(|&at| self.pred(at)(lexeme))
+    }
+
+    // Length of the longest literal at the start of input, literals are
+    // checked with their predicate (eg: case insensitive ones)
+    fn literal_len(&self, input: &str) -> Option<usize> {
+        self.literals.iter().find_map(|&(len, at)| {
+            let end = input.char_indices().nth(len).map_or(input.len(), |(idx, _)| idx);
+            Some(end).filter(|&end| self.pred(at)(&input[..end]))
+        })
+    }
+
+    // Length of the token at the start of input. Falls back to the whole
+    // whitespace-free run so the parser can report it as unexpected.
+    fn token_len(&self, input: &str) -> usize {
+        let run = input.find(char::is_whitespace).unwrap_or(input.len());
+        let literal = self.literal_len(input);
+        // plugged terminals stop at punctuation literals, eg: 1+2
+        let stop = input[..run].char_indices()
+            .find(|&(idx, c)| !c.is_alphanumeric() &&
+                  self.literal_len(&input[idx..]).is_some())
+            .map_or(run, |(idx, _)| idx);
+        let plugged = input[..stop].char_indices().rev()
+            .map(|(idx, c)| idx + c.len_utf8())
+            .find(|&end| self.accepts(&input[..end]));
+        match (literal, plugged) {
+            (None, None) => run,
+            (l, p) => l.max(p).unwrap(),
+        }
+    }
+
+    pub fn tokenize<'a>(&'a self, input: &'a str) -> impl Iterator<Item=&'a str> + 'a {
+        let mut rest = input;
+        std::iter::from_fn(move || {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                return None;
+            }
+            let (token, tail) = rest.split_at(self.token_len(rest));
+            rest = tail;
+            Some(token)
+        })
+    }
+}
+
+/// A parser bundled with the tokenizer derived from its grammar
+pub struct StrParser {
+    pub parser: EarleyParser,
+    pub tokenizer: GrammarTokenizer,
+}
+
+impl StrParser {
+    pub fn parse_str(&self, input: &str) -> Result<ParseTrees, String> {
+        self.parser.parse(self.tokenizer.tokenize(input))
+    }
+}
+
+impl ParserBuilder {
+    // Build a parser that tokenizes input using the grammar's own terminals
+    pub fn into_str_parser(self, start: &str, grammar: &str)
+            -> Result<StrParser, String> {
+        let parser = self.into_parser(start, grammar)?;
+        let tokenizer = GrammarTokenizer::new(&parser.grammar);
+        Ok(StrParser { parser, tokenizer })
+    }
+}
@@ -0,0 +1,65 @@
+#![deny(warnings)]
+
+use crate::ebnf::ParserBuilder;
+use crate::tokenizer::GrammarTokenizer;
+
+fn time_parser() -> crate::tokenizer::StrParser {
+    let g = r#"
+        time := hour 'pm' | hour "o'clock" | 'at' time | day ;
+        day := 'the day after' 'tomorrow' | 'today' ;
+    "#;
+    ParserBuilder::default()
+        .plug_terminal("hour", |h| matches!(h.parse::<u32>(), Ok(h) if h < 13))
+        .into_str_parser("time", g)
+        .unwrap()
+}
+
+#[test]
+fn longest_match() {
+    let p = time_parser();
+    let tokens = |input| p.tokenizer.tokenize(input).collect::<Vec<_>>();
+    assert_eq!(tokens("at 3pm"), vec!["at", "3", "pm"]);
+    assert_eq!(tokens("  at   12o'clock "), vec!["at", "12", "o'clock"]);
+    assert_eq!(tokens("the day after tomorrow"), vec!["the day after", "tomorrow"]);
+    // 13 isn't an hour but 1 and 3 are
+    assert_eq!(tokens("13pm"), vec!["1", "3", "pm"]);
+    // unknown runs are left whole for the parser to reject
+    assert_eq!(tokens("3xy pm"), vec!["3", "xy", "pm"]);
+    assert_eq!(tokens(""), Vec::<&str>::new());
+}
+
+#[test]
+fn parse_str() {
+    let p = time_parser();
+    assert!(p.parse_str("at 3pm").is_ok());
+    assert!(p.parse_str("at at 11 o'clock").is_ok());
+    assert!(p.parse_str("the day after tomorrow").is_ok());
+    assert!(p.parse_str("13pm").is_err());
+    assert!(p.parse_str("3xy pm").is_err());
+}
+
+#[test]
+fn tokenizer_from_grammar() {
+    let parser = ParserBuilder::default()
+        .plug_terminal("num", |n| n.chars().all(|c| c.is_ascii_digit()))
+        .into_parser("expr", "expr := expr ('+' | '-' | '--') num | num ;")
+        .unwrap();
+    let tokenizer = GrammarTokenizer::new(&parser.grammar);
+    let tokens: Vec<_> = tokenizer.tokenize("1+22--3 - 4").collect();
+    assert_eq!(tokens, vec!["1", "+", "22", "--", "3", "-", "4"]);
+    assert!(parser.parse(tokenizer.tokenize("1+22--3 - 4")).is_ok());
+}
+
+#[test]
+fn plugged_terminals_matching_their_name() {
+    // 'word' and 'num' accept their own names, they're still plugged
+    let p = ParserBuilder::default()
+        .plug_terminal("word", |w| w.chars().all(char::is_alphabetic))
+        .plug_terminal("num", |_| true)
+        .into_str_parser("s", "s := word '+' word | num ;")
+        .unwrap();
+    let tokens: Vec<_> = p.tokenizer.tokenize("abc+def").collect();
+    assert_eq!(tokens, vec!["abc", "+", "def"]);
+    assert!(p.parse_str("abc+def").is_ok());
+    assert!(p.parse_str("word + word").is_ok());
+}
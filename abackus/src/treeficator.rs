@@ -1,7 +1,7 @@
 #![deny(warnings)]
 
 use crate::ebnf::ParserBuilder;
-use earlgrey::{EarleyParser, EarleyForest};
+use earlgrey::{EarleyParser, EarleyForest, Grammar};
 use std::fmt::Debug;
 
 #[derive(Clone,Debug)]
@@ -30,6 +30,9 @@ impl Sexpr {
         match *self {
             Sexpr::Atom(ref lexeme) =>
                 *out += &format!("\u{2500} {}\n", lexeme),
+            // empty rules (eg: optionals that matched nothing)
+            Sexpr::List(ref subn) if subn.is_empty() =>
+                *out += "\u{2500} ()\n",
             Sexpr::List(ref subn) => {
                 let (first, rest) = subn.split_first().unwrap();
                 let (last, rest) = rest.split_last().unwrap();
@@ -46,6 +49,17 @@ impl Sexpr {
     }
 }
 
+// Evaler that builds trees when executing semantic actions
+pub(crate) fn tree_builder(grammar: &Grammar) -> EarleyForest<'static, Tree> {
+    let mut tree_builder = EarleyForest::new(
+        |sym, tok| Tree::Leaf(sym.to_string(), tok.to_string()));
+    for rule in grammar.rules.iter().map(|r| r.to_string()) {
+        tree_builder.action(
+            &rule.clone(), move |nodes| Tree::Node(rule.clone(), nodes));
+    }
+    tree_builder
+}
+
 impl ParserBuilder {
     pub fn treeficator<SI>(self, grammar: &str, start: &str)
         -> impl Fn(SI) -> Result<Vec<Tree>, String>
@@ -53,17 +67,12 @@ impl ParserBuilder {
     {
         // User may pre-plug grammar (self.0) with terminals
         // 1. build a parser for user's grammar
-        let grammar = ParserBuilder::parse_grammar(self.0, grammar)
+        let grammar = self.into_builder(grammar)
             .unwrap_or_else(|e| panic!("treeficator error: {:?}", e))
             .into_grammar(start)
             .unwrap_or_else(|e| panic!("treeficator error: {:?}", e));
         // 2. build evaler that builds trees when executing semantic actions
-        let mut tree_builder = EarleyForest::new(
-            |sym, tok| Tree::Leaf(sym.to_string(), tok.to_string()));
-        for rule in grammar.rules.iter().map(|r| r.to_string()) {
-            tree_builder.action(
-                &rule.clone(), move |nodes| Tree::Node(rule.clone(), nodes));
-        }
+        let tree_builder = tree_builder(&grammar);
         // 3. make function that parses strings into trees
         let parser = EarleyParser::new(grammar);
         move |tokenizer| tree_builder.eval_all(&parser.parse(tokenizer)?)
@@ -75,7 +84,7 @@ impl ParserBuilder {
     {
         // User may pre-plug grammar (self.0) with terminals
         // 1. build a parser for user's grammar
-        let grammar = ParserBuilder::parse_grammar(self.0, grammar)
+        let grammar = self.into_builder(grammar)
             .unwrap_or_else(|e| panic!("treeficator error: {:?}", e))
             .into_grammar(start)
             .unwrap_or_else(|e| panic!("treeficator error: {:?}", e));
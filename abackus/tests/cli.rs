@@ -0,0 +1,63 @@
+use std::process::{Command, Output};
+
+// Run the abackus binary from the crate's directory
+fn abackus(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_abackus"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .output()
+        .expect("failed to run abackus")
+}
+
+fn stdout(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stdout).to_string()
+}
+
+fn stderr(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stderr).to_string()
+}
+
+#[test]
+fn parse_with_plugged_terminal() {
+    let out = abackus(&["parse", "src/tests/arith.ebnf", "expr", "-t", "num", "1+2^3"]);
+    assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+    // 'num' is a token class, the input is split at the literals
+    let leaves: Vec<_> = stdout(&out).lines()
+        .filter_map(|l| l.rsplit_once("─ ").map(|(_, leaf)| leaf.to_string()))
+        .filter(|leaf| leaf != "()")
+        .collect();
+    assert_eq!(leaves, vec!["1", "+", "2", "^", "3"]);
+    let out = abackus(&["parse", "src/tests/arith.ebnf", "expr", "-t", "num=1,2", "1+3"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout(&out).starts_with("1+3: "), "{}", stdout(&out));
+}
+
+#[test]
+fn check_grammars() {
+    let out = abackus(&["check", "src/tests/arith.ebnf", "expr", "-t", "num"]);
+    assert_eq!(out.status.code(), Some(0), "{}", stdout(&out));
+    let out = abackus(&["check", "src/tests/broken.ebnf", "num"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(stderr(&out).starts_with("src/tests/broken.ebnf:4: Failed to parse user grammar"),
+            "{}", stderr(&out));
+}
+
+#[test]
+fn dialects_only_where_supported() {
+    for cmd in [&["check", "src/tests/arith.ebnf", "expr"][..], &["fmt", "src/tests/arith.ebnf"]] {
+        let out = abackus(&[cmd, &["-d", "w3c"]].concat());
+        assert_eq!(out.status.code(), Some(2));
+        assert!(stderr(&out).starts_with(&format!("-d isn't supported by {}", cmd[0])));
+    }
+}
+
+#[test]
+fn fmt_test_and_gen() {
+    let out = abackus(&["fmt", "src/tests/arith.ebnf"]);
+    assert_eq!(out.status.code(), Some(0));
+    assert!(stdout(&out).contains("expr := expr ('+' | '-') term | term ;\n"));
+    let out = abackus(&["test", "src/tests/arith.tests", "-t", "num"]);
+    assert_eq!(stdout(&out), "4 passed, 0 failed\n");
+    let out = abackus(&["gen", "src/tests/arith.ebnf", "expr", "-t", "num=1", "-a", "1"]);
+    assert_eq!(stdout(&out), "1\n");
+}
@@ -98,6 +98,16 @@ impl Rule {
 pub struct Grammar {
     pub start: String,
     pub rules: Vec<Rc<Rule>>,
+    // spelling of literal terminals by name
+    literals: HashMap<String, String>,
+}
+
+impl Grammar {
+    /// Spelling of a literal terminal (eg: 'if' in a grammar), None for
+    /// other terminals and non-terminals
+    pub fn literal(&self, name: &str) -> Option<&str> {
+        self.literals.get(name).map(|text| text.as_str())
+    }
 }
 
 
@@ -106,6 +116,7 @@ pub struct Grammar {
 pub struct GrammarBuilder {
     symbols: HashMap<String, Rc<Symbol>>,
     rules: Vec<Rc<Rule>>,
+    literals: HashMap<String, String>,
     error: Option<String>,
 }
 
@@ -147,6 +158,20 @@ impl GrammarBuilder {
         self._add_symbol(Symbol(name.into(), Some(Box::new(pred))), true)
     }
 
+    // Literal terminals also keep their spelling, it may differ from the
+    // name (eg: quoted to not clash with a rule). Pre-existent symbols
+    // aren't literals, eg: a plugged terminal with the same name.
+    pub fn quiet_literal<S, P>(&mut self, name: S, text: &str, pred: P)
+        where S: Into<String>,
+              P: 'static + Fn(&str) -> bool,
+    {
+        let name = name.into();
+        if !self.symbols.contains_key(&name) {
+            self.literals.insert(name.clone(), text.to_string());
+            self.quiet_terminal(name, pred);
+        }
+    }
+
     /// Register new rules for the grammar
     fn _add_rule<S, S2>(&mut self, head: S, spec: &[S2], quiet: bool)
         where S: AsRef<str>, S2: AsRef<str>
@@ -195,7 +220,8 @@ impl GrammarBuilder {
         if !self.symbols.contains_key(&start) {
             self.error = Some(format!("Missing Symbol: {}", start));
         }
-        self.error.map_or(Ok(Grammar{start, rules: self.rules}), Err)
+        let literals = self.literals;
+        self.error.map_or(Ok(Grammar{start, rules: self.rules, literals}), Err)
     }
 
     /// Generate unique name for a Symbol (used to build grammar mechanically)
@@ -250,6 +276,20 @@ mod tests {
         assert!(g.is_ok());
     }
 
+    #[test]
+    fn literals() {
+        let mut gb = GrammarBuilder::default()
+            .nonterm("S")
+            .terminal("word", |n| n.chars().all(char::is_alphabetic));
+        gb.quiet_literal("\"S\"", "S", |n| n == "S");
+        gb.quiet_literal("word", "word", |n| n == "word");
+        let g = gb.rule("S", &["word", "\"S\""]).into_grammar("S").unwrap();
+        assert_eq!(g.literal("\"S\""), Some("S"));
+        // plugged terminals aren't literals even if they accept their name
+        assert_eq!(g.literal("word"), None);
+        assert_eq!(g.literal("S"), None);
+    }
+
     #[test]
     fn dup_symbol() {
         let g = GrammarBuilder::default()
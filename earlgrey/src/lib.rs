@@ -5,7 +5,7 @@ pub use grammar::{GrammarBuilder, Grammar};
 
 mod items;
 mod parser;
-pub use parser::EarleyParser;
+pub use parser::{EarleyParser, ParseTrees};
 
 mod trees;
 pub use trees::EarleyForest;
@@ -0,0 +1,32 @@
+// Compare tokenizing over char iterators against the zero-copy &str path.
+// Run with: cargo bench --bench tokenize
+use lexers::{LispTokenizer, MathTokenizer, StrLispTokenizer, StrMathTokenizer};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+fn bench(name: &str, input: &str, tokenize: impl Fn(&str) -> usize) -> Duration {
+    let rounds = 20;
+    let mut tokens = 0;
+    let start = Instant::now();
+    for _ in 0..rounds {
+        tokens = black_box(tokenize(black_box(input)));
+    }
+    let elapsed = start.elapsed() / rounds;
+    let mb_per_sec = input.len() as f64 / elapsed.as_secs_f64() / 1e6;
+    println!("{:<16} {:>8} tokens {:>10.2?}/iter {:>8.1} MB/s",
+             name, tokens, elapsed, mb_per_sec);
+    elapsed
+}
+
+fn main() {
+    let math = "3.4e-2 * sin(x)/(7! % -4) * max(2, x) + 30 km / (10 s) - alpha^2\n".repeat(10_000);
+    let chars = bench("math chars", &math, |s| MathTokenizer::new(s.chars()).count());
+    let slices = bench("math &str", &math, |s| StrMathTokenizer::new(s).count());
+    println!("math speedup: {:.2}x\n", chars.as_secs_f64() / slices.as_secs_f64());
+
+    let lisp = "(define (fact n) (if (< n 2) 1 (* n (fact (- n 1))))) \"some string\"\n"
+        .repeat(10_000);
+    let chars = bench("lisp chars", &lisp, |s| LispTokenizer::new(s.chars()).count());
+    let slices = bench("lisp &str", &lisp, |s| StrLispTokenizer::new(s).count());
+    println!("lisp speedup: {:.2}x", chars.as_secs_f64() / slices.as_secs_f64());
+}
@@ -0,0 +1,87 @@
+#![deny(warnings)]
+
+use crate::helpers::{is_space, Cursor};
+use crate::scanner::Scanner;
+use crate::str_scanner::StrScanner;
+
+/// Comment syntax for `skip_comments`: line comments run up to the end of
+/// the line and block comments nest, eg: `#| a #| b |# c |#`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Comments {
+    line: Vec<String>,
+    block: Vec<(String, String)>,
+}
+
+impl Comments {
+    // No comments at all, only whitespace is skipped
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Add a line comment, eg: "//"
+    pub fn line(mut self, start: &str) -> Self {
+        self.line.push(start.to_string());
+        self
+    }
+
+    // Add a nested block comment, eg: ("/*", "*/")
+    pub fn block(mut self, open: &str, close: &str) -> Self {
+        self.block.push((open.to_string(), close.to_string()));
+        self
+    }
+
+    // ; line comments and #| block comments |#
+    pub fn lisp() -> Self {
+        Self::new().line(";").block("#|", "|#")
+    }
+
+    // # and // line comments
+    pub fn math() -> Self {
+        Self::new().line("#").line("//")
+    }
+}
+
+// Skip to the matching 'close' (or EOF) after an 'open'
+fn skip_block<S: Cursor>(s: &mut S, open: &str, close: &str) {
+    let mut depth = 1;
+    while depth > 0 {
+        if s.accept_all(close.chars()) {
+            depth -= 1;
+        } else if s.accept_all(open.chars()) {
+            depth += 1;
+        } else if s.next_char().is_none() {
+            return;
+        }
+    }
+}
+
+// Skip whitespace and comments, result is what was skipped
+pub(crate) fn skip_comments<S: Cursor>(s: &mut S, comments: &Comments) -> S::Lexeme {
+    let unicode = s.unicode();
+    loop {
+        s.skip_while(|c| is_space(unicode, c));
+        // blocks first, their open may start like a line comment
+        if let Some((open, close)) = comments.block.iter()
+                .find(|(open, _)| s.accept_all(open.chars())) {
+            skip_block(s, open, close);
+        } else if comments.line.iter().any(|start| s.accept_all(start.chars())) {
+            s.skip_while(|c| c != '\n');
+        } else {
+            return s.extract_lexeme();
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> Scanner<I> {
+    // Skip whitespace and comments, unterminated block comments run to EOF
+    pub fn skip_comments(&mut self, comments: &Comments) -> String {
+        skip_comments(self, comments)
+    }
+}
+
+impl<'a> StrScanner<'a> {
+    // Skip whitespace and comments, unterminated block comments run to EOF
+    pub fn skip_comments(&mut self, comments: &Comments) -> &'a str {
+        skip_comments(self, comments)
+    }
+}
@@ -0,0 +1,61 @@
+#![deny(warnings)]
+
+use crate::comments::Comments;
+use crate::scanner::Scanner;
+use crate::str_scanner::StrScanner;
+use crate::{LispToken, LispTokenizer, MathToken, MathTokenizer};
+use crate::{StrLispTokenizer, StrMathTokenizer};
+
+#[test]
+fn skip_comments() {
+    let comments = Comments::new().line("//").block("/*", "*/");
+    let input = "  // line\n /* a /* nested */ b */x /* open";
+    let mut s = Scanner::text(input.chars());
+    assert_eq!(s.skip_comments(&comments), "  // line\n /* a /* nested */ b */");
+    assert_eq!(s.next(), Some('x'));
+    s.extract();
+    // unterminated blocks run to EOF
+    assert_eq!(s.skip_comments(&comments), " /* open");
+    assert_eq!(s.next(), None);
+
+    let mut s = StrScanner::new("; one\n;two\n  #| #| |# |# (");
+    assert_eq!(s.skip_comments(&Comments::lisp()), "; one\n;two\n  #| #| |# |# ");
+    assert_eq!(s.skip_comments(&Comments::lisp()), "");
+    // only whitespace without comments
+    let mut s = StrScanner::new(" ; x");
+    assert_eq!(s.skip_comments(&Comments::new()), " ");
+}
+
+#[test]
+fn lisp_comments() {
+    let input = "; script\n(define x #| the\n value |# 2) ; done\n\
+                 (list #;(skip 'me (too)) a #; 'b c #;#;d e f x;y\n #;)";
+    let expected = "(define x 2) (list a c f x)";
+    let tokens: Vec<_> = LispTokenizer::new(input.chars()).collect();
+    assert_eq!(tokens, LispTokenizer::new(expected.chars()).collect::<Vec<_>>());
+    let str_tokens: Vec<_> = StrLispTokenizer::new(input).map(|t| t.into_owned()).collect();
+    assert_eq!(str_tokens, tokens);
+
+    let spans: Vec<_> = LispTokenizer::new("#| x |# a".chars()).with_spans()
+        .map(|t| t.span.to_string())
+        .collect();
+    assert_eq!(spans, vec!["1:9-1:10"]);
+
+    // without comments ; is just another symbol
+    let tokens: Vec<_> = LispTokenizer::new("a ;b".chars()).comments(Comments::new()).collect();
+    assert_eq!(tokens, vec![LispToken::Symbol("a".to_string()), LispToken::Symbol(";b".to_string())]);
+}
+
+#[test]
+fn math_comments() {
+    let input = "# constants\nx := 2 // two\ny := x * 3 # three";
+    let tokens: Vec<_> = MathTokenizer::new(input.chars()).collect();
+    let expected: Vec<_> = MathTokenizer::new("x := 2 y := x * 3".chars()).collect();
+    assert_eq!(tokens, expected);
+    let str_tokens: Vec<_> = StrMathTokenizer::new(input).map(|t| t.into_owned()).collect();
+    assert_eq!(str_tokens, tokens);
+
+    let comments = Comments::new().block("/*", "*/");
+    let tokens: Vec<_> = MathTokenizer::new("1 /* one */ + 2".chars()).comments(comments).collect();
+    assert_eq!(tokens, vec![MathToken::Number(1.0), MathToken::BOp("+".to_string()), MathToken::Number(2.0)]);
+}
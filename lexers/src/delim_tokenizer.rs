@@ -1,20 +1,193 @@
 #![deny(warnings)]
 
+use crate::helpers::Cursor;
 use crate::scanner::Scanner;
+use crate::span::{Span, Spanned, WithSpans};
+use crate::str_scanner::StrScanner;
+
+// What was scanned last, removed delimiters need fields on both sides
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Last {
+    Start,
+    Field,
+    Kept,
+    Removed,
+}
+
+// How to split the input, shared by both tokenizers
+#[derive(Clone, Debug)]
+struct Splitter {
+    // delimiters, longest first, and if they are emitted as tokens
+    delims: Vec<(String, bool)>,
+    remove: bool,
+    merge: bool,
+    quotes: Vec<char>,
+    escape: Option<char>,
+    unquote: bool,
+    last: Last,
+}
+
+impl Splitter {
+    fn new(delims: &str, remove: bool) -> Self {
+        Splitter {
+            delims: delims.chars().map(|d| (d.to_string(), !remove)).collect(),
+            remove,
+            merge: remove,
+            quotes: Vec::new(),
+            escape: None,
+            unquote: false,
+            last: Last::Start,
+        }
+    }
+
+    // split on ',' with "quoted" fields, newlines are kept to end records
+    fn csv() -> Self {
+        let mut csv = Splitter::new(",", true);
+        csv.quotes = vec!['"'];
+        csv.merge = false;
+        csv.unquote = true;
+        csv.add("\r\n", true);
+        csv.add("\n", true);
+        csv
+    }
+
+    fn add(&mut self, delim: &str, keep: bool) {
+        if !delim.is_empty() {
+            self.delims.push((delim.to_string(), keep));
+            self.delims.sort_by_key(|(d, _)| std::cmp::Reverse(d.chars().count()));
+        }
+    }
+
+    // Accept a delimiter, result is if it's kept
+    fn accept_delim<S: Cursor>(&self, src: &mut S) -> Option<bool> {
+        self.delims.iter()
+            .find(|(delim, _)| src.accept_all(delim.chars()))
+            .map(|(_, keep)| *keep)
+    }
+
+    fn skip_escaped<S: Cursor>(&self, src: &mut S, c: char) -> bool {
+        if Some(c) == self.escape {
+            src.next_char();
+            return true;
+        }
+        false
+    }
+
+    // Scan up to the next delimiter, quotes only open at the start of a
+    // field (eg: "a, b") so words like o'clock stay whole
+    fn scan_field<S: Cursor>(&self, src: &mut S) {
+        if let Some(q) = src.accept_any(&self.quotes) {
+            while let Some(c) = src.next_char() {
+                // a doubled quote stands for itself
+                if !self.skip_escaped(src, c) && c == q && src.accept(&q).is_none() {
+                    break;
+                }
+            }
+        }
+        loop {
+            let backtrack = src.mark();
+            if self.accept_delim(src).is_some() {
+                src.reset(backtrack);
+                return;
+            }
+            match src.next_char() {
+                Some(c) => {
+                    self.skip_escaped(src, c);
+                }
+                None => return,
+            }
+        }
+    }
+
+    // Drop quotes and escapes from a field
+    fn unquote(&self, field: &str) -> String {
+        let mut chars = field.chars().peekable();
+        let quote = chars.next_if(|c| self.quotes.contains(c));
+        let mut quoted = quote.is_some();
+        let mut value = String::new();
+        while let Some(c) = chars.next() {
+            if Some(c) == self.escape {
+                value.extend(chars.next());
+            } else if quoted && Some(c) == quote {
+                match chars.next_if_eq(&c) {
+                    Some(c) => value.push(c),
+                    None => quoted = false,
+                }
+            } else {
+                value.push(c);
+            }
+        }
+        value
+    }
+}
 
 // A tokenizer that splits input on each delimiter
 pub struct DelimTokenizer<I: Iterator<Item = char>> {
     src: Scanner<I>,
-    delims: Vec<char>,
-    remove: bool, // drop the delimiters ?
+    split: Splitter,
 }
 
 impl<I: Iterator<Item = char>> DelimTokenizer<I> {
+    // Split on any char in 'delims', dropping them if 'remove' is set
     pub fn new(src: I, delims: &str, remove: bool) -> Self {
         DelimTokenizer {
-            src: Scanner::new(src),
-            delims: delims.chars().collect(),
-            remove,
+            src: Scanner::text(src),
+            split: Splitter::new(delims, remove),
+        }
+    }
+
+    // Split fields on ',' where "quoted, fields" may hold commas, newlines
+    // and "" for a quote. Fields can be empty, "\n" tokens end records
+    pub fn csv(src: I) -> Self {
+        DelimTokenizer {
+            src: Scanner::text(src),
+            split: Splitter::csv(),
+        }
+    }
+
+    // Add a delimiter, eg: "::", removed or kept like the others
+    pub fn delim(mut self, delim: &str) -> Self {
+        let keep = !self.split.remove;
+        self.split.add(delim, keep);
+        self
+    }
+
+    // Take runs of delimiters as one. On by default when removing them,
+    // off otherwise. Without merging removed delimiters separate fields
+    // that may be empty, eg: "a,,b" is "a", "", "b"
+    pub fn merge(mut self, merge: bool) -> Self {
+        self.split.merge = merge;
+        self
+    }
+
+    // Quote chars, delimiters inside a quoted field don't split it
+    pub fn quotes(mut self, quotes: &str) -> Self {
+        self.split.quotes = quotes.chars().collect();
+        self
+    }
+
+    // Escape char, the char after it never splits or closes a quote
+    pub fn escape(mut self, escape: char) -> Self {
+        self.split.escape = Some(escape);
+        self
+    }
+
+    // Drop quotes and escapes from fields
+    pub fn unquote(mut self, unquote: bool) -> Self {
+        self.split.unquote = unquote;
+        self
+    }
+
+    // Emit tokens along with their position in the source
+    pub fn with_spans(self) -> WithSpans<Self> {
+        WithSpans(self)
+    }
+
+    fn next_unquoted(&mut self) -> Option<Spanned<String>> {
+        let token = next_spanned(&mut self.src, &mut self.split)?;
+        match self.split.unquote && self.split.last == Last::Field {
+            true => Some(token.map(|field| self.split.unquote(&field))),
+            false => Some(token),
         }
     }
 }
@@ -22,18 +195,125 @@ impl<I: Iterator<Item = char>> DelimTokenizer<I> {
 impl<I: Iterator<Item = char>> Iterator for DelimTokenizer<I> {
     type Item = String;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.src.until_any(&self.delims) {
-            Some(self.src.extract_string())
-        } else if let Some(c) = self.src.accept_any(&self.delims) {
-            self.src.extract(); // ignore
-            if self.remove {
-                self.next()
+        self.next_unquoted().map(|t| t.value)
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for WithSpans<DelimTokenizer<I>> {
+    type Item = Spanned<String>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_unquoted()
+    }
+}
+
+// A DelimTokenizer over a &str, tokens are slices of the source so quoted
+// fields keep their quotes and escapes
+pub struct StrDelimTokenizer<'a> {
+    src: StrScanner<'a>,
+    split: Splitter,
+}
+
+impl<'a> StrDelimTokenizer<'a> {
+    pub fn new(src: &'a str, delims: &str, remove: bool) -> Self {
+        StrDelimTokenizer {
+            src: StrScanner::new(src),
+            split: Splitter::new(delims, remove),
+        }
+    }
+
+    // see DelimTokenizer::csv
+    pub fn csv(src: &'a str) -> Self {
+        StrDelimTokenizer {
+            src: StrScanner::new(src),
+            split: Splitter::csv(),
+        }
+    }
+
+    // see DelimTokenizer::delim
+    pub fn delim(mut self, delim: &str) -> Self {
+        let keep = !self.split.remove;
+        self.split.add(delim, keep);
+        self
+    }
+
+    // see DelimTokenizer::merge
+    pub fn merge(mut self, merge: bool) -> Self {
+        self.split.merge = merge;
+        self
+    }
+
+    // see DelimTokenizer::quotes
+    pub fn quotes(mut self, quotes: &str) -> Self {
+        self.split.quotes = quotes.chars().collect();
+        self
+    }
+
+    // see DelimTokenizer::escape
+    pub fn escape(mut self, escape: char) -> Self {
+        self.split.escape = Some(escape);
+        self
+    }
+
+    // Emit tokens along with their position in the source
+    pub fn with_spans(self) -> WithSpans<Self> {
+        WithSpans(self)
+    }
+}
+
+impl<'a> Iterator for StrDelimTokenizer<'a> {
+    type Item = &'a str;
+    fn next(&mut self) -> Option<Self::Item> {
+        next_spanned(&mut self.src, &mut self.split).map(|t| t.value)
+    }
+}
+
+impl<'a> Iterator for WithSpans<StrDelimTokenizer<'a>> {
+    type Item = Spanned<&'a str>;
+    fn next(&mut self) -> Option<Self::Item> {
+        next_spanned(&mut self.0.src, &mut self.0.split)
+    }
+}
+
+fn next_spanned<S: Cursor>(src: &mut S, split: &mut Splitter) -> Option<Spanned<S::Lexeme>> {
+    loop {
+        let start = src.location();
+        let backtrack = src.mark();
+        let token = if let Some(mut keep) = split.accept_delim(src) {
+            let after_field = match keep {
+                true => split.last != Last::Removed,
+                false => split.last == Last::Field,
+            };
+            if !split.merge && !after_field {
+                // an empty field before this delimiter
+                src.reset(backtrack);
+                split.last = Last::Field;
+                src.extract_lexeme()
             } else {
-                Some(c.to_string())
+                if split.merge {
+                    while let Some(k) = split.accept_delim(src) {
+                        keep |= k;
+                    }
+                }
+                let delim = src.extract_lexeme();
+                if !keep {
+                    split.last = Last::Removed;
+                    continue;
+                }
+                split.last = Last::Kept;
+                delim
             }
+        } else if src.peek().is_some() {
+            split.scan_field(src);
+            split.last = Last::Field;
+            src.extract_lexeme()
+        } else if !split.merge && split.last == Last::Removed {
+            // an empty field after the last delimiter
+            split.last = Last::Field;
+            src.extract_lexeme()
         } else {
-            None
-        }
+            return None;
+        };
+        return Some(Spanned::new(token, Span { start, end: src.location() }));
     }
 }
 
@@ -41,7 +321,7 @@ impl<I: Iterator<Item = char>> Iterator for DelimTokenizer<I> {
 
 #[cfg(test)]
 mod tests {
-    use super::DelimTokenizer;
+    use super::{DelimTokenizer, StrDelimTokenizer};
 
     #[test]
     fn delim_tokenizer() {
@@ -65,4 +345,42 @@ mod tests {
             assert_eq!(lx.next(), None);
         }
     }
+
+    #[test]
+    fn delim_options() {
+        // multi-char delimiters win over their single chars
+        let lx = DelimTokenizer::new("a::b:c, d".chars(), ":, ", true).delim("::");
+        assert_eq!(lx.collect::<Vec<_>>(), vec!["a", "b", "c", "d"]);
+        let lx = StrDelimTokenizer::new("x<=y<z", "<", false).delim("<=");
+        assert_eq!(lx.collect::<Vec<_>>(), vec!["x", "<=", "y", "<", "z"]);
+
+        // merge repeats, or keep the empty fields between them
+        let lx = DelimTokenizer::new(",a,,b,".chars(), ",", true).merge(false);
+        assert_eq!(lx.collect::<Vec<_>>(), vec!["", "a", "", "b", ""]);
+        let lx = StrDelimTokenizer::new("1++2", "+", false).merge(true);
+        assert_eq!(lx.collect::<Vec<_>>(), vec!["1", "++", "2"]);
+
+        // quotes open fields only, escapes work everywhere
+        let input = r#"meet "at 3:30pm, sharp" o'clock 'a b' c\ d"#;
+        let lx = StrDelimTokenizer::new(input, " ", true).quotes("\"'").escape('\\');
+        assert_eq!(lx.collect::<Vec<_>>(),
+                   vec!["meet", r#""at 3:30pm, sharp""#, "o'clock", "'a b'", r"c\ d"]);
+        let lx = DelimTokenizer::new(input.chars(), " ", true).quotes("\"'").escape('\\')
+            .unquote(true);
+        assert_eq!(lx.collect::<Vec<_>>(), vec!["meet", "at 3:30pm, sharp", "o'clock", "a b", "c d"]);
+    }
+
+    #[test]
+    fn csv() {
+        let input = "name,quote\r\n\"Doe, J\",\"said \"\"hi\"\"\nthere\"\n,\n";
+        let lx = DelimTokenizer::csv(input.chars());
+        assert_eq!(lx.collect::<Vec<_>>(), vec![
+            "name", "quote", "\r\n", "Doe, J", "said \"hi\"\nthere", "\n", "", "", "\n",
+        ]);
+        let spans: Vec<_> = StrDelimTokenizer::csv("a,,b").with_spans()
+            .map(|t| (t.value, t.span.to_string()))
+            .collect();
+        assert_eq!(spans, vec![("a", "1:1-1:2".to_string()), ("", "1:3-1:3".to_string()),
+                               ("b", "1:4-1:5".to_string())]);
+    }
 }
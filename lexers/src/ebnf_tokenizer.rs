@@ -1,16 +1,17 @@
 #![deny(warnings)]
 
 use crate::scanner::Scanner;
+use crate::span::{Position, Span, Spanned, WithSpans};
 
 pub struct EbnfTokenizer<I: Iterator<Item = char>> {
     input: Scanner<I>,
-    lookahead: Vec<String>,
+    lookahead: Vec<Spanned<String>>,
 }
 
 impl<I: Iterator<Item = char>> EbnfTokenizer<I> {
     pub fn new(source: I) -> Self {
         EbnfTokenizer {
-            input: Scanner::new(source),
+            input: Scanner::text(source),
             lookahead: Vec::new(),
         }
     }
@@ -18,11 +19,18 @@ impl<I: Iterator<Item = char>> EbnfTokenizer<I> {
     pub fn scanner(source: I) -> Scanner<Self> {
         Scanner::new(Self::new(source))
     }
-}
 
-impl<I: Iterator<Item = char>> Iterator for EbnfTokenizer<I> {
-    type Item = String;
-    fn next(&mut self) -> Option<Self::Item> {
+    // Accept unicode identifiers and whitespace (see Scanner::unicode)
+    pub fn unicode(self, unicode: bool) -> Self {
+        EbnfTokenizer { input: self.input.unicode(unicode), ..self }
+    }
+
+    // Emit tokens along with their position in the source
+    pub fn with_spans(self) -> WithSpans<Self> {
+        WithSpans(self)
+    }
+
+    fn next_spanned(&mut self) -> Option<Spanned<String>> {
         // used for accumulating string parts
         if !self.lookahead.is_empty() {
             return self.lookahead.pop();
@@ -35,33 +43,53 @@ impl<I: Iterator<Item = char>> Iterator for EbnfTokenizer<I> {
                 if nl == '\n' {
                     s.extract(); // ignore comment
                                  // discard comment and allow more by restarting
-                    return self.next();
+                    return self.next_spanned();
                 }
             }
         }
+        self.get_token()
+    }
+
+    // Extract the pending token which began at `start`
+    fn extract(s: &mut Scanner<I>, start: Position) -> Option<Spanned<String>> {
+        let token = s.extract_string();
+        Some(Spanned::new(token, Span { start, end: s.location() }))
+    }
+
+    fn get_token(&mut self) -> Option<Spanned<String>> {
+        let s = &mut self.input;
+        let start = s.location();
         if s.accept_any(&['[', ']', '{', '}', '(', ')', '|', ';'])
             .is_some()
         {
-            return Some(s.extract_string());
+            return Self::extract(s, start);
         }
         let backtrack = s.buffer_pos();
         if s.accept(&':').is_some() {
             if s.accept(&'=').is_some() {
-                return Some(s.extract_string());
+                return Self::extract(s, start);
             }
             s.set_buffer_pos(backtrack);
         }
         let backtrack = s.buffer_pos();
         if let Some(q) = s.accept_any(&['"', '\'']) {
-            while let Some(n) = s.next() {
-                if n == q {
-                    // store closing quote
-                    self.lookahead.push(n.to_string());
-                    // store string content
-                    let v = s.extract_string();
-                    self.lookahead.push(v[1..v.len() - 1].to_string());
-                    // return opening quote
-                    return Some(q.to_string());
+            let open = s.location();
+            let mut close = open;
+            loop {
+                match s.next() {
+                    Some(n) if n == q => {
+                        let end = s.location();
+                        // store closing quote
+                        self.lookahead.push(Spanned::new(n.to_string(), Span { start: close, end }));
+                        // store string content
+                        let v = s.extract_string();
+                        let content = v[1..v.len() - 1].to_string();
+                        self.lookahead.push(Spanned::new(content, Span { start: open, end: close }));
+                        // return opening quote
+                        return Some(Spanned::new(q.to_string(), Span { start, end: open }));
+                    }
+                    Some(c) => close.step_char(&c),
+                    None => break,
                 }
             }
             s.set_buffer_pos(backtrack);
@@ -70,10 +98,24 @@ impl<I: Iterator<Item = char>> Iterator for EbnfTokenizer<I> {
         s.accept(&'@');
         // NOTE: scan_identifier limits the valid options
         if let Some(id) = s.scan_identifier() {
-            return Some(id);
+            return Some(Spanned::new(id, Span { start, end: s.location() }));
         }
         // backtrack possible '@'
         s.set_buffer_pos(backtrack);
         None
     }
 }
+
+impl<I: Iterator<Item = char>> Iterator for EbnfTokenizer<I> {
+    type Item = String;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned().map(|t| t.value)
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for WithSpans<EbnfTokenizer<I>> {
+    type Item = Spanned<String>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_spanned()
+    }
+}
@@ -1,6 +1,9 @@
 #![deny(warnings)]
 
 use crate::scanner::Scanner;
+use crate::span::Position;
+use crate::units::{self, UnitRegistry};
+use std::borrow::Cow;
 
 static WHITE: &[char] = &[' ', '\n', '\r', '\t'];
 static DIGITS: &[char] = &['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
@@ -20,134 +23,327 @@ static ALNUM: &[char] = &[
     'U', 'V', 'W', 'X', 'Y', 'Z',
 ];
 
+// First digit of each Unicode decimal digit (Nd) block of ten
+pub(crate) static DIGIT_ZEROS: &[u32] = &[
+    0x0030, 0x0660, 0x06F0, 0x07C0, 0x0966, 0x09E6, 0x0A66, 0x0AE6, 0x0B66, 0x0BE6, 0x0C66,
+    0x0CE6, 0x0D66, 0x0DE6, 0x0E50, 0x0ED0, 0x0F20, 0x1040, 0x1090, 0x17E0, 0x1810, 0x1946,
+    0x19D0, 0x1A80, 0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0,
+    0xA9F0, 0xAA50, 0xABF0, 0xFF10, 0x104A0, 0x10D30, 0x11066, 0x110F0, 0x11136, 0x111D0,
+    0x112F0, 0x11450, 0x114D0, 0x11650, 0x116C0, 0x11730, 0x118E0, 0x11950, 0x11C50, 0x11D50,
+    0x11DA0, 0x11F50, 0x16A60, 0x16AC0, 0x16B50, 0x1D7CE, 0x1D7D8, 0x1D7E2, 0x1D7EC, 0x1D7F6,
+    0x1E140, 0x1E2F0, 0x1E4F0, 0x1E950, 0x1FBF0,
+];
+
+// Value of a decimal digit in any script, eg: '٣' or full-width '３' are 3
+pub(crate) fn digit_value(c: char) -> Option<u32> {
+    let c = c as u32;
+    let block = DIGIT_ZEROS.partition_point(|zero| *zero <= c).checked_sub(1)?;
+    Some(c - DIGIT_ZEROS[block]).filter(|value| *value < 10)
+}
+
+/// Rewrite decimal digits from any script as ASCII so lexemes scanned in
+/// unicode mode can be parsed, eg: "４２.５" becomes "42.5".
+pub fn normalize_digits(lexeme: &str) -> Cow<'_, str> {
+    match lexeme.is_ascii() {
+        true => Cow::Borrowed(lexeme),
+        false => Cow::Owned(lexeme.chars()
+            .map(|c| match digit_value(c) {
+                Some(d) => char::from_digit(d, 10).unwrap(),
+                None => c,
+            })
+            .collect()),
+    }
+}
+
+// Char classes of the scan_* helpers, ASCII unless a scanner is in unicode mode
+
+pub(crate) fn is_space(unicode: bool, c: char) -> bool {
+    match unicode {
+        true => c.is_whitespace(),
+        false => WHITE.contains(&c),
+    }
+}
+
+pub(crate) fn is_digit(unicode: bool, c: char) -> bool {
+    match unicode {
+        true => digit_value(c).is_some(),
+        false => DIGITS.contains(&c),
+    }
+}
+
+fn is_ident_start(unicode: bool, c: char) -> bool {
+    match unicode {
+        true => c == '_' || unicode_ident::is_xid_start(c),
+        false => ALPHA.contains(&c),
+    }
+}
+
+pub(crate) fn is_ident_continue(unicode: bool, c: char) -> bool {
+    match unicode {
+        true => unicode_ident::is_xid_continue(c),
+        false => ALNUM.contains(&c),
+    }
+}
+
+// Char scanners the scan_* helpers work on, lexemes are owned Strings for
+// Scanner and slices of the source for StrScanner
+pub(crate) trait Cursor {
+    type Lexeme: AsRef<str> + Clone;
+
+    fn next_char(&mut self) -> Option<char>;
+    // opaque position in the buffer to backtrack to
+    fn mark(&self) -> usize;
+    fn reset(&mut self, mark: usize);
+    // consume everything up to the current char
+    fn extract_lexeme(&mut self) -> Self::Lexeme;
+    fn location(&self) -> Position;
+    // use unicode char classes instead of ASCII ones
+    fn unicode(&self) -> bool;
+
+    fn peek(&mut self) -> Option<char> {
+        let backtrack = self.mark();
+        let peeked = self.next_char();
+        self.reset(backtrack);
+        peeked
+    }
+
+    fn accept(&mut self, what: &char) -> Option<char> {
+        self.accept_any(std::slice::from_ref(what))
+    }
+
+    fn accept_any(&mut self, any: &[char]) -> Option<char> {
+        let backtrack = self.mark();
+        match self.next_char() {
+            Some(next) if any.contains(&next) => Some(next),
+            _ => {
+                self.reset(backtrack);
+                None
+            }
+        }
+    }
+
+    fn accept_all(&mut self, what: impl Iterator<Item = char>) -> bool {
+        let backtrack = self.mark();
+        for c in what {
+            if self.accept(&c).is_none() {
+                self.reset(backtrack);
+                return false;
+            }
+        }
+        true
+    }
+
+    fn accept_if(&mut self, pred: impl Fn(char) -> bool) -> Option<char> {
+        let backtrack = self.mark();
+        match self.next_char() {
+            Some(next) if pred(next) => Some(next),
+            _ => {
+                self.reset(backtrack);
+                None
+            }
+        }
+    }
+
+    fn skip_while(&mut self, pred: impl Fn(char) -> bool) -> bool {
+        let mut advanced = false;
+        while self.accept_if(&pred).is_some() {
+            advanced = true;
+        }
+        advanced
+    }
+
+    fn skip_all(&mut self, over: &[char]) -> bool {
+        let mut advanced = false;
+        while self.accept_any(over).is_some() {
+            advanced = true;
+        }
+        advanced
+    }
+
+    fn until_any(&mut self, any: &[char]) -> bool {
+        let mut advanced = false;
+        while let Some(next) = self.peek() {
+            if any.contains(&next) {
+                break;
+            }
+            self.next_char();
+            advanced = true;
+        }
+        advanced
+    }
+}
+
+impl<I: Iterator<Item = char>> Cursor for Scanner<I> {
+    type Lexeme = String;
+
+    fn next_char(&mut self) -> Option<char> {
+        self.next()
+    }
+
+    fn mark(&self) -> usize {
+        (self.buffer_pos() + 1) as usize
+    }
+
+    fn reset(&mut self, mark: usize) {
+        self.set_buffer_pos(mark as isize - 1);
+    }
+
+    fn extract_lexeme(&mut self) -> String {
+        self.extract_string()
+    }
+
+    fn location(&self) -> Position {
+        Scanner::location(self)
+    }
+
+    fn unicode(&self) -> bool {
+        self.is_unicode()
+    }
+}
+
+pub(crate) fn scan_whitespace<S: Cursor>(s: &mut S) -> Option<S::Lexeme> {
+    let unicode = s.unicode();
+    s.skip_while(|c| is_space(unicode, c));
+    Some(s.extract_lexeme())
+}
+
+// scan numbers like -?[0-9]+(\.[0-9]+)?([eE][+-][0-9]+)?
+pub(crate) fn scan_number<S: Cursor>(s: &mut S) -> Option<S::Lexeme> {
+    let unicode = s.unicode();
+    let digit = |c| is_digit(unicode, c);
+    let backtrack = s.mark();
+    // optional sign
+    s.accept_any(&['+', '-']);
+    // require integer part
+    if !s.skip_while(digit) {
+        s.reset(backtrack);
+        return None;
+    }
+    // check for fractional part, else it's just an integer
+    let backtrack = s.mark();
+    if s.accept(&'.').is_some() && !s.skip_while(digit) {
+        s.reset(backtrack);
+        return Some(s.extract_lexeme()); // integer
+    }
+    // check for exponent part
+    let backtrack = s.mark();
+    if s.accept_any(&['e', 'E']).is_some() {
+        s.accept_any(&['+', '-']); // exponent sign is optional
+        if !s.skip_while(digit) {
+            s.reset(backtrack);
+            return Some(s.extract_lexeme()); //float
+        }
+    }
+    s.accept(&'i'); // accept imaginary numbers
+    Some(s.extract_lexeme())
+}
+
+pub(crate) fn scan_math_op<S: Cursor>(s: &mut S) -> Option<S::Lexeme> {
+    const OPS: &[char] = &['+', '-', '/', '%', '^', '(', ')', ',', '?'];
+    let backtrack = s.mark();
+    if s.accept_any(&['>', '=', '<', '!']).is_some() {
+        // accept '<', '>', '=', '!', '<=', '>=', '==', '!='
+        s.accept(&'=');
+        Some(s.extract_lexeme())
+    } else if s.accept(&':').is_some() {
+        // accept ':' and ':='. Set delayed to avoid immediate eval of rhs.
+        s.accept(&'=');
+        Some(s.extract_lexeme())
+    } else if s.accept(&'*').is_some() {
+        // accept '*', '**'
+        s.accept(&'*');
+        Some(s.extract_lexeme())
+    } else if let Some(c) = s.accept_any(&['&', '|']) {
+        // accept '&&', '||'
+        if s.accept(&c).is_none() {
+            s.reset(backtrack);
+            return None;
+        }
+        Some(s.extract_lexeme())
+    } else if s.accept_any(OPS).is_some() {
+        Some(s.extract_lexeme())
+    } else {
+        None
+    }
+}
+
+// scan integers like 0x34 0b10101 0o657
+pub(crate) fn scan_integer<S: Cursor>(s: &mut S) -> Option<S::Lexeme> {
+    let backtrack = s.mark();
+    if s.accept(&'0').is_some()
+        && match s.accept_any(&['x', 'o', 'b']) {
+            Some('x') => s.skip_all(HEXDIGITS),
+            Some('o') => s.skip_all(&HEXDIGITS[..8]),
+            Some('b') => s.skip_all(&HEXDIGITS[..2]),
+            _ => false,
+        }
+    {
+        return Some(s.extract_lexeme());
+    }
+    s.reset(backtrack);
+    None
+}
+
+// scan a quoted string like "this is \"an\" example"
+pub(crate) fn scan_quoted_string<S: Cursor>(s: &mut S, q: char) -> Option<S::Lexeme> {
+    let backtrack = s.mark();
+    s.accept(&q)?;
+    while let Some(n) = s.next_char() {
+        if n == '\\' {
+            s.next_char();
+            continue;
+        }
+        if n == q {
+            return Some(s.extract_lexeme());
+        }
+    }
+    s.reset(backtrack);
+    None
+}
+
+// scan [a-zA-Z_][a-zA-Z0-9_]+, or XID identifiers in unicode mode
+pub(crate) fn scan_identifier<S: Cursor>(s: &mut S) -> Option<S::Lexeme> {
+    let unicode = s.unicode();
+    s.accept_if(|c| is_ident_start(unicode, c))?;
+    s.skip_while(|c| is_ident_continue(unicode, c));
+    Some(s.extract_lexeme())
+}
+
 impl<I: Iterator<Item = char>> Scanner<I> {
     pub fn extract_string(&mut self) -> String {
         self.extract().into_iter().collect()
     }
 
     pub fn scan_whitespace(&mut self) -> Option<String> {
-        self.skip_all(WHITE);
-        Some(self.extract_string())
+        scan_whitespace(self)
     }
 
     // scan numbers like -?[0-9]+(\.[0-9]+)?([eE][+-][0-9]+)?
     pub fn scan_number(&mut self) -> Option<String> {
-        let backtrack = self.buffer_pos();
-        // optional sign
-        self.accept_any(&['+', '-']);
-        // require integer part
-        if !self.skip_all(DIGITS) {
-            self.set_buffer_pos(backtrack);
-            return None;
-        }
-        // check for fractional part, else it's just an integer
-        let backtrack = self.buffer_pos();
-        if self.accept(&'.').is_some() && !self.skip_all(DIGITS) {
-            self.set_buffer_pos(backtrack);
-            return Some(self.extract_string()); // integer
-        }
-        // check for exponent part
-        let backtrack = self.buffer_pos();
-        if self.accept_any(&['e', 'E']).is_some() {
-            self.accept_any(&['+', '-']); // exponent sign is optional
-            if !self.skip_all(DIGITS) {
-                self.set_buffer_pos(backtrack);
-                return Some(self.extract_string()); //float
-            }
-        }
-        self.accept(&'i'); // accept imaginary numbers
-        Some(self.extract_string())
+        scan_number(self)
     }
 
     pub fn scan_math_op(&mut self) -> Option<String> {
-        const OPS: &[char] = &['+', '-', '*', '/', '%', '^', '!', '(', ')', ','];
-        if self.accept_any(&['>', '=', '<']).is_some() {
-            // accept '<', '>', '=', '<=', '>=', '=='
-            self.accept(&'=');
-            Some(self.extract_string())
-        } else if self.accept(&':').is_some() && self.accept(&'=').is_some() {
-            // accept ':='. Set delayed to avoid immediate eval of rhs.
-            Some(self.extract_string())
-        } else if self.accept(&'*').is_some() {
-            // accept '*', '**'
-            self.accept(&'*');
-            Some(self.extract_string())
-        } else if self.accept_any(OPS).is_some() {
-            Some(self.extract_string())
-        } else {
-            None
-        }
+        scan_math_op(self)
     }
 
     // scan integers like 0x34 0b10101 0o657
     pub fn scan_integer(&mut self) -> Option<String> {
-        let backtrack = self.buffer_pos();
-        if self.accept(&'0').is_some()
-            && match self.accept_any(&['x', 'o', 'b']) {
-                Some('x') => self.skip_all(HEXDIGITS),
-                Some('o') => self.skip_all(&HEXDIGITS[..8]),
-                Some('b') => self.skip_all(&HEXDIGITS[..2]),
-                _ => false,
-            }
-        {
-            return Some(self.extract_string());
-        }
-        self.set_buffer_pos(backtrack);
-        None
+        scan_integer(self)
     }
 
     // scan a quoted string like "this is \"an\" example"
     pub fn scan_quoted_string(&mut self, q: char) -> Option<String> {
-        let backtrack = self.buffer_pos();
-        self.accept(&q)?;
-        while let Some(n) = self.next() {
-            if n == '\\' {
-                self.next();
-                continue;
-            }
-            if n == q {
-                return Some(self.extract_string());
-            }
-        }
-        self.set_buffer_pos(backtrack);
-        None
+        scan_quoted_string(self, q)
     }
 
-    // scan [a-zA-Z_][a-zA-Z0-9_]+
+    // scan [a-zA-Z_][a-zA-Z0-9_]+, or XID identifiers in unicode mode
     pub fn scan_identifier(&mut self) -> Option<String> {
-        self.accept_any(ALPHA)?;
-        self.skip_all(ALNUM);
-        Some(self.extract_string())
+        scan_identifier(self)
     }
 
-    // scan an optional prefix (unit multiplier) and unit
+    // scan an optional prefix (unit multiplier) and an SI unit
     pub fn scan_unit(&mut self) -> Option<(String, String)> {
-        static PFX: &[&str] = &[
-            "da", "h", "k", "M", "G", "T", "P", "E", "Z", "Y",
-            "y", "z", "a", "f", "p", "n", "µ", "m", "c", "d",
-            "", // no multiplier prefix, raw unit
-        ];
-        // NOTE: longest prefix first for longest match (ie: 'da')
-        assert_eq!(PFX[0], "da");
-        static BARE_UNITS: &[&str] = &[
-            "kat", "mol", "rad",
-            "Bq", "cd", "Gy", "Hz", "lm", "lx", "Pa", "sr", "Sv", "Wb",
-            "A", "°C", "C", "F", "g", "H", "J", "K", "m", "N", "s", "S",
-            "T", "V", "W", "Ω",
-        ];
-        assert_eq!(BARE_UNITS[0].len(), 3);
-        for prefix in PFX {
-            let pfx_backtrack = self.buffer_pos();
-            if self.accept_all(prefix.chars()) {
-                for unit in BARE_UNITS {
-                    if self.accept_all(unit.chars()) {
-                        self.extract_string(); // ignore
-                        return Some((prefix.to_string(), unit.to_string()))
-                    }
-                }
-            }
-            self.set_buffer_pos(pfx_backtrack);
-        }
-        None
+        units::scan_unit(self, UnitRegistry::si_ref())
     }
 }
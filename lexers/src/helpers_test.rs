@@ -1,4 +1,6 @@
+use crate::helpers::{digit_value, normalize_digits, DIGIT_ZEROS};
 use crate::scanner::Scanner;
+use crate::{LispToken, LispTokenizer, MathToken, StrMathTokenizer, StrScanner};
 
 #[test]
 fn scan_number() {
@@ -40,14 +42,14 @@ fn scan_number() {
 fn scan_math_ops() {
     let tests = vec![
         "<", "<=", "=", "==", ">=", ">", "(", ")", ",", "*",
-        "**", "^", "!", "+", "-", "/", "%", ":=",
+        "**", "^", "!", "+", "-", "/", "%", ":=", "!=", "&&", "||", "?", ":",
     ];
     for t in tests.iter() {
         let result = Scanner::new(t.chars()).scan_math_op();
         assert_eq!(Some(t.to_string()), result);
     }
     // Negative tests
-    let result = Scanner::new(":".chars()).scan_math_op();
+    let result = Scanner::new("&|".chars()).scan_math_op();
     assert_eq!(result, None);
 }
 
@@ -92,3 +94,67 @@ fn scan_units() {
         }
     }
 }
+
+#[test]
+fn unicode_classes() {
+    // ASCII by default
+    let mut s = Scanner::new("café\u{a0}x".chars());
+    assert_eq!(s.scan_identifier(), Some("caf".to_string()));
+    let mut s = Scanner::new("\u{a0}x".chars());
+    assert_eq!(s.scan_whitespace(), Some("".to_string()));
+    assert_eq!(Scanner::new("４２".chars()).scan_number(), None);
+
+    fn unicode(input: &str) -> Scanner<std::str::Chars<'_>> {
+        Scanner::new(input.chars()).unicode(true)
+    }
+    assert_eq!(unicode("café\u{a0}x").scan_identifier(), Some("café".to_string()));
+    assert_eq!(unicode("_日本語2 x").scan_identifier(), Some("_日本語2".to_string()));
+    assert_eq!(unicode("e\u{301}t").scan_identifier(), Some("e\u{301}t".to_string()));
+    assert_eq!(unicode("2x").scan_identifier(), None);
+    assert_eq!(unicode("\u{a0}\u{3000} x").scan_whitespace(), Some("\u{a0}\u{3000} ".to_string()));
+    assert_eq!(unicode("-４２.５e３x").scan_number(), Some("-４２.５e３".to_string()));
+    assert_eq!(unicode("٣٫").scan_number(), Some("٣".to_string()));
+    let mut s = StrScanner::new("ñandú ３").unicode(true);
+    assert_eq!(s.scan_identifier(), Some("ñandú"));
+    s.scan_whitespace();
+    assert_eq!(s.scan_number(), Some("３"));
+}
+
+#[test]
+fn unicode_digits() {
+    for zero in DIGIT_ZEROS {
+        for value in 0..10 {
+            let digit = char::from_u32(zero + value).unwrap();
+            assert!(digit.is_numeric(), "{:?}", digit);
+            assert_eq!(digit_value(digit), Some(value));
+        }
+    }
+    assert_eq!(digit_value('a'), None);
+    assert_eq!(digit_value('½'), None);
+    assert_eq!(normalize_digits("-４２.٥e3"), "-42.5e3");
+    assert_eq!(normalize_digits("12"), "12");
+}
+
+#[test]
+fn unicode_tokenizers() {
+    let tokens: Vec<_> = StrMathTokenizer::new("café+４２\u{a0}*x").unicode(true).collect();
+    assert_eq!(tokens, vec![
+        MathToken::Variable("café"),
+        MathToken::BOp("+"),
+        MathToken::Number(42.0),
+        MathToken::BOp("*"),
+        MathToken::Variable("x"),
+    ]);
+    let tokens: Vec<_> = StrMathTokenizer::new("café").collect();
+    assert_eq!(tokens[0], MathToken::Variable("caf"));
+
+    let tokens: Vec<_> = LispTokenizer::new("(a\u{a0}b)".chars()).unicode(true).collect();
+    assert_eq!(tokens, vec![
+        LispToken::OParen,
+        LispToken::Symbol("a".to_string()),
+        LispToken::Symbol("b".to_string()),
+        LispToken::CParen,
+    ]);
+    let tokens: Vec<_> = LispTokenizer::new("(a\u{a0}b)".chars()).collect();
+    assert_eq!(tokens[1], LispToken::Symbol("a\u{a0}b".to_string()));
+}
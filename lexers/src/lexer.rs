@@ -0,0 +1,229 @@
+#![deny(warnings)]
+
+use crate::scanner::Scanner;
+use crate::span::{Span, Spanned, WithSpans};
+
+/// Scanner that rules match against. It reads ahead from the lexer's input so
+/// rules can use any `Scanner` method, `scan_*` helpers included.
+pub type Input<'a> = Scanner<&'a mut dyn Iterator<Item = char>>;
+
+/// What a rule matches, advancing the `Input` past it when it returns true.
+pub trait Pattern {
+    fn matches(&self, input: &mut Input) -> bool;
+}
+
+// A literal string
+impl Pattern for &str {
+    fn matches(&self, input: &mut Input) -> bool {
+        input.accept_all(self.chars())
+    }
+}
+
+impl Pattern for String {
+    fn matches(&self, input: &mut Input) -> bool {
+        input.accept_all(self.chars())
+    }
+}
+
+impl Pattern for char {
+    fn matches(&self, input: &mut Input) -> bool {
+        input.accept(self).is_some()
+    }
+}
+
+// A char class, matches one or more chars from the set
+impl Pattern for &[char] {
+    fn matches(&self, input: &mut Input) -> bool {
+        input.skip_all(self)
+    }
+}
+
+// Closures, eg: |s: &mut Input| s.scan_number().is_some()
+impl<F: Fn(&mut Input) -> bool> Pattern for F {
+    fn matches(&self, input: &mut Input) -> bool {
+        self(input)
+    }
+}
+
+// Builds a token out of its lexeme
+type Make<T> = Box<dyn Fn(&str) -> T>;
+
+struct Rule<T> {
+    pattern: Box<dyn Pattern>,
+    // builds the token from its lexeme, skip rules have none
+    make: Option<Make<T>>,
+    priority: i32,
+}
+
+/// Builds a `Lexer` out of token and skip rules. The rule with the longest
+/// match wins, ties go to the highest priority and then to the first added.
+///
+/// ```
+/// use lexers::{Input, LexerBuilder};
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Tok { Let, Id(String), Num(f64), Eq }
+///
+/// let lexer = LexerBuilder::new()
+///     .skip(&[' ', '\t', '\n'][..])
+///     .token("let", |_| Tok::Let)
+///     .token(|s: &mut Input| s.scan_identifier().is_some(), |id| Tok::Id(id.to_string()))
+///     .token(|s: &mut Input| s.scan_number().is_some(), |n| Tok::Num(n.parse().unwrap()))
+///     .token('=', |_| Tok::Eq)
+///     .build();
+/// let tokens: Vec<_> = lexer.tokenize("let letter = 4".chars()).collect();
+/// assert_eq!(tokens, vec![Tok::Let, Tok::Id("letter".to_string()), Tok::Eq, Tok::Num(4.0)]);
+/// ```
+pub struct LexerBuilder<T> {
+    rules: Vec<Rule<T>>,
+    unknown: Option<Make<T>>,
+    unicode: bool,
+}
+
+impl<T> Default for LexerBuilder<T> {
+    fn default() -> Self {
+        LexerBuilder { rules: Vec::new(), unknown: None, unicode: false }
+    }
+}
+
+impl<T> LexerBuilder<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Emit make(lexeme) for input that matches 'pattern'
+    pub fn token(mut self, pattern: impl Pattern + 'static,
+                 make: impl Fn(&str) -> T + 'static) -> Self {
+        self.rules.push(Rule {
+            pattern: Box::new(pattern),
+            make: Some(Box::new(make)),
+            priority: 0,
+        });
+        self
+    }
+
+    // Discard input that matches 'pattern' (eg: whitespace, comments)
+    pub fn skip(mut self, pattern: impl Pattern + 'static) -> Self {
+        self.rules.push(Rule { pattern: Box::new(pattern), make: None, priority: 0 });
+        self
+    }
+
+    // Set the priority of the last rule, used to break ties (default 0)
+    pub fn priority(mut self, priority: i32) -> Self {
+        if let Some(rule) = self.rules.last_mut() {
+            rule.priority = priority;
+        }
+        self
+    }
+
+    // Emit make(c) for chars no rule matches, else the lexer stops there
+    pub fn unknown(mut self, make: impl Fn(&str) -> T + 'static) -> Self {
+        self.unknown = Some(Box::new(make));
+        self
+    }
+
+    // Rules get an Input in unicode mode (see Scanner::unicode)
+    pub fn unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    pub fn build(self) -> Lexer<T> {
+        Lexer { rules: self.rules, unknown: self.unknown, unicode: self.unicode }
+    }
+}
+
+pub struct Lexer<T> {
+    rules: Vec<Rule<T>>,
+    unknown: Option<Make<T>>,
+    unicode: bool,
+}
+
+impl<T> Lexer<T> {
+    pub fn tokenize<I: Iterator<Item = char>>(&self, source: I) -> Tokens<'_, T, I> {
+        Tokens { lexer: self, src: Scanner::text(source).unicode(self.unicode) }
+    }
+
+    pub fn scanner<I>(&self, source: I) -> Scanner<Tokens<'_, T, I>>
+    where
+        I: Iterator<Item = char>,
+        T: Clone,
+    {
+        Scanner::new(self.tokenize(source))
+    }
+}
+
+/// Tokens of some input, see `Lexer::tokenize`.
+pub struct Tokens<'l, T, I: Iterator<Item = char>> {
+    lexer: &'l Lexer<T>,
+    src: Scanner<I>,
+}
+
+impl<'l, T, I: Iterator<Item = char>> Tokens<'l, T, I> {
+    // Emit tokens along with their position in the source
+    pub fn with_spans(self) -> WithSpans<Self> {
+        WithSpans(self)
+    }
+
+    // Length in chars of what 'rule' matches at the start of the buffer
+    fn match_len(&mut self, rule: &Rule<T>) -> usize {
+        let backtrack = self.src.buffer_pos();
+        let len = {
+            let source: &mut dyn Iterator<Item = char> = &mut self.src;
+            let mut input = Scanner::new(source).unicode(self.lexer.unicode);
+            match rule.pattern.matches(&mut input) {
+                // plain scanners count chars as offsets
+                true => input.location().offset,
+                false => 0,
+            }
+        };
+        self.src.set_buffer_pos(backtrack);
+        len
+    }
+
+    fn next_spanned(&mut self) -> Option<Spanned<T>> {
+        let lexer = self.lexer;
+        loop {
+            let start = self.src.location();
+            // longest match, then highest priority, then first rule
+            let mut best: Option<(usize, i32, &Rule<T>)> = None;
+            for rule in &lexer.rules {
+                let len = self.match_len(rule);
+                if len > 0 && best.is_none_or(|(blen, bprio, _)|
+                        (len, rule.priority) > (blen, bprio)) {
+                    best = Some((len, rule.priority, rule));
+                }
+            }
+            let make = match best {
+                Some((len, _, rule)) => {
+                    (0..len).for_each(|_| { self.src.next(); });
+                    &rule.make
+                },
+                // unmatched input ends tokenization unless there's a fallback
+                None if lexer.unknown.is_some() && self.src.next().is_some() =>
+                    &lexer.unknown,
+                None => return None,
+            };
+            let lexeme = self.src.extract_string();
+            // skip rules have nothing to make, keep going
+            if let Some(make) = make {
+                let span = Span { start, end: self.src.location() };
+                return Some(Spanned::new(make(&lexeme), span));
+            }
+        }
+    }
+}
+
+impl<'l, T, I: Iterator<Item = char>> Iterator for Tokens<'l, T, I> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned().map(|t| t.value)
+    }
+}
+
+impl<'l, T, I: Iterator<Item = char>> Iterator for WithSpans<Tokens<'l, T, I>> {
+    type Item = Spanned<T>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_spanned()
+    }
+}
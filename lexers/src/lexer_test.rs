@@ -0,0 +1,92 @@
+#![deny(warnings)]
+
+use crate::lexer::{Input, LexerBuilder};
+use crate::{LispToken, LispTokenizer};
+
+static WHITE: &[char] = &[' ', '\n', '\r', '\t'];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    If,
+    Id(String),
+    Op(String),
+    Num(f64),
+    Unknown(String),
+}
+
+fn ops() -> LexerBuilder<Tok> {
+    LexerBuilder::new()
+        .skip(WHITE)
+        .token(|s: &mut Input| s.scan_identifier().is_some(), |id| Tok::Id(id.to_string()))
+        .token("if", |_| Tok::If)
+        .priority(1)
+        .token('<', |op| Tok::Op(op.to_string()))
+        .token("<=", |op| Tok::Op(op.to_string()))
+        .token("<<=", |op| Tok::Op(op.to_string()))
+        .token(|s: &mut Input| s.scan_number().is_some(), |n| Tok::Num(n.parse().unwrap()))
+}
+
+#[test]
+fn longest_match() {
+    let lexer = ops().build();
+    let tokens: Vec<_> = lexer.tokenize("if iffy<=2<<=x < 3.5".chars()).collect();
+    assert_eq!(tokens, vec![
+        Tok::If,
+        Tok::Id("iffy".to_string()),
+        Tok::Op("<=".to_string()),
+        Tok::Num(2.0),
+        Tok::Op("<<=".to_string()),
+        Tok::Id("x".to_string()),
+        Tok::Op("<".to_string()),
+        Tok::Num(3.5),
+    ]);
+}
+
+#[test]
+fn unmatched_input() {
+    // without a fallback the lexer stops at unknown input
+    let lexer = ops().build();
+    let tokens: Vec<_> = lexer.tokenize("x < $ y".chars()).collect();
+    assert_eq!(tokens, vec![Tok::Id("x".to_string()), Tok::Op("<".to_string())]);
+
+    let lexer = ops().unknown(|c| Tok::Unknown(c.to_string())).build();
+    let tokens: Vec<_> = lexer.tokenize("x $ y".chars()).with_spans()
+        .map(|t| (t.value, t.span.to_string()))
+        .collect();
+    assert_eq!(tokens, vec![
+        (Tok::Id("x".to_string()), "1:1-1:2".to_string()),
+        (Tok::Unknown("$".to_string()), "1:3-1:4".to_string()),
+        (Tok::Id("y".to_string()), "1:5-1:6".to_string()),
+    ]);
+}
+
+#[test]
+fn lisp_like() {
+    let lexer = LexerBuilder::new()
+        .skip(WHITE)
+        .skip(|s: &mut Input| s.accept(&';').is_some() && { s.until_any(&['\n']); true })
+        .token('(', |_| LispToken::OParen)
+        .token(')', |_| LispToken::CParen)
+        .token('\'', |_| LispToken::Quote)
+        .token('`', |_| LispToken::QuasiQuote)
+        .token(',', |_| LispToken::UnQuote)
+        .token(",@", |_| LispToken::UnQSplice)
+        .token("#t", |_| LispToken::True)
+        .token("#f", |_| LispToken::False)
+        .token(|s: &mut Input| s.scan_string_literal('"').is_some_and(|(_, s)| s.is_ok()),
+               |s| LispToken::String(s.trim_matches('"').to_string()))
+        .token(|s: &mut Input| s.accept_any(&['\'', '`', ',']).is_none()
+                   && s.until_any(&['(', ')', ' ', '\n', '\r', '\t']),
+               |s| LispToken::Symbol(s.to_string()))
+        .token(|s: &mut Input| s.scan_number().is_some(), |n| LispToken::Number(n.parse().unwrap()))
+        .priority(1)
+        .build();
+    let input = "(define (f x) ; comment\n  `(,@x \"a b\" #t 2.5))";
+    let tokens: Vec<_> = lexer.tokenize(input.chars()).collect();
+    let expected: Vec<_> = LispTokenizer::new(input.replace("; comment", "").chars()).collect();
+    assert_eq!(tokens, expected);
+
+    let mut scanner = lexer.scanner("(a)".chars());
+    assert_eq!(scanner.next(), Some(LispToken::OParen));
+    assert_eq!(scanner.peek(), Some(LispToken::Symbol("a".to_string())));
+}
@@ -1,22 +1,71 @@
 #![deny(warnings)]
 
 mod helpers;
+pub use crate::helpers::normalize_digits;
 mod scanner;
 pub use crate::scanner::Scanner;
 
+mod number;
+pub use crate::number::NumberLiteral;
+
+mod string_literal;
+pub use crate::string_literal::StringLiteral;
+
+mod units;
+pub use crate::units::{UnitFactor, UnitRegistry};
+
+mod comments;
+pub use crate::comments::Comments;
+
+mod read_chars;
+pub use crate::read_chars::ReadChars;
+
+mod span;
+pub use crate::span::{Position, Span, Spanned, WithSpans};
+
+mod trivia;
+pub use crate::trivia::{next_lossless, Lossless, WithTrivia};
+
+mod str_scanner;
+pub use crate::str_scanner::StrScanner;
+
 mod ebnf_tokenizer;
 pub use crate::ebnf_tokenizer::EbnfTokenizer;
 
 mod math_tokenizer;
-pub use crate::math_tokenizer::{MathToken, MathTokenizer};
+pub use crate::math_tokenizer::{MathToken, MathTokenizer, StrMathTokenizer};
 
 mod delim_tokenizer;
-pub use crate::delim_tokenizer::DelimTokenizer;
+pub use crate::delim_tokenizer::{DelimTokenizer, StrDelimTokenizer};
+
+mod word_tokenizer;
+pub use crate::word_tokenizer::WordTokenizer;
 
 mod lisp_tokenizer;
-pub use crate::lisp_tokenizer::{LispToken, LispTokenizer};
+pub use crate::lisp_tokenizer::{LispToken, LispTokenizer, StrLispTokenizer};
+
+mod lexer;
+pub use crate::lexer::{Input, Lexer, LexerBuilder, Pattern, Tokens};
 
 #[cfg(test)]
 mod scanner_test;
 #[cfg(test)]
 mod helpers_test;
+#[cfg(test)]
+mod span_test;
+#[cfg(test)]
+mod lexer_test;
+#[cfg(test)]
+mod str_scanner_test;
+#[cfg(test)]
+mod number_test;
+#[cfg(test)]
+mod string_literal_test;
+#[cfg(test)]
+mod comments_test;
+#[cfg(test)]
+mod units_test;
+#[cfg(test)]
+mod read_chars_test;
+#[cfg(test)]
+mod trivia_test;
@@ -1,9 +1,16 @@
 #![deny(warnings)]
 
+use crate::comments::{self, Comments};
+use crate::helpers::{self, Cursor};
 use crate::scanner::Scanner;
+use crate::span::{Span, Spanned, WithSpans};
+use crate::str_scanner::StrScanner;
+use crate::string_literal;
+use crate::trivia::{self, Lossless, WithTrivia};
 
+// Lexemes are Strings, or slices of the source from StrLispTokenizer
 #[derive(Clone, PartialEq, Debug)]
-pub enum LispToken {
+pub enum LispToken<S = String> {
     OParen,
     CParen,
     Quote,
@@ -12,64 +19,231 @@ pub enum LispToken {
     UnQSplice,
     True,
     False,
-    Symbol(String),
+    Symbol(S),
     Number(f64),
+    // decoded string literals, the span has their source
     String(String),
+    // bad input, eg: an unterminated string
+    Error(String),
 }
 
-pub struct LispTokenizer<I: Iterator<Item = char>>(Scanner<I>);
+impl LispToken<&str> {
+    // Copy borrowed lexemes, eg: to keep tokens past the source
+    pub fn into_owned(self) -> LispToken {
+        match self {
+            LispToken::OParen => LispToken::OParen,
+            LispToken::CParen => LispToken::CParen,
+            LispToken::Quote => LispToken::Quote,
+            LispToken::QuasiQuote => LispToken::QuasiQuote,
+            LispToken::UnQuote => LispToken::UnQuote,
+            LispToken::UnQSplice => LispToken::UnQSplice,
+            LispToken::True => LispToken::True,
+            LispToken::False => LispToken::False,
+            LispToken::Symbol(s) => LispToken::Symbol(s.to_string()),
+            LispToken::Number(n) => LispToken::Number(n),
+            LispToken::String(s) => LispToken::String(s),
+            LispToken::Error(err) => LispToken::Error(err),
+        }
+    }
+}
+
+pub struct LispTokenizer<I: Iterator<Item = char>> {
+    src: Scanner<I>,
+    comments: Comments,
+}
 
 impl<I: Iterator<Item = char>> LispTokenizer<I> {
     pub fn new(source: I) -> Self {
-        LispTokenizer(Scanner::new(source))
+        LispTokenizer {
+            src: Scanner::text(source),
+            comments: Comments::lisp(),
+        }
     }
 
     pub fn scanner(source: I) -> Scanner<Self> {
         Scanner::new(Self::new(source))
     }
+
+    // Split symbols on unicode whitespace too (see Scanner::unicode)
+    pub fn unicode(self, unicode: bool) -> Self {
+        LispTokenizer { src: self.src.unicode(unicode), ..self }
+    }
+
+    // Comments to skip, ; and #| |# by default. #; datum comments are
+    // always skipped
+    pub fn comments(self, comments: Comments) -> Self {
+        LispTokenizer { comments, ..self }
+    }
+
+    // Emit tokens along with their position in the source
+    pub fn with_spans(self) -> WithSpans<Self> {
+        WithSpans(self)
+    }
+
+    // Emit tokens with the source text and comments around them
+    pub fn lossless(self) -> WithTrivia<Self> {
+        WithTrivia::new(LispTokenizer { src: self.src.record(true), ..self })
+    }
 }
 
 impl<I: Iterator<Item = char>> Iterator for LispTokenizer<I> {
     type Item = LispToken;
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.scan_whitespace();
-        if let Some(s) = self.0.scan_quoted_string('"') {
-            return Some(LispToken::String(s));
+        next_spanned(&mut self.src, &self.comments).map(|t| t.value)
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for WithSpans<LispTokenizer<I>> {
+    type Item = Spanned<LispToken>;
+    fn next(&mut self) -> Option<Self::Item> {
+        next_spanned(&mut self.0.src, &self.0.comments)
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for WithTrivia<LispTokenizer<I>> {
+    type Item = Lossless<LispToken>;
+    fn next(&mut self) -> Option<Self::Item> {
+        trivia::next_lossless(&mut self.tokenizer, &mut self.done, |t| &mut t.src,
+                              |t| skip_trivia(&mut t.src, &t.comments),
+                              |t| next_spanned(&mut t.src, &t.comments))
+    }
+}
+
+// A LispTokenizer over a &str, its tokens borrow lexemes from the source.
+// Strings are the exception, they're decoded into an owned String. Their
+// source is at the token's span with_spans, eg: &source[start.offset..end.offset]
+pub struct StrLispTokenizer<'a> {
+    src: StrScanner<'a>,
+    comments: Comments,
+}
+
+impl<'a> StrLispTokenizer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        StrLispTokenizer {
+            src: StrScanner::new(source),
+            comments: Comments::lisp(),
         }
-        if let Some(lexeme) = self.0.accept_any(&[')', '(', '\'', '`', ',']) {
-            let token = match lexeme {
-                '(' => LispToken::OParen,
-                ')' => LispToken::CParen,
-                '\'' => LispToken::Quote,
-                '`' => LispToken::QuasiQuote,
-                ',' => {
-                    if self.0.accept(&'@').is_some() {
-                        LispToken::UnQSplice
-                    } else {
-                        LispToken::UnQuote
-                    }
-                }
-                _ => unreachable!(),
-            };
-            self.0.extract(); // ignore
-            return Some(token);
+    }
+
+    pub fn scanner(source: &'a str) -> Scanner<Self> {
+        Scanner::new(Self::new(source))
+    }
+
+    // Split symbols on unicode whitespace too (see Scanner::unicode)
+    pub fn unicode(self, unicode: bool) -> Self {
+        StrLispTokenizer { src: self.src.unicode(unicode), ..self }
+    }
+
+    // Comments to skip, ; and #| |# by default. #; datum comments are
+    // always skipped
+    pub fn comments(self, comments: Comments) -> Self {
+        StrLispTokenizer { comments, ..self }
+    }
+
+    // Emit tokens along with their position in the source
+    pub fn with_spans(self) -> WithSpans<Self> {
+        WithSpans(self)
+    }
+}
+
+impl<'a> Iterator for StrLispTokenizer<'a> {
+    type Item = LispToken<&'a str>;
+    fn next(&mut self) -> Option<Self::Item> {
+        next_spanned(&mut self.src, &self.comments).map(|t| t.value)
+    }
+}
+
+impl<'a> Iterator for WithSpans<StrLispTokenizer<'a>> {
+    type Item = Spanned<LispToken<&'a str>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        next_spanned(&mut self.0.src, &self.0.comments)
+    }
+}
+
+fn next_spanned<S: Cursor>(src: &mut S, comments: &Comments)
+        -> Option<Spanned<LispToken<S::Lexeme>>> {
+    skip_trivia(src, comments);
+    let start = src.location();
+    let token = get_token(src)?;
+    Some(Spanned::new(token, Span { start, end: src.location() }))
+}
+
+// Skip whitespace, comments and #; datum comments
+fn skip_trivia<S: Cursor>(src: &mut S, comments: &Comments) {
+    comments::skip_comments(src, comments);
+    while src.accept_all("#;".chars()) {
+        skip_datum(src, comments);
+        comments::skip_comments(src, comments);
+    }
+}
+
+// Skip the expression after a #; datum comment, eg: #;(a 'b) or #;x
+fn skip_datum<S: Cursor>(src: &mut S, comments: &Comments) {
+    let mut depth = 0;
+    loop {
+        // don't eat the enclosing list's ')' in (a #;)
+        comments::skip_comments(src, comments);
+        if depth == 0 && src.peek() == Some(')') {
+            return;
+        }
+        match next_spanned(src, comments).map(|t| t.value) {
+            None => return,
+            Some(LispToken::OParen) => depth += 1,
+            Some(LispToken::CParen) => depth -= 1,
+            // quotes prefix the datum
+            Some(LispToken::Quote) | Some(LispToken::QuasiQuote) |
+            Some(LispToken::UnQuote) | Some(LispToken::UnQSplice) => continue,
+            Some(_) => (),
         }
-        if self.0.until_any(&[')', ' ', '\n', '\r', '\t']) {
-            use std::str::FromStr;
-            let lexeme = self.0.extract_string();
-            return match &lexeme[..] {
-                "#t" => Some(LispToken::True),
-                "#f" => Some(LispToken::False),
-                num => match f64::from_str(num) {
-                    Ok(n) => Some(LispToken::Number(n)),
-                    _ => Some(LispToken::Symbol(lexeme)),
-                },
-            };
+        if depth == 0 {
+            return;
         }
-        None
     }
 }
 
+fn get_token<S: Cursor>(src: &mut S) -> Option<LispToken<S::Lexeme>> {
+    if let Some((_, literal)) = string_literal::scan_string_literal(src, '"') {
+        return Some(match literal {
+            Ok(s) => LispToken::String(s),
+            Err(err) => LispToken::Error(err),
+        });
+    }
+    if let Some(lexeme) = src.accept_any(&[')', '(', '\'', '`', ',']) {
+        let token = match lexeme {
+            '(' => LispToken::OParen,
+            ')' => LispToken::CParen,
+            '\'' => LispToken::Quote,
+            '`' => LispToken::QuasiQuote,
+            ',' => {
+                if src.accept(&'@').is_some() {
+                    LispToken::UnQSplice
+                } else {
+                    LispToken::UnQuote
+                }
+            }
+            _ => unreachable!(),
+        };
+        src.extract_lexeme(); // ignore
+        return Some(token);
+    }
+    let unicode = src.unicode();
+    // ; starts a comment so it ends symbols too
+    if src.accept_if(|c| c != ')' && !helpers::is_space(unicode, c)).is_some() {
+        src.skip_while(|c| c != ')' && c != ';' && !helpers::is_space(unicode, c));
+        use std::str::FromStr;
+        let lexeme = src.extract_lexeme();
+        return match lexeme.as_ref() {
+            "#t" => Some(LispToken::True),
+            "#f" => Some(LispToken::False),
+            num => match f64::from_str(num) {
+                Ok(n) => Some(LispToken::Number(n)),
+                _ => Some(LispToken::Symbol(lexeme)),
+            },
+        };
+    }
+    None
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
@@ -94,7 +268,7 @@ mod tests {
                 Symbol(format!("max")),
                 Quote,
                 Symbol(format!("a")),
-                String(format!("\"hello\"")),
+                String(format!("hello")),
                 CParen,
             ],
         ];
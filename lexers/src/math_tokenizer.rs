@@ -1,31 +1,63 @@
 #![deny(warnings)]
 
+use crate::comments::{self, Comments};
+use crate::helpers::{self, Cursor};
+use crate::number::{self, NumberLiteral};
 use crate::scanner::Scanner;
+use crate::span::{Span, Spanned, WithSpans};
+use crate::str_scanner::StrScanner;
+use crate::trivia::{self, Lossless, WithTrivia};
+use crate::units::{self, UnitRegistry};
+use std::borrow::Cow;
 
+// Lexemes are Strings, or slices of the source from StrMathTokenizer
 #[derive(Clone, PartialEq, Debug)]
-pub enum MathToken {
-    Unknown(String),
+pub enum MathToken<S = String> {
+    Unknown(S),
     Number(f64),
-    Quantity(f64, String, String),
-    Variable(String),
-    Function(String, usize), // arity
-    UOp(String),
-    BOp(String),
+    Quantity(f64, S, S),
+    Variable(S),
+    Function(S, usize), // arity
+    UOp(S),
+    BOp(S),
     OParen,
     CParen,
     Comma,
 }
 
+impl MathToken<&str> {
+    // Copy borrowed lexemes, eg: to keep tokens past the source
+    pub fn into_owned(self) -> MathToken {
+        match self {
+            MathToken::Unknown(s) => MathToken::Unknown(s.to_string()),
+            MathToken::Number(n) => MathToken::Number(n),
+            MathToken::Quantity(n, prefix, unit) =>
+                MathToken::Quantity(n, prefix.to_string(), unit.to_string()),
+            MathToken::Variable(s) => MathToken::Variable(s.to_string()),
+            MathToken::Function(s, arity) => MathToken::Function(s.to_string(), arity),
+            MathToken::UOp(s) => MathToken::UOp(s.to_string()),
+            MathToken::BOp(s) => MathToken::BOp(s.to_string()),
+            MathToken::OParen => MathToken::OParen,
+            MathToken::CParen => MathToken::CParen,
+            MathToken::Comma => MathToken::Comma,
+        }
+    }
+}
+
 pub struct MathTokenizer<I: Iterator<Item = char>> {
     src: Scanner<I>,
     prev: Option<MathToken>,
+    comments: Comments,
+    units: Cow<'static, UnitRegistry>,
 }
 
 impl<I: Iterator<Item = char>> MathTokenizer<I> {
     pub fn new(source: I) -> Self {
         MathTokenizer {
-            src: Scanner::new(source),
+            src: Scanner::text(source),
             prev: None,
+            comments: Comments::math(),
+            units: Cow::Borrowed(UnitRegistry::si_ref()),
         }
     }
 
@@ -33,57 +65,171 @@ impl<I: Iterator<Item = char>> MathTokenizer<I> {
         Scanner::new(Self::new(source))
     }
 
-    // when would a minus be unary? we need to know the prev token
-    fn makes_unary(prev: &Option<MathToken>) -> bool {
-        !matches!(*prev,
-            Some(MathToken::Number(_)) |
-            Some(MathToken::Variable(_)) |
-            Some(MathToken::CParen))
-    }
-
-    fn get_token(&mut self) -> Option<MathToken> {
-        self.src.scan_whitespace(); // discard whatever came before + and spaces
-        if let Some(op) = self.src.scan_math_op() {
-            return match op.as_ref() {
-                "(" => Some(MathToken::OParen),
-                ")" => Some(MathToken::CParen),
-                "," => Some(MathToken::Comma),
-                "!" => Some(MathToken::UOp(op)),
-                "-" if Self::makes_unary(&self.prev) => Some(MathToken::UOp(op)),
-                _ => Some(MathToken::BOp(op)),
-            };
-        }
-        if let Some(id) = self.src.scan_identifier() {
-            return match self.src.peek() {
-                Some('(') => Some(MathToken::Function(id, 0)),
-                _ => Some(MathToken::Variable(id)),
-            };
-        }
-        if let Some(num) = self.src.scan_number() {
-            self.src.scan_whitespace(); // discard whatever came before + and spaces
-            use std::str::FromStr;
-            let value = f64::from_str(&num).unwrap();
-            if let Some((prefix, unit)) = self.src.scan_unit() {
-                return Some(MathToken::Quantity(value, prefix, unit));
-            }
-            return Some(MathToken::Number(value));
-        }
-        if self.src.next().is_some() {
-            return Some(MathToken::Unknown(self.src.extract_string()));
-        }
-        None
+    // Accept unicode identifiers, digits and whitespace (see Scanner::unicode)
+    pub fn unicode(self, unicode: bool) -> Self {
+        MathTokenizer { src: self.src.unicode(unicode), ..self }
+    }
+
+    // Comments to skip, # and // line comments by default
+    pub fn comments(self, comments: Comments) -> Self {
+        MathTokenizer { comments, ..self }
+    }
+
+    // Units of quantities like 3 km, SI units by default
+    pub fn units(self, units: UnitRegistry) -> Self {
+        MathTokenizer { units: Cow::Owned(units), ..self }
+    }
+
+    // Emit tokens along with their position in the source
+    pub fn with_spans(self) -> WithSpans<Self> {
+        WithSpans(self)
+    }
+
+    // Emit tokens with the source text and comments around them
+    pub fn lossless(self) -> WithTrivia<Self> {
+        WithTrivia::new(MathTokenizer { src: self.src.record(true), ..self })
     }
 }
 
 impl<I: Iterator<Item = char>> Iterator for MathTokenizer<I> {
     type Item = MathToken;
     fn next(&mut self) -> Option<Self::Item> {
-        let token = self.get_token();
-        self.prev = token.clone();
-        token
+        next_spanned(&mut self.src, &mut self.prev, &self.comments, &self.units).map(|t| t.value)
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for WithSpans<MathTokenizer<I>> {
+    type Item = Spanned<MathToken>;
+    fn next(&mut self) -> Option<Self::Item> {
+        next_spanned(&mut self.0.src, &mut self.0.prev, &self.0.comments, &self.0.units)
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for WithTrivia<MathTokenizer<I>> {
+    type Item = Lossless<MathToken>;
+    fn next(&mut self) -> Option<Self::Item> {
+        trivia::next_lossless(&mut self.tokenizer, &mut self.done, |t| &mut t.src,
+                              |t| { comments::skip_comments(&mut t.src, &t.comments); },
+                              |t| next_spanned(&mut t.src, &mut t.prev, &t.comments, &t.units))
+    }
+}
+
+// A MathTokenizer over a &str, its tokens borrow lexemes from the source
+pub struct StrMathTokenizer<'a> {
+    src: StrScanner<'a>,
+    prev: Option<MathToken<&'a str>>,
+    comments: Comments,
+    units: Cow<'static, UnitRegistry>,
+}
+
+impl<'a> StrMathTokenizer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        StrMathTokenizer {
+            src: StrScanner::new(source),
+            prev: None,
+            comments: Comments::math(),
+            units: Cow::Borrowed(UnitRegistry::si_ref()),
+        }
+    }
+
+    pub fn scanner(source: &'a str) -> Scanner<Self> {
+        Scanner::new(Self::new(source))
+    }
+
+    // Accept unicode identifiers, digits and whitespace (see Scanner::unicode)
+    pub fn unicode(self, unicode: bool) -> Self {
+        StrMathTokenizer { src: self.src.unicode(unicode), ..self }
+    }
+
+    // Comments to skip, # and // line comments by default
+    pub fn comments(self, comments: Comments) -> Self {
+        StrMathTokenizer { comments, ..self }
+    }
+
+    // Units of quantities like 3 km, SI units by default
+    pub fn units(self, units: UnitRegistry) -> Self {
+        StrMathTokenizer { units: Cow::Owned(units), ..self }
+    }
+
+    // Emit tokens along with their position in the source
+    pub fn with_spans(self) -> WithSpans<Self> {
+        WithSpans(self)
+    }
+}
+
+impl<'a> Iterator for StrMathTokenizer<'a> {
+    type Item = MathToken<&'a str>;
+    fn next(&mut self) -> Option<Self::Item> {
+        next_spanned(&mut self.src, &mut self.prev, &self.comments, &self.units).map(|t| t.value)
     }
 }
 
+impl<'a> Iterator for WithSpans<StrMathTokenizer<'a>> {
+    type Item = Spanned<MathToken<&'a str>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        next_spanned(&mut self.0.src, &mut self.0.prev, &self.0.comments, &self.0.units)
+    }
+}
+
+// when would a minus be unary? we need to know the prev token
+fn makes_unary<S>(prev: &Option<MathToken<S>>) -> bool {
+    !matches!(*prev,
+        Some(MathToken::Number(_)) |
+        Some(MathToken::Variable(_)) |
+        Some(MathToken::CParen))
+}
+
+fn next_spanned<S: Cursor>(src: &mut S, prev: &mut Option<MathToken<S::Lexeme>>,
+                           comments: &Comments, units: &UnitRegistry)
+        -> Option<Spanned<MathToken<S::Lexeme>>> {
+    // discard whatever came before, spaces and comments
+    comments::skip_comments(src, comments);
+    let start = src.location();
+    let token = get_token(src, prev, units);
+    *prev = token.clone();
+    let span = Span { start, end: src.location() };
+    token.map(|t| Spanned::new(t, span))
+}
+
+fn get_token<S: Cursor>(src: &mut S, prev: &Option<MathToken<S::Lexeme>>, units: &UnitRegistry)
+        -> Option<MathToken<S::Lexeme>> {
+    if let Some(op) = helpers::scan_math_op(src) {
+        return match op.as_ref() {
+            "(" => Some(MathToken::OParen),
+            ")" => Some(MathToken::CParen),
+            "," => Some(MathToken::Comma),
+            "!" => Some(MathToken::UOp(op)),
+            "-" if makes_unary(prev) => Some(MathToken::UOp(op)),
+            _ => Some(MathToken::BOp(op)),
+        };
+    }
+    if let Some(id) = helpers::scan_identifier(src) {
+        return match src.peek() {
+            Some('(') => Some(MathToken::Function(id, 0)),
+            _ => Some(MathToken::Variable(id)),
+        };
+    }
+    if let Some((num, literal)) = number::scan_number_literal(src) {
+        // no complex numbers here
+        if let NumberLiteral::Imaginary { .. } = literal {
+            return Some(MathToken::Unknown(num));
+        }
+        // units may follow after spaces, else leave them out of the span
+        let backtrack = src.mark();
+        let unicode = src.unicode();
+        src.skip_while(|c| helpers::is_space(unicode, c));
+        if let Some((prefix, unit)) = units::scan_unit(src, units) {
+            return Some(MathToken::Quantity(literal.value(), prefix, unit));
+        }
+        src.reset(backtrack);
+        return Some(MathToken::Number(literal.value()));
+    }
+    if src.next_char().is_some() {
+        return Some(MathToken::Unknown(src.extract_lexeme()));
+    }
+    None
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
@@ -202,4 +348,46 @@ mod tests {
         }
         assert_eq!(lx.next(), None);
     }
+
+    #[test]
+    fn number_literals() {
+        let lx = MathTokenizer::new("0x1F + 0b101 * 1_000 - .5 + 0o17 km + 2i".chars());
+        let expect = vec![
+            Number(31.0),
+            BOp("+".to_string()),
+            Number(5.0),
+            BOp("*".to_string()),
+            Number(1000.0),
+            BOp("-".to_string()),
+            Number(0.5),
+            BOp("+".to_string()),
+            Quantity(15.0, "k".to_string(), "m".to_string()),
+            BOp("+".to_string()),
+            Unknown("2i".to_string()),
+        ];
+        assert_eq!(lx.collect::<Vec<_>>(), expect);
+    }
+
+    #[test]
+    fn logic_ops() {
+        let lx = MathTokenizer::new("x!=1 && 3! >= -y || c ? a : b".chars());
+        let expect = vec![
+            Variable("x".to_string()),
+            BOp("!=".to_string()),
+            Number(1.0),
+            BOp("&&".to_string()),
+            Number(3.0),
+            UOp("!".to_string()),
+            BOp(">=".to_string()),
+            UOp("-".to_string()),
+            Variable("y".to_string()),
+            BOp("||".to_string()),
+            Variable("c".to_string()),
+            BOp("?".to_string()),
+            Variable("a".to_string()),
+            BOp(":".to_string()),
+            Variable("b".to_string()),
+        ];
+        assert_eq!(lx.collect::<Vec<_>>(), expect);
+    }
 }
@@ -0,0 +1,216 @@
+#![deny(warnings)]
+
+use crate::helpers::{is_digit, is_ident_continue, normalize_digits, Cursor};
+use crate::scanner::Scanner;
+use crate::str_scanner::StrScanner;
+use std::str::FromStr;
+
+/// A number scanned by `scan_number_literal` and the radix it was written in.
+/// Integers that don't fit an i64 are read as floats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumberLiteral {
+    Int { value: i64, radix: u32 },
+    Float { value: f64, radix: u32 },
+    Imaginary { value: f64, radix: u32 },
+}
+
+impl NumberLiteral {
+    pub fn value(&self) -> f64 {
+        match *self {
+            NumberLiteral::Int { value, .. } => value as f64,
+            NumberLiteral::Float { value, .. } => value,
+            NumberLiteral::Imaginary { value, .. } => value,
+        }
+    }
+
+    pub fn radix(&self) -> u32 {
+        match *self {
+            NumberLiteral::Int { radix, .. } => radix,
+            NumberLiteral::Float { radix, .. } => radix,
+            NumberLiteral::Imaginary { radix, .. } => radix,
+        }
+    }
+}
+
+// Skip digits with single '_' separators between them (eg: 1_000), result
+// is if any digits were found. Digits are appended to 'digits'.
+fn skip_digits<S: Cursor>(s: &mut S, digit: impl Fn(char) -> bool, digits: &mut String) -> bool {
+    match s.accept_if(&digit) {
+        Some(d) => digits.push(d),
+        None => return false,
+    }
+    loop {
+        let backtrack = s.mark();
+        let separated = s.accept(&'_').is_some();
+        match s.accept_if(&digit) {
+            Some(d) => digits.push(d),
+            None => {
+                if separated {
+                    s.reset(backtrack);
+                }
+                return true;
+            }
+        }
+    }
+}
+
+// Accept 'word' ignoring ASCII case
+fn accept_word<S: Cursor>(s: &mut S, word: &str) -> bool {
+    let backtrack = s.mark();
+    for w in word.chars() {
+        if s.accept_if(|c| c.eq_ignore_ascii_case(&w)).is_none() {
+            s.reset(backtrack);
+            return false;
+        }
+    }
+    true
+}
+
+// Value of digits in some radix, lossy for big numbers
+fn radix_value(digits: &str, radix: u32) -> f64 {
+    digits.chars()
+        .filter_map(|d| d.to_digit(radix))
+        .fold(0.0, |acc, d| acc * radix as f64 + d as f64)
+}
+
+// 0x, 0o and 0b integers or hex floats like 0x1.8p3 (after the sign)
+fn scan_radix<S: Cursor>(s: &mut S) -> Option<(f64, Option<i64>, u32)> {
+    let backtrack = s.mark();
+    s.accept(&'0')?;
+    let radix = match s.accept_any(&['x', 'X', 'o', 'O', 'b', 'B']) {
+        Some('x') | Some('X') => 16,
+        Some('o') | Some('O') => 8,
+        Some('b') | Some('B') => 2,
+        _ => {
+            s.reset(backtrack);
+            return None;
+        }
+    };
+    let digit = |c: char| c.is_digit(radix);
+    let mut digits = String::new();
+    if !skip_digits(s, digit, &mut digits) {
+        s.reset(backtrack);
+        return None;
+    }
+    let int = i64::from_str_radix(&digits, radix).ok();
+    if radix != 16 {
+        return Some((radix_value(&digits, radix), int, radix));
+    }
+    // hex floats have a fraction and/or a binary exponent
+    let mut fraction = String::new();
+    let backtrack = s.mark();
+    if s.accept(&'.').is_some() && !skip_digits(s, digit, &mut fraction) {
+        s.reset(backtrack);
+    }
+    let mut exponent = String::new();
+    let backtrack = s.mark();
+    if s.accept_any(&['p', 'P']).is_some() {
+        let sign = s.accept_any(&['+', '-']);
+        if skip_digits(s, |c| c.is_ascii_digit(), &mut exponent) {
+            exponent.insert(0, sign.unwrap_or('+'));
+        } else {
+            s.reset(backtrack);
+        }
+    }
+    if fraction.is_empty() && exponent.is_empty() {
+        return Some((radix_value(&digits, 16), int, 16));
+    }
+    let mantissa = radix_value(&digits, 16)
+        + radix_value(&fraction, 16) / 16f64.powi(fraction.len() as i32);
+    let exponent = i32::from_str(&exponent).unwrap_or(0);
+    Some((mantissa * 2f64.powi(exponent), None, 16))
+}
+
+// Decimal numbers like 1_000, 2.5, .5 or 1e-3 (after the sign)
+fn scan_decimal<S: Cursor>(s: &mut S) -> Option<(f64, Option<i64>)> {
+    let unicode = s.unicode();
+    let digit = |c| is_digit(unicode, c);
+    let mut number = String::new();
+    let integer = skip_digits(s, digit, &mut number);
+    // fractional part, requires digits after the dot
+    let backtrack = s.mark();
+    let mut fraction = String::new();
+    if s.accept(&'.').is_some() && !skip_digits(s, digit, &mut fraction) {
+        s.reset(backtrack);
+    }
+    if !integer && fraction.is_empty() {
+        return None;
+    }
+    let int = match fraction.is_empty() {
+        true => i64::from_str(&normalize_digits(&number)).ok(),
+        false => None,
+    };
+    if !fraction.is_empty() {
+        number.push('.');
+        number.push_str(&fraction);
+    }
+    // exponent part
+    let backtrack = s.mark();
+    if s.accept_any(&['e', 'E']).is_some() {
+        let sign = s.accept_any(&['+', '-']);
+        let mut exponent = String::new();
+        if skip_digits(s, digit, &mut exponent) {
+            number.push('e');
+            number.extend(sign);
+            number.push_str(&exponent);
+            let value = f64::from_str(&normalize_digits(&number)).ok()?;
+            return Some((value, None));
+        }
+        s.reset(backtrack);
+    }
+    let value = f64::from_str(&normalize_digits(&number)).ok()?;
+    Some((value, int))
+}
+
+// Scan numbers in any of the forms below with an optional sign and an 'i'
+// suffix for imaginary numbers:
+//   decimals 42, 1_000_000, 2.5, .5, 6.02e23
+//   radix integers 0xff, 0o17, 0b1010 and hex floats 0x1.8p3
+//   inf, infinity and nan (any case)
+pub(crate) fn scan_number_literal<S: Cursor>(s: &mut S) -> Option<(S::Lexeme, NumberLiteral)> {
+    let backtrack = s.mark();
+    let sign = match s.accept_any(&['+', '-']) {
+        Some('-') => -1,
+        _ => 1,
+    };
+    let (value, int, radix, word) = if let Some((value, int, radix)) = scan_radix(s) {
+        (value, int, radix, false)
+    } else if let Some((value, int)) = scan_decimal(s) {
+        (value, int, 10, false)
+    } else if accept_word(s, "infinity") || accept_word(s, "inf") {
+        (f64::INFINITY, None, 10, true)
+    } else if accept_word(s, "nan") {
+        (f64::NAN, None, 10, true)
+    } else {
+        s.reset(backtrack);
+        return None;
+    };
+    let imaginary = s.accept(&'i').is_some();
+    // inf and nan are words, don't take them out of identifiers like 'info'
+    let unicode = s.unicode();
+    if word && s.peek().is_some_and(|c| is_ident_continue(unicode, c)) {
+        s.reset(backtrack);
+        return None;
+    }
+    let value = sign as f64 * value;
+    let literal = match (int, imaginary) {
+        (_, true) => NumberLiteral::Imaginary { value, radix },
+        (Some(int), false) => NumberLiteral::Int { value: sign * int, radix },
+        (None, false) => NumberLiteral::Float { value, radix },
+    };
+    Some((s.extract_lexeme(), literal))
+}
+
+impl<I: Iterator<Item = char>> Scanner<I> {
+    // scan any number literal, see NumberLiteral
+    pub fn scan_number_literal(&mut self) -> Option<(String, NumberLiteral)> {
+        scan_number_literal(self)
+    }
+}
+
+impl<'a> StrScanner<'a> {
+    // scan any number literal, see NumberLiteral
+    pub fn scan_number_literal(&mut self) -> Option<(&'a str, NumberLiteral)> {
+        scan_number_literal(self)
+    }
+}
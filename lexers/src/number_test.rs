@@ -0,0 +1,93 @@
+#![deny(warnings)]
+
+use crate::number::NumberLiteral::{self, *};
+use crate::scanner::Scanner;
+use crate::str_scanner::StrScanner;
+use crate::{MathToken, MathTokenizer};
+
+fn scan(input: &str) -> Option<(String, NumberLiteral)> {
+    let literal = StrScanner::new(input).scan_number_literal();
+    let expected = Scanner::text(input.chars()).scan_number_literal();
+    // debug strings so NaN compares equal
+    assert_eq!(format!("{:?}", literal.map(|(n, l)| (n.to_string(), l))),
+               format!("{:?}", expected), "{}", input);
+    expected
+}
+
+#[test]
+fn decimals() {
+    let tests = [
+        ("42", Int { value: 42, radix: 10 }),
+        ("-42", Int { value: -42, radix: 10 }),
+        ("1_000_000", Int { value: 1_000_000, radix: 10 }),
+        ("2.5", Float { value: 2.5, radix: 10 }),
+        (".5", Float { value: 0.5, radix: 10 }),
+        ("-.25e2", Float { value: -25.0, radix: 10 }),
+        ("6.02E+23", Float { value: 6.02e23, radix: 10 }),
+        ("1_0e1_0", Float { value: 10e10, radix: 10 }),
+        ("435i", Imaginary { value: 435.0, radix: 10 }),
+        ("-54.3i", Imaginary { value: -54.3, radix: 10 }),
+        ("99999999999999999999", Float { value: 1e20, radix: 10 }),
+    ];
+    for (input, expected) in tests {
+        assert_eq!(scan(input), Some((input.to_string(), expected)));
+    }
+}
+
+#[test]
+fn radix() {
+    let tests = [
+        ("0xff", Int { value: 255, radix: 16 }),
+        ("0XdEaD_bEeF", Int { value: 0xdead_beef, radix: 16 }),
+        ("0o17", Int { value: 15, radix: 8 }),
+        ("-0b1010", Int { value: -10, radix: 2 }),
+        ("0x1.8p3", Float { value: 12.0, radix: 16 }),
+        ("0x1p-2", Float { value: 0.25, radix: 16 }),
+        ("0x10i", Imaginary { value: 16.0, radix: 16 }),
+    ];
+    for (input, expected) in tests {
+        assert_eq!(scan(input), Some((input.to_string(), expected)));
+    }
+    // 0x needs digits, this is the number 0 followed by 'x'
+    assert_eq!(scan("0x.8"), Some(("0".to_string(), Int { value: 0, radix: 10 })));
+}
+
+#[test]
+fn partial() {
+    // separators and dots need digits on both sides
+    let tests = [
+        ("1__0", "1"), ("1_", "1"), ("3.", "3"), ("3.x", "3"), ("0b12", "0b1"),
+        ("2e", "2"), ("2e+x", "2"), ("0x1p", "0x1"), ("7km", "7"), ("1e999km", "1e999"),
+    ];
+    for (input, lexeme) in tests {
+        assert_eq!(scan(input).map(|(n, _)| n), Some(lexeme.to_string()), "{}", input);
+    }
+    for input in ["_1", ".", "-", "x1", "", "info", "nanny", "-e5"] {
+        assert_eq!(scan(input), None, "{}", input);
+    }
+}
+
+#[test]
+fn special() {
+    assert_eq!(scan("inf").unwrap().1, Float { value: f64::INFINITY, radix: 10 });
+    assert_eq!(scan("-Infinity").unwrap().1, Float { value: f64::NEG_INFINITY, radix: 10 });
+    assert_eq!(scan("infi").unwrap().1, Imaginary { value: f64::INFINITY, radix: 10 });
+    assert_eq!(scan("inf+1").unwrap().0, "inf");
+    // decimals that overflow aren't words
+    assert_eq!(scan("1e999km").unwrap().1, Float { value: f64::INFINITY, radix: 10 });
+    let tokens: Vec<_> = MathTokenizer::new("1e999km".chars()).collect();
+    assert_eq!(tokens, vec![MathToken::Quantity(f64::INFINITY, "k".to_string(), "m".to_string())]);
+    let (lexeme, nan) = scan("NaN)").unwrap();
+    assert_eq!(lexeme, "NaN");
+    assert!(nan.value().is_nan());
+    assert_eq!(nan.radix(), 10);
+}
+
+#[test]
+fn unicode() {
+    let mut s = Scanner::text("٣_٤.٥ x".chars()).unicode(true);
+    assert_eq!(s.scan_number_literal(), Some(("٣_٤.٥".to_string(), Float { value: 34.5, radix: 10 })));
+    // radix digits are always ASCII
+    let mut s = StrScanner::new("0x٣").unicode(true);
+    assert_eq!(s.scan_number_literal(), Some(("0", Int { value: 0, radix: 10 })));
+}
@@ -0,0 +1,97 @@
+#![deny(warnings)]
+
+use std::io::{self, BufRead, BufReader, ErrorKind, Read};
+
+/// Decodes UTF-8 chars out of a `BufRead` to feed scanners and tokenizers
+/// with files or sockets. Reading stops on I/O errors and invalid UTF-8,
+/// which are kept in `error()`, so tokenize through `by_ref()`:
+///
+/// ```
+/// use lexers::{MathTokenizer, ReadChars};
+///
+/// let mut chars = ReadChars::from_read(&b"3 * x"[..]);
+/// let tokens: Vec<_> = MathTokenizer::new(chars.by_ref()).collect();
+/// assert_eq!(tokens.len(), 3);
+/// assert!(chars.error().is_none());
+/// ```
+pub struct ReadChars<R: BufRead> {
+    reader: R,
+    // bytes read so far, to tell where decoding failed
+    offset: usize,
+    error: Option<io::Error>,
+}
+
+impl<R: Read> ReadChars<BufReader<R>> {
+    pub fn from_read(reader: R) -> Self {
+        ReadChars::new(BufReader::new(reader))
+    }
+}
+
+impl<R: BufRead> ReadChars<R> {
+    pub fn new(reader: R) -> Self {
+        ReadChars { reader, offset: 0, error: None }
+    }
+
+    // Why reading stopped early, if it did
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok([]) => return Ok(None),
+                Ok(buf) => {
+                    let byte = buf[0];
+                    self.reader.consume(1);
+                    self.offset += 1;
+                    return Ok(Some(byte));
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn read_char(&mut self) -> io::Result<Option<char>> {
+        let start = self.offset;
+        let invalid = || io::Error::new(ErrorKind::InvalidData,
+                                        format!("invalid UTF-8 at byte {}", start));
+        let mut bytes = [0; 4];
+        bytes[0] = match self.read_byte()? {
+            Some(byte) => byte,
+            None => return Ok(None),
+        };
+        let width = match bytes[0] {
+            0x00..=0x7f => return Ok(Some(bytes[0] as char)),
+            0xc2..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf4 => 4,
+            _ => return Err(invalid()),
+        };
+        for byte in bytes.iter_mut().take(width).skip(1) {
+            *byte = self.read_byte()?.ok_or_else(invalid)?;
+        }
+        match std::str::from_utf8(&bytes[..width]) {
+            Ok(c) => Ok(c.chars().next()),
+            Err(_) => Err(invalid()),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ReadChars<R> {
+    type Item = char;
+    fn next(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        self.read_char().unwrap_or_else(|err| {
+            self.error = Some(err);
+            None
+        })
+    }
+}
@@ -0,0 +1,125 @@
+#![deny(warnings)]
+
+use crate::read_chars::ReadChars;
+use crate::scanner::Scanner;
+use crate::{LispToken, LispTokenizer, MathToken, MathTokenizer};
+use std::io::{self, BufReader, ErrorKind, Read};
+
+// Repeats 'chunk' up to 'len' bytes without holding the whole input
+struct Generator {
+    chunk: &'static [u8],
+    len: usize,
+    pos: usize,
+}
+
+impl Generator {
+    // 'chunk' repeated to about 'len' bytes, result has the repetitions
+    fn new(chunk: &'static str, len: usize) -> (Self, usize) {
+        let times = len / chunk.len();
+        (Generator { chunk: chunk.as_bytes(), len: times * chunk.len(), pos: 0 }, times)
+    }
+}
+
+impl Read for Generator {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() && self.pos < self.len {
+            buf[n] = self.chunk[self.pos % self.chunk.len()];
+            n += 1;
+            self.pos += 1;
+        }
+        Ok(n)
+    }
+}
+
+// Fails once its bytes run out
+struct Broken(&'static [u8]);
+
+impl Read for Broken {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() {
+            return Err(io::Error::other("disk on fire"));
+        }
+        buf[0] = self.0[0];
+        self.0 = &self.0[1..];
+        Ok(1)
+    }
+}
+
+#[test]
+fn utf8() {
+    let input = "añb €x 😀!";
+    // tiny buffers split multi-byte chars
+    let chars = ReadChars::new(BufReader::with_capacity(1, input.as_bytes()));
+    assert_eq!(chars.collect::<String>(), input);
+
+    let mut chars = ReadChars::from_read(&b"ab\xffcd"[..]);
+    assert_eq!(chars.by_ref().collect::<String>(), "ab");
+    let err = chars.take_error().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "invalid UTF-8 at byte 2");
+    assert_eq!(chars.next(), Some('c'));
+
+    for bad in [&b"x\xe2\x82"[..], b"x\xc3(", b"x\xed\xa0\x80", b"x\xc0\xaf"] {
+        let mut chars = ReadChars::from_read(bad);
+        assert_eq!(chars.by_ref().collect::<String>(), "x");
+        assert_eq!(chars.error().unwrap().to_string(), "invalid UTF-8 at byte 1");
+        // stays stopped
+        assert_eq!(chars.next(), None);
+    }
+
+    let mut chars = ReadChars::from_read(Broken(b"(+ 1"));
+    let tokens: Vec<_> = LispTokenizer::new(chars.by_ref()).collect();
+    assert_eq!(tokens, vec![LispToken::OParen, LispToken::Symbol("+".to_string()),
+                            LispToken::Number(1.0)]);
+    assert_eq!(chars.error().unwrap().to_string(), "disk on fire");
+}
+
+#[test]
+fn bounded_buffer() {
+    // ~2MB of tokens through a plain scanner, the buffer never holds more
+    // than the token being scanned and a char of lookahead
+    let chunk = "x1 := 3.25e2 * (y_2 - 42) # note\n";
+    let (input, times) = Generator::new(chunk, 2 << 20);
+    let mut chars = ReadChars::from_read(input);
+    let mut s = Scanner::text(chars.by_ref());
+    let mut tokens = 0;
+    let mut max_buffered = 0;
+    loop {
+        s.scan_whitespace();
+        max_buffered = max_buffered.max(s.buffered());
+        if s.accept(&'#').is_some() {
+            s.until_any(&['\n']);
+            s.extract();
+        } else if s.scan_math_op().or_else(|| s.scan_number())
+                .or_else(|| s.scan_identifier()).is_some() {
+            tokens += 1;
+        } else {
+            break;
+        }
+        max_buffered = max_buffered.max(s.buffered());
+    }
+    assert!(chars.error().is_none());
+    assert_eq!(tokens, 9 * times);
+    assert!(max_buffered <= "3.25e2".len() + 2, "{}", max_buffered);
+}
+
+#[test]
+fn stream_tokenizers() {
+    let chunk = "(define (f x) ; twice\n  (* 2 x))\n";
+    let (input, times) = Generator::new(chunk, 2 << 20);
+    let mut chars = ReadChars::from_read(input);
+    let closed = LispTokenizer::new(chars.by_ref())
+        .filter(|t| *t == LispToken::CParen)
+        .count();
+    assert!(chars.error().is_none());
+    assert_eq!(closed, 3 * times);
+
+    let chunk = "1 km + x_1 * 2.5 // more\n";
+    let (input, times) = Generator::new(chunk, 2 << 20);
+    let chars = ReadChars::from_read(input);
+    let quantities = MathTokenizer::new(chars)
+        .filter(|t| matches!(t, MathToken::Quantity(..)))
+        .count();
+    assert_eq!(quantities, times);
+}
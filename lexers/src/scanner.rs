@@ -1,5 +1,10 @@
 #![deny(warnings)]
 
+use crate::span::{Position, Span};
+
+/// Reads items on demand and keeps them from the last `extract` up to the
+/// furthest one read, so memory is bounded by the longest token plus some
+/// lookahead rather than by the input. See `ReadChars` for files.
 pub struct Scanner<I: Iterator>
 where
     I::Item: Clone,
@@ -7,6 +12,13 @@ where
     src: I,
     buf: Vec<I::Item>,
     pos: isize,
+    // position of the first item in buf, moved forward on extract
+    start: Position,
+    step: fn(&mut Position, &I::Item),
+    // scan_* helpers use unicode char classes
+    unicode: bool,
+    // extracted items kept for take_recorded, see record()
+    recorded: Option<Vec<I::Item>>,
 }
 
 // Scanners are Iterators
@@ -34,14 +46,40 @@ where
     I: Iterator,
     I::Item: Clone,
 {
+    // Positions of a generic scanner count items, see Scanner::text for chars
     pub fn new(source: I) -> Scanner<I> {
         Scanner {
             src: source,
             buf: Vec::new(),
             pos: -1,
+            start: Position::default(),
+            step: Position::step_item,
+            unicode: false,
+            recorded: None,
+        }
+    }
+
+    // Position right after the current item
+    pub fn location(&self) -> Position {
+        let mut position = self.start;
+        let n = std::cmp::min(self.pos + 1, self.buf.len() as isize) as usize;
+        self.buf[..n].iter().for_each(|item| (self.step)(&mut position, item));
+        position
+    }
+
+    // Span of the items that the next extract would return
+    pub fn span(&self) -> Span {
+        Span {
+            start: self.start,
+            end: self.location(),
         }
     }
 
+    // Items held since the last extract, including those read ahead
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
     // Allows getting current buffer position to backtrack
     pub fn buffer_pos(&self) -> isize {
         self.pos
@@ -106,8 +144,50 @@ where
         // Split buffer and keep the remainder
         let mut remaining = self.buf.split_off(split_point as usize);
         std::mem::swap(&mut self.buf, &mut remaining);
+        remaining.iter().for_each(|item| (self.step)(&mut self.start, item));
+        if let Some(recorded) = self.recorded.as_mut() {
+            recorded.extend(remaining.iter().cloned());
+        }
         remaining
     }
+
+    // Keep a copy of extracted items, eg: to rebuild the source of tokens
+    // along with the whitespace and comments between them
+    pub fn record(mut self, record: bool) -> Self {
+        self.recorded = match record {
+            true => Some(Vec::new()),
+            false => None,
+        };
+        self
+    }
+
+    // Extract and return every item extracted since the last call, empty
+    // unless recording
+    pub fn take_recorded(&mut self) -> Vec<I::Item> {
+        self.extract();
+        self.recorded.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+}
+
+impl<I: Iterator<Item = char>> Scanner<I> {
+    // A scanner over text, its positions track lines and byte offsets
+    pub fn text(source: I) -> Scanner<I> {
+        Scanner {
+            step: Position::step_char,
+            ..Scanner::new(source)
+        }
+    }
+
+    // Use unicode whitespace, digits and XID identifiers in scan_* helpers
+    // instead of the default ASCII ones
+    pub fn unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    pub fn is_unicode(&self) -> bool {
+        self.unicode
+    }
 }
 
 impl<I> Scanner<I>
@@ -0,0 +1,78 @@
+#![deny(warnings)]
+
+use std::fmt;
+
+/// Location in the source: 1-based line and column and a 0-based offset.
+/// For text the column counts chars and the offset counts bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position { line: 1, column: 1, offset: 0 }
+    }
+}
+
+impl Position {
+    // Move past one item of a source that isn't text
+    pub(crate) fn step_item<T>(&mut self, _: &T) {
+        self.column += 1;
+        self.offset += 1;
+    }
+
+    // Move past a char, new-lines start the next line
+    pub(crate) fn step_char(&mut self, c: &char) {
+        self.offset += c.len_utf8();
+        match c {
+            '\n' => {
+                self.line += 1;
+                self.column = 1;
+            }
+            _ => self.column += 1,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Source range of a token, from its first item up to (excluding) `end`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// A token along with where it was found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(value: T, span: Span) -> Self {
+        Spanned { value, span }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned { value: f(self.value), span: self.span }
+    }
+}
+
+/// Adapter returned by each tokenizer's `with_spans()`, it emits
+/// `Spanned` tokens instead of bare ones.
+pub struct WithSpans<T>(pub(crate) T);
@@ -0,0 +1,108 @@
+#![deny(warnings)]
+
+use crate::scanner::Scanner;
+use crate::span::{Position, Span, Spanned};
+use crate::{DelimTokenizer, EbnfTokenizer, LispToken, LispTokenizer, MathToken, MathTokenizer};
+
+fn pos(line: usize, column: usize, offset: usize) -> Position {
+    Position { line, column, offset }
+}
+
+// Compact (lexeme, "line:col-line:col") pairs to compare against
+fn spans<T>(tokens: impl Iterator<Item = Spanned<T>>) -> Vec<(T, String)> {
+    tokens.map(|t| (t.value, t.span.to_string())).collect()
+}
+
+#[test]
+fn scanner_positions() {
+    let mut s = Scanner::text("ab\nñc".chars());
+    assert_eq!(s.location(), pos(1, 1, 0));
+    s.next();
+    s.next();
+    assert_eq!(s.location(), pos(1, 3, 2));
+    // backtracking moves the location back
+    s.prev();
+    assert_eq!(s.location(), pos(1, 2, 1));
+    s.next();
+    s.next();
+    assert_eq!(s.span(), Span { start: pos(1, 1, 0), end: pos(2, 1, 3) });
+    assert_eq!(s.extract_string(), "ab\n");
+    assert_eq!(s.span(), Span { start: pos(2, 1, 3), end: pos(2, 1, 3) });
+    // offsets count bytes, columns count chars
+    s.next();
+    assert_eq!(s.location(), pos(2, 2, 5));
+    while s.next().is_some() {}
+    assert_eq!(s.location(), pos(2, 3, 6));
+    s.extract();
+    assert_eq!(s.location(), pos(2, 3, 6));
+
+    // other scanners count items
+    let mut s = Scanner::new(vec!["x", "\n", "y"].into_iter());
+    s.next();
+    s.next();
+    s.extract();
+    assert_eq!(s.location(), pos(1, 3, 2));
+}
+
+#[test]
+fn math_spans() {
+    let lx = MathTokenizer::new("3 km +\n  sin(x2)*4 ".chars()).with_spans();
+    assert_eq!(spans(lx), vec![
+        (MathToken::Quantity(3.0, "k".to_string(), "m".to_string()), "1:1-1:5".to_string()),
+        (MathToken::BOp("+".to_string()), "1:6-1:7".to_string()),
+        (MathToken::Function("sin".to_string(), 0), "2:3-2:6".to_string()),
+        (MathToken::OParen, "2:6-2:7".to_string()),
+        (MathToken::Variable("x2".to_string()), "2:7-2:9".to_string()),
+        (MathToken::CParen, "2:9-2:10".to_string()),
+        (MathToken::BOp("*".to_string()), "2:10-2:11".to_string()),
+        (MathToken::Number(4.0), "2:11-2:12".to_string()),
+    ]);
+    // unary detection still works when tracking spans
+    let lx = MathTokenizer::new("-x".chars()).with_spans();
+    let tokens: Vec<_> = lx.map(|t| t.value).collect();
+    assert_eq!(tokens, MathTokenizer::new("-x".chars()).collect::<Vec<_>>());
+    assert_eq!(tokens[0], MathToken::UOp("-".to_string()));
+}
+
+#[test]
+fn lisp_spans() {
+    let lx = LispTokenizer::new("(define x\n  \"hi\")".chars()).with_spans();
+    assert_eq!(spans(lx), vec![
+        (LispToken::OParen, "1:1-1:2".to_string()),
+        (LispToken::Symbol("define".to_string()), "1:2-1:8".to_string()),
+        (LispToken::Symbol("x".to_string()), "1:9-1:10".to_string()),
+        (LispToken::String("hi".to_string()), "2:3-2:7".to_string()),
+        (LispToken::CParen, "2:7-2:8".to_string()),
+    ]);
+}
+
+#[test]
+fn ebnf_spans() {
+    let grammar = "# rules\nexpr := 'né' @tag ;";
+    let lx = EbnfTokenizer::new(grammar.chars()).with_spans();
+    let tokens: Vec<_> = lx.collect();
+    let expected = vec![
+        ("expr", pos(2, 1, 8), pos(2, 5, 12)),
+        (":=", pos(2, 6, 13), pos(2, 8, 15)),
+        ("'", pos(2, 9, 16), pos(2, 10, 17)),
+        ("né", pos(2, 10, 17), pos(2, 12, 20)),
+        ("'", pos(2, 12, 20), pos(2, 13, 21)),
+        ("@tag", pos(2, 14, 22), pos(2, 18, 26)),
+        (";", pos(2, 19, 27), pos(2, 20, 28)),
+    ];
+    assert_eq!(tokens.len(), expected.len());
+    for (token, (value, start, end)) in tokens.iter().zip(expected) {
+        assert_eq!(*token, Spanned::new(value.to_string(), Span { start, end }));
+        assert_eq!(&grammar[start.offset..end.offset], value);
+    }
+}
+
+#[test]
+fn delim_spans() {
+    let lx = DelimTokenizer::new("a,,bc\nd".chars(), ",\n", true).with_spans();
+    assert_eq!(spans(lx), vec![
+        ("a".to_string(), "1:1-1:2".to_string()),
+        ("bc".to_string(), "1:4-1:6".to_string()),
+        ("d".to_string(), "2:1-2:2".to_string()),
+    ]);
+}
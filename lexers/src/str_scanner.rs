@@ -0,0 +1,210 @@
+#![deny(warnings)]
+
+use crate::helpers::{self, Cursor};
+use crate::span::{Position, Span};
+use crate::units::{self, UnitRegistry};
+
+/// A `Scanner` over a `&str` that hands out slices of the source instead of
+/// allocating a `String` per token. Buffer positions are byte offsets.
+#[derive(Clone, Debug)]
+pub struct StrScanner<'a> {
+    src: &'a str,
+    // byte offsets: start of the pending token and end of the current char
+    start: usize,
+    pos: usize,
+    // position of 'start' in lines and columns
+    location: Position,
+    // scan_* helpers use unicode char classes
+    unicode: bool,
+}
+
+// Scanners are Iterators
+impl<'a> Iterator for StrScanner<'a> {
+    type Item = char;
+    fn next(&mut self) -> Option<char> {
+        let next = self.src[self.pos..].chars().next()?;
+        self.pos += next.len_utf8();
+        Some(next)
+    }
+}
+
+impl<'a> StrScanner<'a> {
+    pub fn new(src: &'a str) -> Self {
+        StrScanner {
+            src,
+            start: 0,
+            pos: 0,
+            location: Position::default(),
+            unicode: false,
+        }
+    }
+
+    // Use unicode whitespace, digits and XID identifiers in scan_* helpers
+    // instead of the default ASCII ones
+    pub fn unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    pub fn is_unicode(&self) -> bool {
+        self.unicode
+    }
+
+    // Allows getting current buffer position to backtrack
+    pub fn buffer_pos(&self) -> usize {
+        self.pos
+    }
+
+    // Reset buffer position, normally used for backtracking. Positions
+    // before the pending token or not on a char boundary return false
+    pub fn set_buffer_pos(&mut self, pos: usize) -> bool {
+        if pos < self.start || !self.src.is_char_boundary(pos) {
+            return false;
+        }
+        self.pos = pos;
+        true
+    }
+
+    // Returns the current char on which the scanner is positioned
+    pub fn current(&self) -> Option<char> {
+        self.view().chars().next_back()
+    }
+
+    // Steps the scanner back and returns the char at that position
+    pub fn prev(&mut self) -> Option<char> {
+        if let Some(c) = self.current() {
+            self.pos -= c.len_utf8();
+        }
+        self.current()
+    }
+
+    // Returns the char ahead without actually advancing the scanner
+    pub fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    // Returns the previous char without actually backtracking the scanner
+    pub fn peek_prev(&self) -> Option<char> {
+        let mut view = self.view().chars();
+        view.next_back();
+        view.next_back()
+    }
+
+    // Returns the pending token, up to the current char
+    pub fn view(&self) -> &'a str {
+        &self.src[self.start..self.pos]
+    }
+
+    // Returns the source that hasn't been scanned yet
+    pub fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    // Consumes the pending token (which can be ignored)
+    pub fn extract(&mut self) -> &'a str {
+        let token = self.view();
+        token.chars().for_each(|c| self.location.step_char(&c));
+        self.start = self.pos;
+        token
+    }
+
+    // Position right after the current char
+    pub fn location(&self) -> Position {
+        let mut location = self.location;
+        self.view().chars().for_each(|c| location.step_char(&c));
+        location
+    }
+
+    // Span of the chars that the next extract would return
+    pub fn span(&self) -> Span {
+        Span {
+            start: self.location,
+            end: self.location(),
+        }
+    }
+
+    // Advance the scanner only if the next char is the expected one
+    pub fn accept(&mut self, what: &char) -> Option<char> {
+        Cursor::accept(self, what)
+    }
+
+    // Advance the scanner only if the next char is in the 'any' set
+    pub fn accept_any(&mut self, any: &[char]) -> Option<char> {
+        Cursor::accept_any(self, any)
+    }
+
+    // Advance the scanner only on a full match of 'what'
+    pub fn accept_all(&mut self, what: impl Iterator<Item = char>) -> bool {
+        Cursor::accept_all(self, what)
+    }
+
+    // Skip over the 'over' set, result is if the scanner was advanced
+    pub fn skip_all(&mut self, over: &[char]) -> bool {
+        Cursor::skip_all(self, over)
+    }
+
+    // Find an element in the 'any' set or EOF, return if the scanner advanced
+    pub fn until_any(&mut self, any: &[char]) -> bool {
+        Cursor::until_any(self, any)
+    }
+
+    pub fn scan_whitespace(&mut self) -> Option<&'a str> {
+        helpers::scan_whitespace(self)
+    }
+
+    pub fn scan_number(&mut self) -> Option<&'a str> {
+        helpers::scan_number(self)
+    }
+
+    pub fn scan_math_op(&mut self) -> Option<&'a str> {
+        helpers::scan_math_op(self)
+    }
+
+    pub fn scan_integer(&mut self) -> Option<&'a str> {
+        helpers::scan_integer(self)
+    }
+
+    pub fn scan_quoted_string(&mut self, q: char) -> Option<&'a str> {
+        helpers::scan_quoted_string(self, q)
+    }
+
+    pub fn scan_identifier(&mut self) -> Option<&'a str> {
+        helpers::scan_identifier(self)
+    }
+
+    pub fn scan_unit(&mut self) -> Option<(&'a str, &'a str)> {
+        units::scan_unit(self, UnitRegistry::si_ref())
+    }
+}
+
+impl<'a> Cursor for StrScanner<'a> {
+    type Lexeme = &'a str;
+
+    fn next_char(&mut self) -> Option<char> {
+        self.next()
+    }
+
+    fn mark(&self) -> usize {
+        self.pos
+    }
+
+    fn reset(&mut self, mark: usize) {
+        self.set_buffer_pos(mark);
+    }
+
+    fn extract_lexeme(&mut self) -> &'a str {
+        self.extract()
+    }
+
+    fn location(&self) -> Position {
+        StrScanner::location(self)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        StrScanner::peek(self)
+    }
+
+    fn unicode(&self) -> bool {
+        self.unicode
+    }
+}
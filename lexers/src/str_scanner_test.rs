@@ -0,0 +1,91 @@
+#![deny(warnings)]
+
+use crate::scanner::Scanner;
+use crate::span::Position;
+use crate::str_scanner::StrScanner;
+use crate::{DelimTokenizer, LispTokenizer, MathTokenizer};
+use crate::{StrDelimTokenizer, StrLispTokenizer, StrMathTokenizer};
+
+#[test]
+fn extremes() {
+    let mut s = StrScanner::new("just a test buffer@");
+    assert_eq!(s.prev(), None);
+    assert_eq!(s.peek_prev(), None);
+    assert_eq!(s.next(), Some('j'));
+    assert_eq!(s.prev(), None);
+    while s.next() != Some('@') {}
+    assert_eq!(s.current(), Some('@'));
+    assert_eq!(s.peek_prev(), Some('r'));
+    assert_eq!(s.prev(), Some('r'));
+    assert_eq!(s.prev(), Some('e'));
+    assert_eq!(s.next(), Some('r'));
+    assert_eq!(s.next(), Some('@'));
+    assert_eq!(s.next(), None);
+}
+
+#[test]
+fn extract() {
+    let source = "añb xyz";
+    let mut s = StrScanner::new(source);
+    assert_eq!(s.extract(), "");
+    s.next();
+    s.next();
+    // buffer positions are byte offsets
+    assert_eq!(s.buffer_pos(), 3);
+    assert!(!s.set_buffer_pos(2));
+    s.next();
+    let token = s.extract();
+    assert_eq!(token, "añb");
+    // the token borrows from the source
+    assert_eq!(token.as_ptr(), source.as_ptr());
+    assert!(!s.set_buffer_pos(0));
+    assert_eq!(s.scan_whitespace(), Some(" "));
+    assert_eq!(s.scan_identifier(), Some("xyz"));
+    assert_eq!(s.location(), Position { line: 1, column: 8, offset: 8 });
+    assert_eq!(s.rest(), "");
+}
+
+#[test]
+fn same_as_scanner() {
+    let inputs = [
+        "-54.3i", "54E+2x", "0x3f", "0b12", "\"a \\\" b\" c", "_id2 x", "3 km", "20µF", "<=",
+        ":=", "**2", "a+b", "!=x", "&&", "|x", "?:",
+    ];
+    for input in inputs {
+        let mut s = StrScanner::new(input);
+        let mut c = Scanner::text(input.chars());
+        assert_eq!(s.scan_number().map(str::to_string), c.scan_number(), "{}", input);
+        assert_eq!(s.scan_integer().map(str::to_string), c.scan_integer(), "{}", input);
+        assert_eq!(s.scan_math_op().map(str::to_string), c.scan_math_op(), "{}", input);
+        assert_eq!(s.scan_quoted_string('"').map(str::to_string),
+                   c.scan_quoted_string('"'), "{}", input);
+        assert_eq!(s.scan_identifier().map(str::to_string), c.scan_identifier(), "{}", input);
+        assert_eq!(s.scan_whitespace().map(str::to_string), c.scan_whitespace(), "{}", input);
+        assert_eq!(s.scan_unit().map(|(p, u)| (p.to_string(), u.to_string())),
+                   c.scan_unit(), "{}", input);
+        assert_eq!(s.location(), c.location(), "{}", input);
+    }
+}
+
+#[test]
+fn str_tokenizers() {
+    let input = "3.4e-2 * sin(x)/(7! % -4) *\n 30 km + max(2, x) $";
+    let tokens: Vec<_> = StrMathTokenizer::new(input).with_spans()
+        .map(|t| t.map(|t| t.into_owned()))
+        .collect();
+    assert_eq!(tokens, MathTokenizer::new(input.chars()).with_spans().collect::<Vec<_>>());
+
+    let input = "(max 'a \"hello\" `(,@b #t))";
+    let tokens: Vec<_> = StrLispTokenizer::new(input).map(|t| t.into_owned()).collect();
+    assert_eq!(tokens, LispTokenizer::new(input.chars()).collect::<Vec<_>>());
+
+    let input = "another, test, here,going on";
+    let tokens: Vec<_> = StrDelimTokenizer::new(input, " ,", true).collect();
+    assert_eq!(tokens, vec!["another", "test", "here", "going", "on"]);
+    let tokens: Vec<_> = StrDelimTokenizer::new(input, " ,", false).with_spans().collect();
+    let expected: Vec<_> = DelimTokenizer::new(input.chars(), " ,", false).with_spans().collect();
+    assert_eq!(tokens.len(), expected.len());
+    for (token, expected) in tokens.into_iter().zip(expected) {
+        assert_eq!(token.map(str::to_string), expected);
+    }
+}
@@ -0,0 +1,127 @@
+#![deny(warnings)]
+
+use crate::helpers::Cursor;
+use crate::scanner::Scanner;
+use crate::str_scanner::StrScanner;
+
+/// Decoded value of a string literal, or why it's bad: unterminated or
+/// with an invalid escape
+pub type StringLiteral = Result<String, String>;
+
+// Decode the escape after a '\', None for line continuations
+fn scan_escape<S: Cursor>(s: &mut S, q: char) -> Result<Option<char>, String> {
+    let c = match s.next_char() {
+        Some(c) => c,
+        None => return Err("unterminated string".to_string()),
+    };
+    let decoded = match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '0' => '\0',
+        '\\' | '"' | '\'' => c,
+        c if c == q => c,
+        // line continuation, skip the newline and indentation
+        '\n' => {
+            s.skip_while(|c| c.is_whitespace());
+            return Ok(None);
+        }
+        'u' => {
+            let mut hex = String::new();
+            if s.accept(&'{').is_some() {
+                while let Some(h) = s.accept_if(|c| c.is_ascii_hexdigit()) {
+                    hex.push(h);
+                }
+                if s.accept(&'}').is_some() && (1..=6).contains(&hex.len()) {
+                    let code = u32::from_str_radix(&hex, 16).unwrap();
+                    if let Some(c) = char::from_u32(code) {
+                        return Ok(Some(c));
+                    }
+                }
+            }
+            return Err(format!("invalid unicode escape '\\u{}'", hex));
+        }
+        c => return Err(format!("invalid escape '\\{}'", c)),
+    };
+    Ok(Some(decoded))
+}
+
+// Body of a raw string after the opening quote, ends on a quote followed
+// by as many '#' as it started with
+fn scan_raw_body<S: Cursor>(s: &mut S, q: char, hashes: usize) -> Result<String, String> {
+    let mut value = String::new();
+    while let Some(c) = s.next_char() {
+        if c == q {
+            let backtrack = s.mark();
+            if (0..hashes).all(|_| s.accept(&'#').is_some()) {
+                return Ok(value);
+            }
+            s.reset(backtrack);
+        }
+        value.push(c);
+    }
+    Err("unterminated string".to_string())
+}
+
+// Body of a string after the opening quote, the first error is reported
+// after reaching the closing quote so scanning can carry on past it
+fn scan_body<S: Cursor>(s: &mut S, q: char) -> Result<String, String> {
+    let mut value = String::new();
+    let mut error = None;
+    while let Some(c) = s.next_char() {
+        match c {
+            '\\' => match scan_escape(s, q) {
+                Ok(decoded) => value.extend(decoded),
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            },
+            c if c == q => return error.map_or(Ok(value), Err),
+            c => value.push(c),
+        }
+    }
+    Err(error.unwrap_or_else(|| "unterminated string".to_string()))
+}
+
+// Scan a string literal quoted with 'q' and decode it. Strings may span
+// lines and support the escapes \n \t \r \0 \\ \' \" \u{7FFF} and a '\' at
+// the end of a line to skip the newline and leading whitespace. Raw strings
+// like r"C:\path" or r#"say "hi""# are taken verbatim.
+// Result is None when there's no string ahead, else the lexeme and either
+// the value or an error for unterminated strings and invalid escapes. Bad
+// strings are consumed anyway, up to the closing quote or EOF.
+pub(crate) fn scan_string_literal<S: Cursor>(s: &mut S, q: char)
+        -> Option<(S::Lexeme, StringLiteral)> {
+    let backtrack = s.mark();
+    let value = if s.accept(&'r').is_some() {
+        let mut hashes = 0;
+        while s.accept(&'#').is_some() {
+            hashes += 1;
+        }
+        if s.accept(&q).is_none() {
+            s.reset(backtrack);
+            return None;
+        }
+        scan_raw_body(s, q, hashes)
+    } else {
+        s.accept(&q)?;
+        scan_body(s, q)
+    };
+    Some((s.extract_lexeme(), value))
+}
+
+impl<I: Iterator<Item = char>> Scanner<I> {
+    // scan and decode a string literal like "a\tb\u{e9}", r"raw" or
+    // r#"raw "quoted""#. None when there's no string ahead, else the lexeme
+    // and decoded value or an error for unterminated strings / bad escapes
+    pub fn scan_string_literal(&mut self, q: char) -> Option<(String, StringLiteral)> {
+        scan_string_literal(self, q)
+    }
+}
+
+impl<'a> StrScanner<'a> {
+    // scan and decode a string literal, see Scanner::scan_string_literal
+    pub fn scan_string_literal(&mut self, q: char) -> Option<(&'a str, StringLiteral)> {
+        scan_string_literal(self, q)
+    }
+}
@@ -0,0 +1,89 @@
+#![deny(warnings)]
+
+use crate::scanner::Scanner;
+use crate::str_scanner::StrScanner;
+use crate::{LispToken, LispTokenizer, StrLispTokenizer, StringLiteral};
+
+fn scan(input: &str) -> Option<(String, StringLiteral)> {
+    let literal = StrScanner::new(input).scan_string_literal('"');
+    let expected = Scanner::text(input.chars()).scan_string_literal('"');
+    assert_eq!(literal.map(|(l, v)| (l.to_string(), v)), expected, "{}", input);
+    expected
+}
+
+#[test]
+fn escapes() {
+    let tests = [
+        (r#""plain" x"#, r#""plain""#, "plain"),
+        (r#""a\tb\nc\r\0""#, r#""a\tb\nc\r\0""#, "a\tb\nc\r\0"),
+        (r#""say \"hi\" \\ \'""#, r#""say \"hi\" \\ \'""#, "say \"hi\" \\ '"),
+        (r#""\u{e9}\u{1F600}""#, r#""\u{e9}\u{1F600}""#, "é😀"),
+        ("\"two\nlines\"", "\"two\nlines\"", "two\nlines"),
+        ("\"join \\\n    lines\"", "\"join \\\n    lines\"", "join lines"),
+    ];
+    for (input, lexeme, value) in tests {
+        assert_eq!(scan(input), Some((lexeme.to_string(), Ok(value.to_string()))));
+    }
+    let mut s = StrScanner::new("'it\\'s'");
+    assert_eq!(s.scan_string_literal('\''), Some(("'it\\'s'", Ok("it's".to_string()))));
+}
+
+#[test]
+fn raw() {
+    let tests = [
+        (r#"r"C:\path""#, r"C:\path"),
+        (r##"r#"say "hi""#"##, r#"say "hi""#),
+        (r###"r##"a "# b"##"###, r##"a "# b"##),
+    ];
+    for (input, value) in tests {
+        assert_eq!(scan(input), Some((input.to_string(), Ok(value.to_string()))));
+    }
+    // not strings
+    assert_eq!(scan("r#x"), None);
+    assert_eq!(scan("raw"), None);
+    assert_eq!(scan("x\"\""), None);
+}
+
+#[test]
+fn errors() {
+    let tests = [
+        ("\"open", "unterminated string"),
+        ("\"open\\", "unterminated string"),
+        ("r#\"open\"", "unterminated string"),
+        (r#""bad \q" x"#, "invalid escape '\\q'"),
+        (r#""\u{110000}""#, "invalid unicode escape '\\u110000'"),
+        (r#""\u41""#, "invalid unicode escape '\\u'"),
+    ];
+    for (input, err) in tests {
+        let (lexeme, value) = scan(input).unwrap();
+        assert_eq!(value, Err(err.to_string()), "{}", input);
+        assert_eq!(lexeme, input.trim_end_matches(" x"));
+    }
+    // bad strings are consumed up to the closing quote
+    let mut s = Scanner::text(r#""\x" rest"#.chars());
+    assert!(s.scan_string_literal('"').unwrap().1.is_err());
+    assert_eq!(s.scan_whitespace(), Some(" ".to_string()));
+}
+
+#[test]
+fn lisp_strings() {
+    let input = "(print \"a\\tb\" r\"\\d\" \"bad\\q\" x)";
+    let tokens: Vec<_> = LispTokenizer::new(input.chars()).collect();
+    assert_eq!(tokens, vec![
+        LispToken::OParen,
+        LispToken::Symbol("print".to_string()),
+        LispToken::String("a\tb".to_string()),
+        LispToken::String("\\d".to_string()),
+        LispToken::Error("invalid escape '\\q'".to_string()),
+        LispToken::Symbol("x".to_string()),
+        LispToken::CParen,
+    ]);
+    let str_tokens: Vec<_> = StrLispTokenizer::new(input).map(|t| t.into_owned()).collect();
+    assert_eq!(str_tokens, tokens);
+    // the source of a string is at its span
+    let raw: Vec<_> = StrLispTokenizer::new(input).with_spans()
+        .filter(|t| matches!(t.value, LispToken::String(_)))
+        .map(|t| &input[t.span.start.offset..t.span.end.offset])
+        .collect();
+    assert_eq!(raw, vec!["\"a\\tb\"", "r\"\\d\""]);
+}
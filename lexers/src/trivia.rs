@@ -0,0 +1,52 @@
+#![deny(warnings)]
+
+use crate::scanner::Scanner;
+use crate::span::Spanned;
+
+/// A token along with its source text and the trivia (whitespace and
+/// comments) before it. Joining `leading` and `text` of every item gives
+/// back the input exactly, the last item has no token and holds whatever
+/// trails the last token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lossless<T> {
+    pub leading: String,
+    pub text: String,
+    pub token: Option<Spanned<T>>,
+}
+
+/// Adapter returned by each tokenizer's `lossless()`, it emits `Lossless`
+/// tokens instead of bare ones.
+pub struct WithTrivia<T> {
+    pub(crate) tokenizer: T,
+    // the trailing trivia was emitted
+    pub(crate) done: bool,
+}
+
+impl<T> WithTrivia<T> {
+    pub(crate) fn new(tokenizer: T) -> Self {
+        WithTrivia { tokenizer, done: false }
+    }
+}
+
+/// Wrap the next token of a tokenizer with the text around it, to build
+/// `lossless()` modes of tokenizers in other crates. `skip` consumes the
+/// trivia ahead and `next` scans a token, the tokenizer's scanner (`src`)
+/// must be recording, see `Scanner::record`.
+pub fn next_lossless<C, I, T>(tokenizer: &mut C, done: &mut bool,
+                              src: fn(&mut C) -> &mut Scanner<I>,
+                              skip: impl FnOnce(&mut C),
+                              next: impl FnOnce(&mut C) -> Option<Spanned<T>>)
+        -> Option<Lossless<T>>
+where
+    I: Iterator<Item = char>,
+{
+    if *done {
+        return None;
+    }
+    skip(tokenizer);
+    let leading = src(tokenizer).take_recorded().into_iter().collect();
+    let token = next(tokenizer);
+    *done = token.is_none();
+    let text = src(tokenizer).take_recorded().into_iter().collect();
+    Some(Lossless { leading, text, token })
+}
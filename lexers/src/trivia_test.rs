@@ -0,0 +1,78 @@
+#![deny(warnings)]
+
+use crate::trivia::Lossless;
+use crate::{LispToken, LispTokenizer, MathToken, MathTokenizer, Scanner, WordTokenizer};
+
+// Join the trivia and text of every token
+fn concat<T>(tokens: impl Iterator<Item = Lossless<T>>) -> String {
+    tokens.map(|t| t.leading + &t.text).collect()
+}
+
+#[test]
+fn lisp_round_trip() {
+    let inputs = [
+        "(+ 3 4 5)",
+        "  ; leading comment\n(define (f x)\t#| block #| nested |# |#\n  '(x \"a\\tb\\u{e9}\" ,@y))  ",
+        "(a #;(skip 'me) b #; c)\r\n; trailing",
+        "(\"unterminated",
+        "",
+        " \n ",
+    ];
+    for input in inputs {
+        assert_eq!(concat(LispTokenizer::new(input.chars()).lossless()), input);
+    }
+}
+
+#[test]
+fn math_round_trip() {
+    let inputs = [
+        "3+4*2/-(1-5)^2^3",
+        "  30 km / (10 s)  # speed\n// more\n * max(2, x) $ ",
+        "x := .5e3 //",
+    ];
+    for input in inputs {
+        assert_eq!(concat(MathTokenizer::new(input.chars()).lossless()), input);
+    }
+    let input = "Friday,  the Twenty-First\tat 3pm\n";
+    assert_eq!(concat(WordTokenizer::new(input.chars()).lossless()), input);
+}
+
+#[test]
+fn trivia_attachment() {
+    use LispToken::*;
+    let tokens: Vec<_> = LispTokenizer::new("(a ; note\n #;b \"c\\n\") ".chars())
+        .lossless()
+        .map(|t| (t.leading, t.text, t.token.map(|t| t.value)))
+        .collect();
+    let t = |leading: &str, text: &str, token| (leading.to_string(), text.to_string(), token);
+    assert_eq!(tokens, vec![
+        t("", "(", Some(OParen)),
+        t("", "a", Some(Symbol("a".to_string()))),
+        t(" ; note\n #;b ", "\"c\\n\"", Some(String("c\n".to_string()))),
+        t("", ")", Some(CParen)),
+        t(" ", "", None),
+    ]);
+    // spans and decoded values are those of the plain tokenizer
+    let spanned: Vec<_> = MathTokenizer::new("2 km # far".chars()).lossless()
+        .filter_map(|t| t.token)
+        .collect();
+    assert_eq!(spanned.len(), 1);
+    assert_eq!(spanned[0].value, MathToken::Quantity(2.0, "k".to_string(), "m".to_string()));
+    assert_eq!(spanned[0].span.to_string(), "1:1-1:5");
+}
+
+#[test]
+fn scanner_record() {
+    let mut s = Scanner::new("abc".chars());
+    assert!(s.take_recorded().is_empty());
+    let mut s = Scanner::new("abcd".chars()).record(true);
+    s.next();
+    s.extract();
+    s.next();
+    s.peek();
+    assert_eq!(s.take_recorded(), vec!['a', 'b']);
+    s.next();
+    s.next();
+    assert_eq!(s.take_recorded(), vec!['c', 'd']);
+    assert!(s.take_recorded().is_empty());
+}
@@ -0,0 +1,265 @@
+#![deny(warnings)]
+
+use crate::helpers::Cursor;
+use crate::scanner::Scanner;
+use crate::str_scanner::StrScanner;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+// Maps keys to words, used for longest match of prefixes and units
+#[derive(Clone, Debug, Default)]
+struct Trie {
+    next: BTreeMap<char, Trie>,
+    word: Option<String>,
+}
+
+impl Trie {
+    fn insert(&mut self, key: &str, word: &str) {
+        let node = key.chars().fold(self, |node, c| node.next.entry(c).or_default());
+        node.word = Some(word.to_string());
+    }
+
+    // Length in chars and word of every key ahead, the cursor stays put
+    fn matches<S: Cursor>(&self, s: &mut S) -> Vec<(usize, &str)> {
+        let backtrack = s.mark();
+        let mut node = self;
+        let mut found: Vec<_> = node.word.iter().map(|w| (0, w.as_str())).collect();
+        let mut len = 0;
+        while let Some(next) = s.peek().and_then(|c| node.next.get(&c)) {
+            s.next_char();
+            len += 1;
+            node = next;
+            found.extend(node.word.iter().map(|w| (len, w.as_str())));
+        }
+        s.reset(backtrack);
+        found
+    }
+}
+
+/// One factor of a (compound) unit, `km/h^2` is k·m and h^-2.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnitFactor {
+    pub prefix: String,
+    // unit symbol, aliases are resolved
+    pub unit: String,
+    pub power: i32,
+}
+
+/// Prefixes, units and aliases that `scan_unit` recognizes. Any prefix goes
+/// with any unit, the longest match wins and then the longest unit, so
+/// `ft` is feet rather than femto-tonnes. The default registry has the SI
+/// units and prefixes.
+///
+/// ```
+/// use lexers::{Scanner, UnitRegistry};
+///
+/// let units = UnitRegistry::si().prefix("Ki").unit("B").unit("mph").alias("ohm", "Ω");
+/// let mut s = Scanner::new("KiB".chars());
+/// assert_eq!(s.scan_unit_with(&units), Some(("Ki".to_string(), "B".to_string())));
+/// assert_eq!(units.factors("kohm").unwrap()[0].unit, "Ω");
+/// ```
+#[derive(Clone, Debug)]
+pub struct UnitRegistry {
+    prefixes: Trie,
+    units: Trie,
+}
+
+impl Default for UnitRegistry {
+    fn default() -> Self {
+        Self::si()
+    }
+}
+
+impl UnitRegistry {
+    // A registry without any units
+    pub fn new() -> Self {
+        let mut prefixes = Trie::default();
+        prefixes.insert("", ""); // no multiplier prefix, raw unit
+        UnitRegistry { prefixes, units: Trie::default() }
+    }
+
+    // SI prefixes and units
+    pub fn si() -> Self {
+        static PFX: &[&str] = &[
+            "da", "h", "k", "M", "G", "T", "P", "E", "Z", "Y",
+            "y", "z", "a", "f", "p", "n", "µ", "m", "c", "d",
+        ];
+        static BARE_UNITS: &[&str] = &[
+            "kat", "mol", "rad",
+            "Bq", "cd", "Gy", "Hz", "lm", "lx", "Pa", "sr", "Sv", "Wb",
+            "A", "°C", "C", "F", "g", "H", "J", "K", "m", "N", "s", "S",
+            "T", "V", "W", "Ω",
+        ];
+        let units = PFX.iter().fold(Self::new(), |units, p| units.prefix(p));
+        BARE_UNITS.iter().fold(units, |units, u| units.unit(u))
+    }
+
+    // Shared SI registry, see si()
+    pub(crate) fn si_ref() -> &'static UnitRegistry {
+        static SI: OnceLock<UnitRegistry> = OnceLock::new();
+        SI.get_or_init(Self::si)
+    }
+
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefixes.insert(prefix, prefix);
+        self
+    }
+
+    pub fn unit(mut self, symbol: &str) -> Self {
+        self.units.insert(symbol, symbol);
+        self
+    }
+
+    // Another name for a unit, eg: ("ohm", "Ω")
+    pub fn alias(mut self, alias: &str, symbol: &str) -> Self {
+        self.units.insert(alias, symbol);
+        self
+    }
+
+    // Split a unit like "km/h" into its factors, None if it's not a unit
+    pub fn factors(&self, unit: &str) -> Option<Vec<UnitFactor>> {
+        let mut s = StrScanner::new(unit);
+        let (_, factors) = scan_compound(&mut s, self)?;
+        match s.peek() {
+            None => Some(factors),
+            Some(_) => None,
+        }
+    }
+
+    // Could a prefix or unit start with 'c'
+    fn starts(&self, c: char) -> bool {
+        self.prefixes.next.contains_key(&c) || self.units.next.contains_key(&c)
+    }
+}
+
+fn advance<S: Cursor>(s: &mut S, chars: usize) {
+    (0..chars).for_each(|_| {
+        s.next_char();
+    });
+}
+
+// Powers like ^2, ^-1, ² or ³
+fn scan_power<S: Cursor>(s: &mut S) -> Option<i32> {
+    let backtrack = s.mark();
+    if s.accept(&'^').is_some() {
+        let sign = s.accept(&'-').map_or(1, |_| -1);
+        let mut digits = String::new();
+        while let Some(d) = s.accept_if(|c| c.is_ascii_digit()) {
+            digits.push(d);
+        }
+        if let Ok(power) = i32::from_str(&digits) {
+            return Some(sign * power);
+        }
+        s.reset(backtrack);
+        return None;
+    }
+    match s.accept_any(&['²', '³']) {
+        Some('²') => Some(2),
+        Some('³') => Some(3),
+        _ => None,
+    }
+}
+
+// A prefixed unit with an optional power, result has the lengths of the
+// prefix and unit in chars and if a power was given
+fn scan_factor<S: Cursor>(s: &mut S, units: &UnitRegistry)
+        -> Option<(usize, usize, bool, UnitFactor)> {
+    let start = s.mark();
+    let mut best: Option<(usize, usize, &str, &str)> = None;
+    for (plen, prefix) in units.prefixes.matches(s) {
+        advance(s, plen);
+        for (ulen, unit) in units.units.matches(s) {
+            if ulen > 0 && best.is_none_or(|(bplen, bulen, _, _)|
+                    (plen + ulen, ulen) > (bplen + bulen, bulen)) {
+                best = Some((plen, ulen, prefix, unit));
+            }
+        }
+        s.reset(start);
+    }
+    let (plen, ulen, prefix, unit) = best?;
+    advance(s, plen + ulen);
+    let power = scan_power(s);
+    let factor = UnitFactor {
+        prefix: prefix.to_string(),
+        unit: unit.to_string(),
+        power: power.unwrap_or(1),
+    };
+    Some((plen, ulen, power.is_some(), factor))
+}
+
+// Prefix and unit lengths in chars of plain units like 'km', None for
+// compound ones, along with the factors
+type Compound = (Option<(usize, usize)>, Vec<UnitFactor>);
+
+// Units joined by '/' or '·' like m/s^2 or kg·m, a '/' only divides by
+// the unit right after it
+fn scan_compound<S: Cursor>(s: &mut S, units: &UnitRegistry) -> Option<Compound> {
+    let (plen, ulen, powered, factor) = scan_factor(s, units)?;
+    let mut factors = vec![factor];
+    loop {
+        let backtrack = s.mark();
+        let sign = match s.accept_any(&['/', '·', '⋅']) {
+            Some('/') => -1,
+            Some(_) => 1,
+            None => break,
+        };
+        match scan_factor(s, units) {
+            Some((_, _, _, mut factor)) => {
+                factor.power *= sign;
+                factors.push(factor);
+            }
+            None => {
+                s.reset(backtrack);
+                break;
+            }
+        }
+    }
+    let plain = match factors.len() == 1 && !powered {
+        true => Some((plen, ulen)),
+        false => None,
+    };
+    Some((plain, factors))
+}
+
+// scan an optional prefix (unit multiplier) and unit. Compound units like
+// m/s^2 come whole in the unit with an empty prefix, see factors()
+pub(crate) fn scan_unit<S: Cursor>(s: &mut S, units: &UnitRegistry) -> Option<(S::Lexeme, S::Lexeme)> {
+    // bail out early unless a prefix or unit starts here
+    if !s.peek().is_some_and(|c| units.starts(c)) {
+        return None;
+    }
+    let start = s.mark();
+    let (plain, _) = scan_compound(s, units)?;
+    let end = s.location().offset;
+    // re-scan to split the lexeme, ignoring what came before
+    s.reset(start);
+    s.extract_lexeme();
+    match plain {
+        Some((plen, ulen)) => {
+            advance(s, plen);
+            let prefix = s.extract_lexeme();
+            advance(s, ulen);
+            Some((prefix, s.extract_lexeme()))
+        }
+        None => {
+            let prefix = s.extract_lexeme();
+            while s.location().offset < end && s.next_char().is_some() {}
+            Some((prefix, s.extract_lexeme()))
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> Scanner<I> {
+    // scan a unit from 'units', see UnitRegistry
+    pub fn scan_unit_with(&mut self, units: &UnitRegistry) -> Option<(String, String)> {
+        scan_unit(self, units)
+    }
+}
+
+impl<'a> StrScanner<'a> {
+    // scan a unit from 'units', see UnitRegistry
+    pub fn scan_unit_with(&mut self, units: &UnitRegistry) -> Option<(&'a str, &'a str)> {
+        scan_unit(self, units)
+    }
+}
@@ -0,0 +1,77 @@
+#![deny(warnings)]
+
+use crate::scanner::Scanner;
+use crate::str_scanner::StrScanner;
+use crate::units::{UnitFactor, UnitRegistry};
+use crate::{MathToken, MathTokenizer, StrMathTokenizer};
+
+fn units() -> UnitRegistry {
+    UnitRegistry::si()
+        .prefix("Ki").prefix("Mi").prefix("Gi")
+        .unit("B").unit("h").unit("ft").unit("mph").unit("eV").unit("%").unit("t")
+        .alias("ohm", "Ω").alias("sec", "s")
+}
+
+fn scan(input: &str, units: &UnitRegistry) -> Option<(String, String)> {
+    let unit = StrScanner::new(input).scan_unit_with(units);
+    let expected = Scanner::text(input.chars()).scan_unit_with(units);
+    assert_eq!(unit.map(|(p, u)| (p.to_string(), u.to_string())), expected, "{}", input);
+    expected
+}
+
+#[test]
+fn longest_match() {
+    let units = units();
+    let tests = [
+        ("KiB", "Ki", "B"), ("GiB/s", "", "GiB/s"), ("km/h", "", "km/h"), ("mph", "", "mph"),
+        // feet, not femto-tonnes
+        ("ft", "", "ft"), ("MeV", "M", "eV"), ("50%", "", ""), ("%", "", "%"),
+        ("kohm", "k", "ohm"), ("mm", "m", "m"), ("mmol", "m", "mol"), ("sec", "", "sec"),
+        ("m/s^2 x", "", "m/s^2"), ("kg·m²", "", "kg·m²"), ("m/x", "", "m"), ("m^x", "", "m"),
+    ];
+    for (input, prefix, unit) in tests {
+        let expected = match unit {
+            "" => None,
+            _ => Some((prefix.to_string(), unit.to_string())),
+        };
+        assert_eq!(scan(input, &units), expected, "{}", input);
+    }
+    // the default registry is SI only, K is kelvin
+    assert_eq!(scan("KiB", &UnitRegistry::default()), Some(("".to_string(), "K".to_string())));
+    assert_eq!(scan("ft", &UnitRegistry::default()), None);
+    assert_eq!(scan("x", &UnitRegistry::new().unit("y")), None);
+}
+
+#[test]
+fn factors() {
+    let units = units();
+    let factor = |prefix: &str, unit: &str, power| UnitFactor {
+        prefix: prefix.to_string(),
+        unit: unit.to_string(),
+        power,
+    };
+    assert_eq!(units.factors("km/h"), Some(vec![factor("k", "m", 1), factor("", "h", -1)]));
+    assert_eq!(units.factors("kg·m/s^2"),
+               Some(vec![factor("k", "g", 1), factor("", "m", 1), factor("", "s", -2)]));
+    assert_eq!(units.factors("Mohm³"), Some(vec![factor("M", "Ω", 3)]));
+    assert_eq!(units.factors("m^-1"), Some(vec![factor("", "m", -1)]));
+    assert_eq!(units.factors("m/"), None);
+    assert_eq!(units.factors("furlong"), None);
+}
+
+#[test]
+fn math_units() {
+    let input = "3 km/h + 2 KiB * 10 mph";
+    let tokens: Vec<_> = MathTokenizer::new(input.chars()).units(units()).collect();
+    assert_eq!(tokens, vec![
+        MathToken::Quantity(3.0, "".to_string(), "km/h".to_string()),
+        MathToken::BOp("+".to_string()),
+        MathToken::Quantity(2.0, "Ki".to_string(), "B".to_string()),
+        MathToken::BOp("*".to_string()),
+        MathToken::Quantity(10.0, "".to_string(), "mph".to_string()),
+    ]);
+    let str_tokens: Vec<_> = StrMathTokenizer::new(input).units(units())
+        .map(|t| t.into_owned())
+        .collect();
+    assert_eq!(str_tokens, tokens);
+}
@@ -0,0 +1,136 @@
+#![deny(warnings)]
+
+use crate::helpers::Cursor;
+use crate::scanner::Scanner;
+use crate::span::{Span, Spanned, WithSpans};
+use crate::trivia::{self, Lossless, WithTrivia};
+
+// Join letters into a single word, eg: twenty-first, o'clock
+static JOINERS: &[char] = &['-', '\'', '’'];
+
+// A tokenizer for natural language like "friday, 10:30am". Words are
+// lowercased, numbers are split from letters (3pm is 3 and pm) and other
+// chars are tokens of their own. Spans point to the original text.
+pub struct WordTokenizer<I: Iterator<Item = char>> {
+    src: Scanner<I>,
+    lowercase: bool,
+}
+
+impl<I: Iterator<Item = char>> WordTokenizer<I> {
+    pub fn new(source: I) -> Self {
+        WordTokenizer {
+            src: Scanner::text(source),
+            lowercase: true,
+        }
+    }
+
+    pub fn scanner(source: I) -> Scanner<Self> {
+        Scanner::new(Self::new(source))
+    }
+
+    // Keep words as written instead of lowercasing them
+    pub fn lowercase(self, lowercase: bool) -> Self {
+        WordTokenizer { lowercase, ..self }
+    }
+
+    // Emit tokens along with their position in the source
+    pub fn with_spans(self) -> WithSpans<Self> {
+        WithSpans(self)
+    }
+
+    // Emit tokens with the source text and whitespace around them
+    pub fn lossless(self) -> WithTrivia<Self> {
+        WithTrivia::new(WordTokenizer { src: self.src.record(true), ..self })
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for WordTokenizer<I> {
+    type Item = String;
+    fn next(&mut self) -> Option<Self::Item> {
+        next_spanned(&mut self.src, self.lowercase).map(|t| t.value)
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for WithSpans<WordTokenizer<I>> {
+    type Item = Spanned<String>;
+    fn next(&mut self) -> Option<Self::Item> {
+        next_spanned(&mut self.0.src, self.0.lowercase)
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for WithTrivia<WordTokenizer<I>> {
+    type Item = Lossless<String>;
+    fn next(&mut self) -> Option<Self::Item> {
+        trivia::next_lossless(&mut self.tokenizer, &mut self.done, |t| &mut t.src,
+                              |t| { Cursor::skip_while(&mut t.src, char::is_whitespace); },
+                              |t| next_spanned(&mut t.src, t.lowercase))
+    }
+}
+
+fn next_spanned<S: Cursor>(src: &mut S, lowercase: bool) -> Option<Spanned<String>> {
+    src.skip_while(char::is_whitespace);
+    src.extract_lexeme();
+    let start = src.location();
+    let first = src.next_char()?;
+    if first.is_alphabetic() {
+        loop {
+            src.skip_while(char::is_alphabetic);
+            let backtrack = src.mark();
+            if src.accept_any(JOINERS).is_none() || src.accept_if(char::is_alphabetic).is_none() {
+                src.reset(backtrack);
+                break;
+            }
+        }
+    } else if first.is_ascii_digit() {
+        src.skip_while(|c| c.is_ascii_digit());
+        // decimals like 1.5, but not the dot in "at 10."
+        let backtrack = src.mark();
+        if src.accept(&'.').is_none() || !src.skip_while(|c| c.is_ascii_digit()) {
+            src.reset(backtrack);
+        }
+    }
+    let word = src.extract_lexeme();
+    let word = match lowercase {
+        true => word.as_ref().to_lowercase(),
+        false => word.as_ref().to_string(),
+    };
+    Some(Spanned::new(word, Span { start, end: src.location() }))
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::WordTokenizer;
+
+    fn words(input: &str) -> Vec<String> {
+        WordTokenizer::new(input.chars()).collect()
+    }
+
+    #[test]
+    fn word_tokenizer() {
+        assert_eq!(words("Friday, March 3rd at 10:30am!"),
+                   vec!["friday", ",", "march", "3", "rd", "at", "10", ":", "30", "am", "!"]);
+        assert_eq!(words("the twenty-first of June, o'clock 2h30m"),
+                   vec!["the", "twenty-first", "of", "june", ",", "o'clock", "2", "h", "30", "m"]);
+        assert_eq!(words("in 1.5 hours. -3 10-11 a- -b"),
+                   vec!["in", "1.5", "hours", ".", "-", "3", "10", "-", "11", "a", "-", "-", "b"]);
+        assert_eq!(words("Mañana’s 5th"), vec!["mañana’s", "5", "th"]);
+        assert_eq!(words("  "), Vec::<String>::new());
+        let kept: Vec<_> = WordTokenizer::new("Next Monday".chars()).lowercase(false).collect();
+        assert_eq!(kept, vec!["Next", "Monday"]);
+    }
+
+    #[test]
+    fn word_spans() {
+        let spans: Vec<_> = WordTokenizer::new("Café,\n 3pm".chars()).with_spans()
+            .map(|t| (t.value, t.span.to_string()))
+            .collect();
+        assert_eq!(spans, vec![
+            ("café".to_string(), "1:1-1:5".to_string()),
+            (",".to_string(), "1:5-1:6".to_string()),
+            ("3".to_string(), "2:2-2:3".to_string()),
+            ("pm".to_string(), "2:3-2:5".to_string()),
+        ]);
+    }
+}
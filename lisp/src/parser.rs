@@ -8,6 +8,7 @@ pub enum ParseError {
     UnexpectedCParen,
     UnexpectedEOF,
     NotImplemented,
+    BadToken(String),
 }
 
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
@@ -28,7 +29,7 @@ impl string::ToString for LispExpr {
     fn to_string(&self) -> String {
         match self {
             &LispExpr::Symbol(ref s) => s.clone(),
-            &LispExpr::String(ref s) => s.clone(),
+            &LispExpr::String(ref s) => format!("{:?}", s),
             &LispExpr::Number(n) => format!("{}", n),
             &LispExpr::List(ref v) => {
                 let base = match v.first() {
@@ -70,6 +71,7 @@ impl Parser {
             Some(LispToken::String(n))  => Ok(LispExpr::String(n)),
             Some(LispToken::Number(n))  => Ok(LispExpr::Number(n)),
             Some(LispToken::Symbol(s))  => Ok(LispExpr::Symbol(s)),
+            Some(LispToken::Error(err)) => Err(ParseError::BadToken(err)),
             Some(LispToken::OParen)     => {
                 let mut list = Vec::new();
                 while lex.peek() != Some(LispToken::CParen) { // even when != None
@@ -22,3 +22,15 @@ fn test_lisp1() {
     ]);
     assert_eq!(p.unwrap(), r);
 }
+
+#[test]
+fn test_strings() {
+    let p = Parser::parse_str("(strlen \"a\\tb\")").unwrap();
+    let r = LispExpr::List(vec![
+        LispExpr::Symbol(format!("strlen")),
+        LispExpr::String(format!("a\tb")),
+    ]);
+    assert_eq!(p, r);
+    assert_eq!(p.to_string(), "(strlen \"a\\tb\")");
+    assert!(Parser::parse_str("\"open").is_err());
+}
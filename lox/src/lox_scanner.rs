@@ -1,4 +1,6 @@
 use lexers::Scanner;
+#[cfg(test)]
+use lexers::{Lossless, Span, Spanned};
 
 #[derive(Clone,Debug,PartialEq)]
 pub enum TT {
@@ -36,15 +38,22 @@ impl<I: Iterator<Item=char>> LoxScanner<I> {
                 errors: false})
     }
 
+    // Keep whitespace and comments around tokens, joining the text of all
+    // tokens gives back the source, eg: for formatters
+    #[cfg(test)]
+    pub fn lossless(source: I) -> LoxTrivia<I> {
+        let scanner = LoxScanner{
+            src: Scanner::text(source).record(true),
+            line: 1,
+            errors: false};
+        LoxTrivia{scanner, done: false}
+    }
+
     fn tokenize(&mut self, literal: TT) -> Option<Token> {
         let lexeme = match literal {
             TT::EOF => String::new(),
             _ => self.src.extract_string()
         };
-        let literal = match literal {
-            TT::Str(_) => TT::Str(lexeme.trim_matches('"').to_string()),
-            other => other
-        };
         Some(Token{line: self.line, token: literal, lexeme})
     }
 
@@ -53,17 +62,19 @@ impl<I: Iterator<Item=char>> LoxScanner<I> {
         self.errors = true;
     }
 
-    fn scan_restof_string(&mut self, q: char) -> bool {
-        let backtrack = self.src.buffer_pos();
-        let orig_line = self.line;
-        while let Some(n) = self.src.next() {
-            if n == '\n' { self.line += 1; }
-            if n == '\\' { self.src.next(); continue; }
-            if n == q { return true; }
+    // strings like "a\tb" or r"raw", they may span lines. None if there's
+    // no string ahead, Some(None) for bad strings which are reported
+    fn string(&mut self) -> Option<Option<Token>> {
+        let line = self.line;
+        let (lexeme, value) = self.src.scan_string_literal('"')?;
+        self.line += lexeme.matches('\n').count();
+        match value {
+            Ok(value) => Some(Some(Token{line, token: TT::Str(value), lexeme})),
+            Err(err) => {
+                self.error(format!("{} at line {}", err, line));
+                Some(None)
+            }
         }
-        self.src.set_buffer_pos(backtrack);
-        self.line = orig_line;
-        false
     }
 
     fn id_or_keyword(&mut self, keyword: String) -> Option<Token> {
@@ -94,6 +105,39 @@ impl<I: Iterator<Item=char>> LoxScanner<I> {
         }
     }
 
+    // skip whitespace and comments
+    #[cfg(test)]
+    fn skip_trivia(&mut self) {
+        loop {
+            if self.src.accept(&'\n').is_some() {
+                self.line += 1;
+            } else if self.src.accept_any(&[' ', '\t', '\r']).is_none() {
+                if !self.src.accept_all("//".chars()) {
+                    break;
+                }
+                self.src.until_any(&['\n']);
+            }
+        }
+        self.src.extract();
+    }
+
+    // next token and its span, bad chars and strings are reported and
+    // joined to the next token
+    #[cfg(test)]
+    fn next_spanned(&mut self) -> Option<Spanned<Token>> {
+        let start = self.src.location();
+        loop {
+            match self.scan_token() {
+                Some(Token{token: TT::EOF, ..}) => return None,
+                Some(token) => {
+                    let span = Span{start, end: self.src.location()};
+                    return Some(Spanned::new(token, span));
+                },
+                None => (),
+            }
+        }
+    }
+
     fn scan_token(&mut self) -> Option<Token> {
         let token = match self.src.next() {
             Some('(') => self.tokenize(TT::OPAREN),
@@ -136,9 +180,9 @@ impl<I: Iterator<Item=char>> LoxScanner<I> {
             },
             Some(' ') | Some('\t') | Some('\r') => None,
             Some('\n') => { self.line += 1; None }, // track current line
-            Some('"') => match self.scan_restof_string('"') {
-                true => self.tokenize(TT::Str(String::new())),
-                false => { self.error("unterminated string"); None }
+            Some('"') => {
+                self.src.prev(); // hacky but works
+                self.string().flatten()
             },
             Some(d) if d.is_digit(10) => {
                 self.src.prev(); // hacky but works
@@ -149,6 +193,10 @@ impl<I: Iterator<Item=char>> LoxScanner<I> {
             },
             Some(a) if a.is_alphabetic() => {
                 self.src.prev(); // hacky but works
+                // raw strings like r"\d+"
+                if let Some(raw) = self.string() {
+                    return raw;
+                }
                 let id = self.src.scan_identifier().unwrap();
                 self.id_or_keyword(id)
             },
@@ -177,3 +225,21 @@ impl<I: Iterator<Item=char>> Iterator for LoxScanner<I> {
         }
     }
 }
+
+// Tokens of a LoxScanner along with the whitespace and comments around
+// them, see LoxScanner::lossless
+#[cfg(test)]
+pub struct LoxTrivia<I: Iterator<Item=char>> {
+    scanner: LoxScanner<I>,
+    done: bool,
+}
+
+#[cfg(test)]
+impl<I: Iterator<Item=char>> Iterator for LoxTrivia<I> {
+    type Item = Lossless<Token>;
+    fn next(&mut self) -> Option<Self::Item> {
+        lexers::next_lossless(&mut self.scanner, &mut self.done, |s| &mut s.src,
+                              |s| s.skip_trivia(),
+                              |s| s.next_spanned())
+    }
+}
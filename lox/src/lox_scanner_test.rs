@@ -0,0 +1,41 @@
+use crate::lox_scanner::{LoxScanner, TT};
+
+// Join the trivia and text of every token
+fn concat(source: &str) -> String {
+    LoxScanner::lossless(source.chars()).map(|t| t.leading + &t.text).collect()
+}
+
+#[test]
+fn lossless_round_trip() {
+    let inputs = [
+        "print 1 + 2;",
+        "  // leading comment\nvar s = \"a\\tb\\u{e9}\"; // trailing\r\n\tprint s;\n",
+        "fun f(x) {\n  return r\"\\d+\" + \"multi\nline\";\n}\n// no newline at the end",
+        "print \"unterminated",
+        "var x = 1 # 2;",
+        "",
+        " \n ",
+        include_str!("tests/break-loops.lox"),
+        include_str!("tests/nested-scopes.lox"),
+    ];
+    for input in inputs {
+        assert_eq!(concat(input), input);
+    }
+}
+
+#[test]
+fn lossless_tokens() {
+    let source = "var s = \"x\"; // done\nprint s;";
+    let tokens: Vec<_> = LoxScanner::lossless(source.chars()).collect();
+    let texts: Vec<_> = tokens.iter().map(|t| t.text.as_str()).collect();
+    assert_eq!(texts, vec!["var", "s", "=", "\"x\"", ";", "print", "s", ";", ""]);
+    assert_eq!(tokens[5].leading, " // done\n");
+    let print = tokens[5].token.as_ref().unwrap();
+    assert_eq!((print.value.token.clone(), print.value.line), (TT::PRINT, 2));
+    assert_eq!((print.span.start.line, print.span.start.column), (2, 1));
+    assert_eq!(tokens[3].token.as_ref().unwrap().value.token, TT::Str("x".to_string()));
+    // same tokens as the plain scanner
+    let plain: Vec<_> = LoxScanner::scanner(source.chars()).map(|t| t.token).collect();
+    let lossless: Vec<_> = tokens.into_iter().filter_map(|t| t.token.map(|t| t.value.token)).collect();
+    assert_eq!(lossless, plain);
+}
@@ -10,6 +10,8 @@ mod lox_interpreter;
 mod lox_environment;
 mod lox_native;
 mod lox_resolver;
+#[cfg(test)]
+mod lox_scanner_test;
 
 use crate::lox_scanner::LoxScanner;
 use crate::lox_parser::LoxParser;
@@ -1,10 +1,10 @@
 mod repl {
-    use lexers::{MathToken, MathTokenizer};
+    use lexers::{MathToken, MathTokenizer, Scanner};
     use shunting::{MathContext, ShuntingParser, MathOp};
 
     pub fn evalexpr(input: &str) {
         match ShuntingParser::parse_str(input) {
-            Err(e) => println!("Parse error: {:?}", e),
+            Err(e) => println!("Parse error: {}", e),
             Ok(expr) => match MathContext::new().eval(&expr) {
                 Err(e) => println!("Eval error: {:?}", e),
                 Ok(r) => println!("{} -> {}", expr, r),
@@ -12,13 +12,47 @@ mod repl {
         };
     }
 
+    // Function definition heads like 'f(x, y) :='
+    fn parse_fn_head(ml: &mut impl Iterator<Item = MathToken>) -> Option<(String, Vec<String>)> {
+        let fname = match (ml.next(), ml.next()) {
+            (Some(MathToken::Function(fname, _)), Some(MathToken::OParen)) => fname,
+            _ => return None,
+        };
+        let mut params = Vec::new();
+        loop {
+            match ml.next()? {
+                MathToken::Variable(param) => params.push(param),
+                MathToken::CParen if params.is_empty() => break,
+                _ => return None,
+            }
+            match ml.next()? {
+                MathToken::Comma => continue,
+                MathToken::CParen => break,
+                _ => return None,
+            }
+        }
+        match ml.next()? {
+            MathToken::BOp(op) if op == ":=" => Some((fname, params)),
+            _ => None,
+        }
+    }
+
     pub fn parse_statement(cx: &MathContext, input: &str) {
-        let mut ml = MathTokenizer::scanner(input.chars());
+        let mut ml = Scanner::new(MathTokenizer::new(input.chars()).with_spans());
         let backtrack = ml.buffer_pos();
-        if let (Some(MathToken::Variable(var)), Some(op)) = (ml.next(), ml.next()) {
+        if let Some((fname, params)) = parse_fn_head(&mut ml.by_ref().map(|t| t.value)) {
+            match cx.parse(&mut ml) {
+                Err(e) => println!("Parse error: {}", e),
+                Ok(body) => cx.deffn(&fname, params, body),
+            }
+            return;
+        }
+        ml.set_buffer_pos(backtrack);
+        if let (Some(MathToken::Variable(var)), Some(op)) =
+                (ml.next().map(|t| t.value), ml.next().map(|t| t.value)) {
             if op == MathToken::BOp(":=".to_string()) {
-                match ShuntingParser::parse(&mut ml) {
-                    Err(e) => println!("Parse error: {:?}", e),
+                match cx.parse(&mut ml) {
+                    Err(e) => println!("Parse error: {}", e),
                     Ok(expr) => match cx.compile(&expr) {
                         Err(e) => println!("Compile error: {:?}", e),
                         Ok(code) => cx.setvar(&var, code),
@@ -29,8 +63,8 @@ mod repl {
         }
         // wasn't assignment... try evaluating expression
         ml.set_buffer_pos(backtrack);
-        match ShuntingParser::parse(&mut ml) {
-            Err(e) => println!("Parse error: {:?}", e),
+        match cx.parse(&mut ml) {
+            Err(e) => println!("Parse error: {}", e),
             Ok(expr) => match cx.compile(&expr) {
                 Err(e) => println!("Compile error: {:?}", e),
                 Ok(MathOp::Number(n)) => println!("{}", n),
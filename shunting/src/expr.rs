@@ -0,0 +1,107 @@
+use crate::parser::RPNExpr;
+use lexers::{MathToken, Span};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExprKind {
+    Number(f64),
+    Variable(String),
+    UOp(String, Box<Expr>),
+    BOp(String, Box<Expr>, Box<Expr>),
+    Function(String, Vec<Expr>),
+    // c ? a : b or if(c, a, b)
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+/// Expression tree built by `ShuntingParser`, each node spans its source
+/// including parens around it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Expr { kind, span }
+    }
+
+    // Lower the tree to postfix tokens
+    pub fn rpn(&self) -> RPNExpr {
+        fn lower(expr: &Expr, out: &mut Vec<MathToken>) {
+            match &expr.kind {
+                ExprKind::Number(n) => out.push(MathToken::Number(*n)),
+                ExprKind::Variable(v) => out.push(MathToken::Variable(v.clone())),
+                ExprKind::UOp(op, arg) => {
+                    lower(arg, out);
+                    out.push(MathToken::UOp(op.clone()));
+                }
+                ExprKind::BOp(op, lhs, rhs) => {
+                    lower(lhs, out);
+                    lower(rhs, out);
+                    out.push(MathToken::BOp(op.clone()));
+                }
+                ExprKind::Function(fname, args) => {
+                    args.iter().for_each(|arg| lower(arg, out));
+                    out.push(MathToken::Function(fname.clone(), args.len()));
+                }
+                ExprKind::Cond(cond, then, otherwise) => {
+                    [cond, then, otherwise].iter().for_each(|arg| lower(arg, out));
+                    out.push(MathToken::Function("if".to_string(), 3));
+                }
+            }
+        }
+        let mut out = Vec::new();
+        lower(self, &mut out);
+        RPNExpr(out)
+    }
+
+    // Operands of the node, empty for leaves
+    pub fn children(&self) -> Vec<&Expr> {
+        match &self.kind {
+            ExprKind::Number(_) | ExprKind::Variable(_) => Vec::new(),
+            ExprKind::UOp(_, arg) => vec![arg],
+            ExprKind::BOp(_, lhs, rhs) => vec![lhs, rhs],
+            ExprKind::Function(_, args) => args.iter().collect(),
+            ExprKind::Cond(cond, then, otherwise) => vec![cond, then, otherwise],
+        }
+    }
+}
+
+impl fmt::Display for ExprKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExprKind::Number(n) => write!(f, "{}", n),
+            ExprKind::Variable(v) => write!(f, "{}", v),
+            ExprKind::UOp(op, _) | ExprKind::BOp(op, _, _) => write!(f, "{}", op),
+            ExprKind::Function(fname, _) => write!(f, "{}()", fname),
+            ExprKind::Cond(_, _, _) => write!(f, "?:"),
+        }
+    }
+}
+
+// Prints the tree, one node per line
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn print_helper(root: &Expr, indent: &str, out: &mut String) {
+            let children = root.children();
+            match children.split_last() {
+                None => *out += &format!("\u{2500}{}\n", root.kind),
+                Some((last_node, rest)) => {
+                    // Print current node
+                    *out += &format!("\u{252c}{}\n", root.kind);
+                    // Print its children
+                    for mid_node in rest {
+                        *out += &format!("{}\u{251c}", indent);
+                        print_helper(mid_node, &format!("{}\u{2502}", indent), out);
+                    }
+                    *out += &format!("{}\u{2570}", indent);
+                    print_helper(last_node, &format!("{} ", indent), out);
+                }
+            }
+        }
+        let mut output = String::new();
+        print_helper(self, "", &mut output);
+        write!(f, "{}", output)
+    }
+}
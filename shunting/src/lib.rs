@@ -1,11 +1,16 @@
+mod expr;
+mod mathlib;
 mod parser;
 mod rpneval;
 mod rpnprint;
 
-pub use crate::parser::{RPNExpr, ShuntingParser};
-pub use crate::rpneval::{MathOp, MathContext};
+pub use crate::expr::{Expr, ExprKind};
+pub use crate::parser::{Assoc, Fixity, Operators, ParseError, RPNExpr, ShuntingParser};
+pub use crate::rpneval::{Arity, MathContext, MathOp, NativeFn, RandomVariable};
 
 #[cfg(test)]
 mod parser_test;
 #[cfg(test)]
 mod rpneval_test;
+#[cfg(test)]
+mod mathlib_test;
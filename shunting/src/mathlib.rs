@@ -0,0 +1,136 @@
+use crate::rpneval::{Arity, MathContext};
+use std::f64::consts;
+
+// Standard functions of every MathContext, backed by libm. Args outside a
+// function's domain are an error rather than NaN, eg: sqrt(-1).
+//
+//  rounding     abs sign floor ceil round trunc fract clamp(x, lo, hi)
+//  arithmetic   mod(x, y) gcd(..) lcm(..) min(..) max(..) sum(..) mean(..)
+//  powers       sqrt cbrt pow(x, y) hypot(x, y) exp exp2 expm1
+//  logarithms   ln log(x) log(x, base) log10 log2 log1p
+//  trigonometry sin cos tan asin acos atan atan2(y, x) deg rad
+//  hyperbolic   sinh cosh tanh asinh acosh atanh
+//  special      gamma lgamma erf erfc
+//  combinatorics nCr nMCr nPr nMPr
+//
+// log is the decimal logarithm, like log10, unless a base is given.
+
+type Func = fn(&[f64]) -> f64;
+type Domain = fn(&[f64]) -> bool;
+
+fn any(_: &[f64]) -> bool {
+    true
+}
+
+fn integers(a: &[f64]) -> bool {
+    a.iter().all(|x| x.fract() == 0.0)
+}
+
+// n and k for nCr(n, k) and friends
+fn counts(a: &[f64]) -> bool {
+    integers(a) && a.iter().all(|&x| x >= 0.0)
+}
+
+fn gcd(a: f64, b: f64) -> f64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0.0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn lcm(a: f64, b: f64) -> f64 {
+    match a == 0.0 || b == 0.0 {
+        true => 0.0,
+        false => (a / gcd(a, b) * b).abs(),
+    }
+}
+
+// Name, arity, function, domain and a description of the domain
+static FUNCS: &[(&str, Arity, Func, Domain, &str)] = &[
+    // rounding
+    ("abs", Arity::Fixed(1), |a| libm::fabs(a[0]), any, ""),
+    ("sign", Arity::Fixed(1), |a| if a[0] == 0.0 { 0.0 } else { a[0].signum() }, any, ""),
+    ("floor", Arity::Fixed(1), |a| libm::floor(a[0]), any, ""),
+    ("ceil", Arity::Fixed(1), |a| libm::ceil(a[0]), any, ""),
+    ("round", Arity::Fixed(1), |a| libm::round(a[0]), any, ""),
+    ("trunc", Arity::Fixed(1), |a| libm::trunc(a[0]), any, ""),
+    ("fract", Arity::Fixed(1), |a| a[0] - libm::trunc(a[0]), any, ""),
+    ("clamp", Arity::Fixed(3), |a| a[0].max(a[1]).min(a[2]), |a| a[1] <= a[2], "lo <= hi"),
+    // arithmetic
+    ("mod", Arity::Fixed(2), |a| a[0] - a[1] * libm::floor(a[0] / a[1]), |a| a[1] != 0.0, "y != 0"),
+    ("gcd", Arity::Variadic(1), |a| a.iter().fold(0.0, |g, &x| gcd(g, x)), integers, "integers"),
+    ("lcm", Arity::Variadic(1), |a| a.iter().fold(1.0, |l, &x| lcm(l, x)), integers, "integers"),
+    ("min", Arity::Variadic(1), |a| a.iter().fold(a[0], |m, &x| m.min(x)), any, ""),
+    ("max", Arity::Variadic(1), |a| a.iter().fold(a[0], |m, &x| m.max(x)), any, ""),
+    ("sum", Arity::Variadic(0), |a| a.iter().sum(), any, ""),
+    ("mean", Arity::Variadic(1), |a| a.iter().sum::<f64>() / a.len() as f64, any, ""),
+    // powers
+    ("sqrt", Arity::Fixed(1), |a| libm::sqrt(a[0]), |a| a[0] >= 0.0, "x >= 0"),
+    ("cbrt", Arity::Fixed(1), |a| libm::cbrt(a[0]), any, ""),
+    ("pow", Arity::Fixed(2), |a| libm::pow(a[0], a[1]),
+     |a| a[0] >= 0.0 || a[1].fract() == 0.0, "x >= 0 or an integer y"),
+    ("hypot", Arity::Fixed(2), |a| libm::hypot(a[0], a[1]), any, ""),
+    ("exp", Arity::Fixed(1), |a| libm::exp(a[0]), any, ""),
+    ("exp2", Arity::Fixed(1), |a| libm::exp2(a[0]), any, ""),
+    ("expm1", Arity::Fixed(1), |a| libm::expm1(a[0]), any, ""),
+    // logarithms
+    ("ln", Arity::Fixed(1), |a| libm::log(a[0]), |a| a[0] > 0.0, "x > 0"),
+    ("log", Arity::Range(1, 2),
+     |a| match a.len() {
+         1 => libm::log10(a[0]),
+         _ => libm::log(a[0]) / libm::log(a[1]),
+     },
+     |a| a[0] > 0.0 && a.get(1).is_none_or(|&b| b > 0.0 && b != 1.0),
+     "x > 0 and base > 0, base != 1"),
+    ("log10", Arity::Fixed(1), |a| libm::log10(a[0]), |a| a[0] > 0.0, "x > 0"),
+    ("log2", Arity::Fixed(1), |a| libm::log2(a[0]), |a| a[0] > 0.0, "x > 0"),
+    ("log1p", Arity::Fixed(1), |a| libm::log1p(a[0]), |a| a[0] > -1.0, "x > -1"),
+    // trigonometry
+    ("sin", Arity::Fixed(1), |a| libm::sin(a[0]), any, ""),
+    ("cos", Arity::Fixed(1), |a| libm::cos(a[0]), any, ""),
+    ("tan", Arity::Fixed(1), |a| libm::tan(a[0]), any, ""),
+    ("asin", Arity::Fixed(1), |a| libm::asin(a[0]), |a| a[0].abs() <= 1.0, "-1 <= x <= 1"),
+    ("acos", Arity::Fixed(1), |a| libm::acos(a[0]), |a| a[0].abs() <= 1.0, "-1 <= x <= 1"),
+    ("atan", Arity::Fixed(1), |a| libm::atan(a[0]), any, ""),
+    ("atan2", Arity::Fixed(2), |a| libm::atan2(a[0], a[1]), any, ""),
+    ("deg", Arity::Fixed(1), |a| a[0] * 180.0 / consts::PI, any, ""),
+    ("rad", Arity::Fixed(1), |a| a[0] * consts::PI / 180.0, any, ""),
+    // hyperbolic
+    ("sinh", Arity::Fixed(1), |a| libm::sinh(a[0]), any, ""),
+    ("cosh", Arity::Fixed(1), |a| libm::cosh(a[0]), any, ""),
+    ("tanh", Arity::Fixed(1), |a| libm::tanh(a[0]), any, ""),
+    ("asinh", Arity::Fixed(1), |a| libm::asinh(a[0]), any, ""),
+    ("acosh", Arity::Fixed(1), |a| libm::acosh(a[0]), |a| a[0] >= 1.0, "x >= 1"),
+    ("atanh", Arity::Fixed(1), |a| libm::atanh(a[0]), |a| a[0].abs() < 1.0, "-1 < x < 1"),
+    // special
+    ("gamma", Arity::Fixed(1), |a| libm::tgamma(a[0]),
+     |a| a[0] > 0.0 || a[0].fract() != 0.0, "x not 0 or a negative integer"),
+    ("lgamma", Arity::Fixed(1), |a| libm::lgamma(a[0]),
+     |a| a[0] > 0.0 || a[0].fract() != 0.0, "x not 0 or a negative integer"),
+    ("erf", Arity::Fixed(1), |a| libm::erf(a[0]), any, ""),
+    ("erfc", Arity::Fixed(1), |a| libm::erfc(a[0]), any, ""),
+    // combinatorics, order not important
+    ("nCr", Arity::Fixed(2),
+     |a| libm::tgamma(a[0] + 1.0) / libm::tgamma(a[1] + 1.0) / libm::tgamma(a[0] - a[1] + 1.0),
+     |a| counts(a) && a[1] <= a[0], "integers 0 <= k <= n"),
+    ("nMCr", Arity::Fixed(2),
+     |a| libm::tgamma(a[0] + a[1]) / libm::tgamma(a[1] + 1.0) / libm::tgamma(a[0]),
+     |a| counts(a) && a[0] >= 1.0, "integers n >= 1, k >= 0"),
+    // order is important
+    ("nPr", Arity::Fixed(2), |a| libm::tgamma(a[0] + 1.0) / libm::tgamma(a[0] - a[1] + 1.0),
+     |a| counts(a) && a[1] <= a[0], "integers 0 <= k <= n"),
+    ("nMPr", Arity::Fixed(2), |a| libm::pow(a[0], a[1]), counts, "integers n, k >= 0"),
+];
+
+pub(crate) fn register(cx: &MathContext) {
+    for &(name, arity, f, domain, rule) in FUNCS {
+        cx.setfn(name, arity, move |args| match domain(args) {
+            true => Ok(f(args)),
+            false => {
+                let args: Vec<_> = args.iter().map(|x| x.to_string()).collect();
+                Err(format!("Domain error: {}({}) needs {}", name, args.join(", "), rule))
+            }
+        });
+    }
+}
@@ -0,0 +1,101 @@
+use crate::rpneval::MathContext;
+
+macro_rules! fuzzy_eq {
+    ($lhs:expr, $rhs:expr) => {
+        assert!(($lhs - $rhs).abs() < 1.0e-10, "{} != {}", $lhs, $rhs)
+    };
+}
+
+fn eval(expr: &str) -> Result<f64, String> {
+    let cx = MathContext::new();
+    cx.eval(&cx.parse_str(expr).unwrap())
+}
+
+#[test]
+fn rounding() {
+    fuzzy_eq!(eval("abs(-2.5) + sign(-3) + sign(0)").unwrap(), 1.5);
+    fuzzy_eq!(eval("floor(-2.5)").unwrap(), -3.0);
+    fuzzy_eq!(eval("ceil(-2.5)").unwrap(), -2.0);
+    fuzzy_eq!(eval("round(2.5) + round(-2.5)").unwrap(), 0.0);
+    fuzzy_eq!(eval("trunc(-2.7)").unwrap(), -2.0);
+    fuzzy_eq!(eval("fract(-2.75)").unwrap(), -0.75);
+    fuzzy_eq!(eval("clamp(7, 0, 5) + clamp(-1, 0, 5)").unwrap(), 5.0);
+    assert_eq!(eval("clamp(1, 5, 0)"), Err("Domain error: clamp(1, 5, 0) needs lo <= hi".to_string()));
+}
+
+#[test]
+fn arithmetic() {
+    fuzzy_eq!(eval("mod(-7, 3)").unwrap(), 2.0);
+    fuzzy_eq!(eval("-7 % 3").unwrap(), -1.0);
+    assert_eq!(eval("mod(1, 0)"), Err("Domain error: mod(1, 0) needs y != 0".to_string()));
+    fuzzy_eq!(eval("gcd(24, -18, 30)").unwrap(), 6.0);
+    fuzzy_eq!(eval("lcm(4, 6, 10)").unwrap(), 60.0);
+    fuzzy_eq!(eval("lcm(0, 6)").unwrap(), 0.0);
+    assert_eq!(eval("gcd(2.5, 5)"), Err("Domain error: gcd(2.5, 5) needs integers".to_string()));
+    fuzzy_eq!(eval("sum(1, 2, 3) + sum() + mean(2, 4, 9)").unwrap(), 11.0);
+    fuzzy_eq!(eval("min(3, -1, 2) + max(3, -1, 2)").unwrap(), 2.0);
+}
+
+#[test]
+fn powers_and_logs() {
+    fuzzy_eq!(eval("sqrt(16) + cbrt(-27)").unwrap(), 1.0);
+    assert_eq!(eval("sqrt(-1)"), Err("Domain error: sqrt(-1) needs x >= 0".to_string()));
+    fuzzy_eq!(eval("pow(-2, 3) + pow(4, 0.5)").unwrap(), -6.0);
+    assert!(eval("pow(-8, 1/3)").is_err());
+    fuzzy_eq!(eval("hypot(3, 4)").unwrap(), 5.0);
+    fuzzy_eq!(eval("exp(1)").unwrap(), std::f64::consts::E);
+    fuzzy_eq!(eval("exp2(10) + expm1(0)").unwrap(), 1024.0);
+    fuzzy_eq!(eval("ln(e^2)").unwrap(), 2.0);
+    // log is the decimal logarithm unless a base is given
+    fuzzy_eq!(eval("log(1000)").unwrap(), 3.0);
+    fuzzy_eq!(eval("log(81, 3)").unwrap(), 4.0);
+    fuzzy_eq!(eval("log10(1000) + log2(8)").unwrap(), 6.0);
+    fuzzy_eq!(eval("log1p(0)").unwrap(), 0.0);
+    assert_eq!(eval("ln(0)"), Err("Domain error: ln(0) needs x > 0".to_string()));
+    assert!(eval("log(8, 1)").is_err());
+    assert!(eval("log1p(-1)").is_err());
+    assert_eq!(eval("log(1, 2, 3)"), Err("Function log takes 1 to 2 args, got 3".to_string()));
+}
+
+#[test]
+fn trigonometry() {
+    fuzzy_eq!(eval("tan(pi/4)").unwrap(), 1.0);
+    fuzzy_eq!(eval("asin(1) + acos(1)").unwrap(), std::f64::consts::FRAC_PI_2);
+    fuzzy_eq!(eval("atan(1) * 4").unwrap(), std::f64::consts::PI);
+    fuzzy_eq!(eval("atan2(1, -1)").unwrap(), 3.0 * std::f64::consts::FRAC_PI_4);
+    fuzzy_eq!(eval("deg(pi) + rad(180)").unwrap(), 180.0 + std::f64::consts::PI);
+    assert_eq!(eval("asin(2)"), Err("Domain error: asin(2) needs -1 <= x <= 1".to_string()));
+    assert!(eval("acos(-1.5)").is_err());
+    fuzzy_eq!(eval("cosh(0.3)^2 - sinh(0.3)^2").unwrap(), 1.0);
+    fuzzy_eq!(eval("tanh(atanh(0.5)) + asinh(sinh(2)) + acosh(cosh(2))").unwrap(), 4.5);
+    assert!(eval("acosh(0.5)").is_err());
+    assert!(eval("atanh(1)").is_err());
+}
+
+#[test]
+fn special() {
+    fuzzy_eq!(eval("gamma(5) + gamma(0.5)^2").unwrap(), 24.0 + std::f64::consts::PI);
+    fuzzy_eq!(eval("lgamma(10)").unwrap(), 362880f64.ln());
+    assert!(eval("gamma(-2)").is_err());
+    assert!(eval("lgamma(0)").is_err());
+    fuzzy_eq!(eval("erf(0) + erfc(0)").unwrap(), 1.0);
+    fuzzy_eq!(eval("nCr(5, 2) + nPr(5, 2) + nMCr(5, 2) + nMPr(5, 2)").unwrap(), 70.0);
+    fuzzy_eq!(eval("nCr(5, 0) + nCr(5, 5) + nPr(3, 3) + nMCr(1, 0) + nMPr(0, 0)").unwrap(), 10.0);
+    assert_eq!(eval("nCr(2, 5)"), Err("Domain error: nCr(2, 5) needs integers 0 <= k <= n".to_string()));
+    assert!(eval("nCr(-1, 0)").is_err());
+    assert!(eval("nCr(5.5, 2)").is_err());
+    assert!(eval("nPr(2, 3)").is_err());
+    assert!(eval("nPr(4, -1)").is_err());
+    assert_eq!(eval("nMCr(0, 2)"), Err("Domain error: nMCr(0, 2) needs integers n >= 1, k >= 0".to_string()));
+    assert!(eval("nMCr(3, 1.5)").is_err());
+    assert!(eval("nMPr(2, -1)").is_err());
+}
+
+#[test]
+fn factorial() {
+    fuzzy_eq!(eval("0! + 5!").unwrap(), 121.0);
+    fuzzy_eq!(eval("0.5!").unwrap(), std::f64::consts::PI.sqrt() / 2.0);
+    fuzzy_eq!(eval("(-0.5)!").unwrap(), std::f64::consts::PI.sqrt());
+    assert_eq!(eval("(-3)!"), Err("Domain error: (-3)! needs x not a negative integer".to_string()));
+    assert!(eval("(2 - 3)!").is_err());
+}
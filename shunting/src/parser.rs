@@ -1,105 +1,381 @@
-use lexers::{MathToken, MathTokenizer};
+use crate::expr::{Expr, ExprKind};
+use lexers::{MathToken, MathTokenizer, Span, Spanned};
+use std::collections::HashMap;
+use std::fmt;
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Assoc {
     Left,
     Right,
 }
 
-pub fn op_precedence(mt: &MathToken) -> Result<(usize, Assoc), String> {
-    // NOTE: This can't encode relations between all tokens, just Ops.
-    // For example:
-    // In https://github.com/rodolf0/natools/blob/master/libparser/parser.c#L56-L94
-    // - unary-minus has to be < than Numbers and OParen
-    // - but OParen has to be < than unary-minus too!
-    // - At the same time, unary-minus has to be > than bin-ops (eg: +)
-    Ok(match mt {
-        MathToken::BOp(o) if o == "+" => (2, Assoc::Left),
-        MathToken::BOp(o) if o == "-" => (2, Assoc::Left),
-        MathToken::BOp(o) if o == "*" => (3, Assoc::Left),
-        MathToken::BOp(o) if o == "/" => (3, Assoc::Left),
-        MathToken::BOp(o) if o == "%" => (3, Assoc::Left),
-        MathToken::BOp(o) if o == "^" || o == "**" => (4, Assoc::Right),
-        MathToken::UOp(o) if o == "-" => (5, Assoc::Right), // unary minus
-        MathToken::UOp(o) if o == "!" => (6, Assoc::Left), // factorial
-        _ => return Err(format!("Undefined precedence for {:?}", mt)),
-    })
+// Where an operator goes relative to its operands
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Fixity {
+    Prefix,  // -x
+    Infix,   // x + y
+    Postfix, // x!
+}
+
+/// Precedence and associativity of operators by lexeme and fixity. Any
+/// lexeme MathTokenizer emits as an operator or single char `Unknown` token
+/// can be an operator, except for `?` and `:` of conditionals which bind
+/// looser than any (precedence 1). The default table has the arithmetic,
+/// comparison and logical operators.
+#[derive(Clone, Debug)]
+pub struct Operators(HashMap<(String, Fixity), (usize, Assoc)>);
+
+impl Default for Operators {
+    fn default() -> Self {
+        // NOTE: This can't encode relations between all tokens, just Ops.
+        // For example:
+        // In https://github.com/rodolf0/natools/blob/master/libparser/parser.c#L56-L94
+        // - unary-minus has to be < than Numbers and OParen
+        // - but OParen has to be < than unary-minus too!
+        // - At the same time, unary-minus has to be > than bin-ops (eg: +)
+        Operators::new()
+            .with("||", Fixity::Infix, 2, Assoc::Left)
+            .with("&&", Fixity::Infix, 3, Assoc::Left)
+            .with("==", Fixity::Infix, 4, Assoc::Left)
+            .with("!=", Fixity::Infix, 4, Assoc::Left)
+            .with("<", Fixity::Infix, 5, Assoc::Left)
+            .with(">", Fixity::Infix, 5, Assoc::Left)
+            .with("<=", Fixity::Infix, 5, Assoc::Left)
+            .with(">=", Fixity::Infix, 5, Assoc::Left)
+            .with("+", Fixity::Infix, 6, Assoc::Left)
+            .with("-", Fixity::Infix, 6, Assoc::Left)
+            .with("*", Fixity::Infix, 7, Assoc::Left)
+            .with("/", Fixity::Infix, 7, Assoc::Left)
+            .with("%", Fixity::Infix, 7, Assoc::Left)
+            .with("^", Fixity::Infix, 8, Assoc::Right)
+            .with("**", Fixity::Infix, 8, Assoc::Right)
+            .with("-", Fixity::Prefix, 9, Assoc::Right) // unary minus
+            .with("!", Fixity::Postfix, 10, Assoc::Left) // factorial
+    }
+}
+
+impl Operators {
+    // A table without operators
+    pub fn new() -> Self {
+        Operators(HashMap::new())
+    }
+
+    // Add or replace an operator. A lexeme is either prefix or postfix, so
+    // adding one drops the other
+    pub fn set(&mut self, op: &str, fixity: Fixity, prec: usize, assoc: Assoc) {
+        match fixity {
+            Fixity::Prefix => self.0.remove(&(op.to_string(), Fixity::Postfix)),
+            Fixity::Postfix => self.0.remove(&(op.to_string(), Fixity::Prefix)),
+            Fixity::Infix => None,
+        };
+        self.0.insert((op.to_string(), fixity), (prec, assoc));
+    }
+
+    pub fn with(mut self, op: &str, fixity: Fixity, prec: usize, assoc: Assoc) -> Self {
+        self.set(op, fixity, prec, assoc);
+        self
+    }
+
+    pub fn get(&self, op: &str, fixity: Fixity) -> Option<(usize, Assoc)> {
+        self.0.get(&(op.to_string(), fixity)).copied()
+    }
+
+    // Fixity of 'op' when an operand is expected or else after one
+    fn fixity(&self, op: &str, want_operand: bool) -> Option<Fixity> {
+        let fixities: &[Fixity] = match want_operand {
+            true => &[Fixity::Prefix],
+            false => &[Fixity::Infix, Fixity::Postfix],
+        };
+        fixities.iter().copied().find(|f| self.get(op, *f).is_some())
+    }
+
+    fn knows(&self, op: &str) -> bool {
+        [Fixity::Prefix, Fixity::Infix, Fixity::Postfix].iter()
+            .any(|f| self.get(op, *f).is_some())
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct RPNExpr(pub Vec<MathToken>);
 
+/// Parse errors point at the offending token.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    MissingOParen(Span),
+    MissingCParen(Span),
+    MisplacedComma(Span),
+    // a '?' without its ':' or the other way around
+    MissingColon(Span),
+    MisplacedColon(Span),
+    BadToken(MathToken, Span),
+    // operators without precedence, eg: :=
+    UnknownOp(String, Span),
+    // an operator, call or parens lack an operand, eg: 3 +
+    MissingOperand(Span),
+    // an operand doesn't belong to any operator, eg: 3 4
+    ExtraOperand(Span),
+    Empty,
+}
+
+impl ParseError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParseError::MissingOParen(span) |
+            ParseError::MissingCParen(span) |
+            ParseError::MisplacedComma(span) |
+            ParseError::MissingColon(span) |
+            ParseError::MisplacedColon(span) |
+            ParseError::BadToken(_, span) |
+            ParseError::UnknownOp(_, span) |
+            ParseError::MissingOperand(span) |
+            ParseError::ExtraOperand(span) => Some(*span),
+            ParseError::Empty => None,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingOParen(_) => write!(f, "Missing Opening Paren")?,
+            ParseError::MissingCParen(_) => write!(f, "Missing Closing Paren")?,
+            ParseError::MisplacedComma(_) => write!(f, "Comma outside function arglist")?,
+            ParseError::MissingColon(_) => write!(f, "Missing ':' for '?'")?,
+            ParseError::MisplacedColon(_) => write!(f, "':' without '?'")?,
+            ParseError::BadToken(MathToken::Unknown(lexeme), _) => write!(f, "Bad token: {}", lexeme)?,
+            ParseError::BadToken(token, _) => write!(f, "Bad token: {:?}", token)?,
+            ParseError::UnknownOp(op, _) => write!(f, "Undefined precedence for {}", op)?,
+            ParseError::MissingOperand(_) => write!(f, "Missing operand")?,
+            ParseError::ExtraOperand(_) => write!(f, "Missing operator")?,
+            ParseError::Empty => return write!(f, "Empty expression"),
+        }
+        match self.span() {
+            Some(span) => write!(f, " at {}", span.start),
+            None => Ok(()),
+        }
+    }
+}
+
+// Precedence of '?' and ':' in conditionals, c ? a : b
+const COND_PREC: usize = 1;
+
+// Tokens waiting for their operands, along with the output length when they
+// were pushed, the precedence of operators and for parens the number of
+// commas read so far
+struct Pending {
+    token: Spanned<MathToken>,
+    mark: usize,
+    prec: usize,
+    commas: usize,
+}
+
+fn join(a: Span, b: Span) -> Span {
+    Span { start: a.start.min(b.start), end: a.end.max(b.end) }
+}
+
+// Output length where the innermost open paren started
+fn floor(stack: &[Pending]) -> usize {
+    stack.iter().rev()
+        .find(|p| p.token.value == MathToken::OParen)
+        .map_or(0, |p| p.mark)
+}
+
+// Build the node of an operator out of the operands in 'out' past 'floor'
+fn reduce(out: &mut Vec<Expr>, floor: usize, op: Spanned<MathToken>) -> Result<(), ParseError> {
+    let mut operand = || match out.len() > floor {
+        true => Ok(out.pop().unwrap()),
+        false => Err(ParseError::MissingOperand(op.span)),
+    };
+    let expr = match op.value {
+        MathToken::BOp(o) if o == "?" => return Err(ParseError::MissingColon(op.span)),
+        // c ? a : b, the '?' was replaced by its ':'
+        MathToken::BOp(o) if o == ":" => {
+            let otherwise = operand()?;
+            let then = operand()?;
+            let cond = operand()?;
+            let span = join(cond.span, otherwise.span);
+            Expr::new(ExprKind::Cond(Box::new(cond), Box::new(then), Box::new(otherwise)), span)
+        }
+        MathToken::UOp(o) => {
+            let arg = operand()?;
+            let span = join(op.span, arg.span);
+            Expr::new(ExprKind::UOp(o, Box::new(arg)), span)
+        }
+        MathToken::BOp(o) => {
+            let rhs = operand()?;
+            let lhs = operand()?;
+            let span = join(lhs.span, rhs.span);
+            Expr::new(ExprKind::BOp(o, Box::new(lhs), Box::new(rhs)), span)
+        }
+        other => return Err(ParseError::BadToken(other, op.span)),
+    };
+    out.push(expr);
+    Ok(())
+}
+
+// Check that 'expected' operands were read past 'mark'
+fn check_operands(out: &[Expr], mark: usize, expected: usize, at: Span) -> Result<(), ParseError> {
+    let found = out.len() - mark;
+    if found < expected {
+        return Err(ParseError::MissingOperand(at));
+    }
+    if found > expected {
+        return Err(ParseError::ExtraOperand(out[mark + expected].span));
+    }
+    Ok(())
+}
+
 pub struct ShuntingParser;
 
 impl ShuntingParser {
-    pub fn parse_str(expr: &str) -> Result<RPNExpr, String> {
-        Self::parse(&mut MathTokenizer::new(expr.chars()))
+    pub fn parse_str(expr: &str) -> Result<Expr, ParseError> {
+        Self::parse(&mut MathTokenizer::new(expr.chars()).with_spans())
     }
 
-    pub fn parse(lexer: &mut impl Iterator<Item = MathToken>) -> Result<RPNExpr, String> {
-        let mut out = Vec::new();
-        let mut stack = Vec::new();
-        let mut arity = Vec::<usize>::new();
+    pub fn parse(lexer: &mut impl Iterator<Item = Spanned<MathToken>>) -> Result<Expr, ParseError> {
+        Self::parse_with(lexer, &Operators::default())
+    }
+
+    // Parse with a custom operator table
+    pub fn parse_with(lexer: &mut impl Iterator<Item = Spanned<MathToken>>, ops: &Operators)
+            -> Result<Expr, ParseError> {
+        let mut out = Vec::<Expr>::new();
+        let mut stack = Vec::<Pending>::new();
+        // operands must come after operators, eg: 3 4 is an error
+        let mut want_operand = true;
 
         for token in lexer {
-            match token {
-                MathToken::Number(_) => out.push(token),
-                MathToken::Variable(_) => out.push(token),
-                MathToken::OParen => stack.push(token),
-                MathToken::Function(_, _) => {
-                    stack.push(token);
-                    arity.push(1);
-                }
-                MathToken::Comma | MathToken::CParen => {
-                    // Flush stack to output queue until open paren
-                    loop {
-                        match stack.pop() {
-                            // Only advance until we find the matching open paren
-                            Some(MathToken::OParen) => break,
-                            Some(any) => out.push(any),
-                            None => return Err("Missing Opening Paren".to_string()),
+            let span = token.span;
+            match token.value {
+                MathToken::BOp(op) if op == "?" || op == ":" => {
+                    if want_operand {
+                        return Err(ParseError::MissingOperand(span));
+                    }
+                    // Flush operators binding tighter than conditionals, for
+                    // ':' up to its '?' which is replaced by the ':'
+                    while let Some(stack_top) = stack.last() {
+                        if stack_top.token.value == MathToken::OParen ||
+                            (op == "?" && stack_top.prec <= COND_PREC) ||
+                            (op == ":" && stack_top.token.value == MathToken::BOp("?".to_string())) {
+                            break;
                         }
+                        let top = stack.pop().unwrap();
+                        reduce(&mut out, floor(&stack), top.token)?;
                     }
-                    if token == MathToken::Comma {
-                        // Keep track of function arity based on number of commas
-                        stack.push(MathToken::OParen); // put back OParen if reading Comma
-                        match arity.last_mut() {
-                            Some(a) => *a += 1,
-                            None => return Err("Comma outside function arglist".to_string())
+                    if op == ":" {
+                        match stack.pop() {
+                            Some(p) if p.token.value == MathToken::BOp("?".to_string()) => (),
+                            _ => return Err(ParseError::MisplacedColon(span)),
                         }
-                    } else if let Some(MathToken::Function(fname, _)) = stack.last() {
-                        // token is CParen. Popped everything up to OParen. Check fn call.
-                        out.push(MathToken::Function(fname.clone(), arity.pop().unwrap()));
-                        stack.pop(); // pop the function we just shifted out
                     }
+                    let token = Spanned::new(MathToken::BOp(op), span);
+                    stack.push(Pending { token, mark: out.len(), prec: COND_PREC, commas: 0 });
+                    want_operand = true;
                 }
-                MathToken::UOp(_) | MathToken::BOp(_) => {
-                    let (input_token_prec, input_token_assoc) = op_precedence(&token)?;
-                    // Flush stack while its precedence is lower than input or reach OParen
+                // what came before tells prefix from infix operators, eg: 2 - -1
+                MathToken::UOp(op) | MathToken::BOp(op) | MathToken::Unknown(op)
+                        if ops.knows(&op) => {
+                    let fixity = match ops.fixity(&op, want_operand) {
+                        Some(fixity) => fixity,
+                        None if want_operand => return Err(ParseError::MissingOperand(span)),
+                        None => return Err(ParseError::ExtraOperand(span)),
+                    };
+                    let (prec, assoc) = ops.get(&op, fixity).unwrap();
+                    // Flush stack while its precedence is higher than input or reach OParen,
+                    // prefix operators have no operands to flush yet
                     while let Some(stack_top) = stack.last() {
-                        if stack_top == &MathToken::OParen {
+                        if fixity == Fixity::Prefix || stack_top.token.value == MathToken::OParen ||
+                            stack_top.prec < prec || (
+                            stack_top.prec == prec && assoc == Assoc::Right) {
                             break;
                         }
-                        let (stack_top_prec, _) = op_precedence(stack_top)?;
-                        if stack_top_prec < input_token_prec || (
-                            stack_top_prec == input_token_prec &&
-                            input_token_assoc == Assoc::Right) {
-                            break;
+                        let top = stack.pop().unwrap();
+                        reduce(&mut out, floor(&stack), top.token)?;
+                    }
+                    let token = match fixity {
+                        Fixity::Infix => MathToken::BOp(op),
+                        _ => MathToken::UOp(op),
+                    };
+                    stack.push(Pending { token: Spanned::new(token, span), mark: out.len(), prec, commas: 0 });
+                    want_operand = fixity != Fixity::Postfix;
+                }
+                MathToken::UOp(op) | MathToken::BOp(op) => return Err(ParseError::UnknownOp(op, span)),
+                MathToken::Number(_) | MathToken::Variable(_) | MathToken::OParen |
+                MathToken::Function(_, _) if !want_operand => return Err(ParseError::ExtraOperand(span)),
+                MathToken::Number(n) => {
+                    out.push(Expr::new(ExprKind::Number(n), span));
+                    want_operand = false;
+                }
+                MathToken::Variable(v) => {
+                    out.push(Expr::new(ExprKind::Variable(v), span));
+                    want_operand = false;
+                }
+                MathToken::OParen | MathToken::Function(_, _) =>
+                    stack.push(Pending { token, mark: out.len(), prec: 0, commas: 0 }),
+                MathToken::Comma | MathToken::CParen => {
+                    // Flush stack to output queue until open paren
+                    let paren = loop {
+                        match stack.pop() {
+                            // Only advance until we find the matching open paren
+                            Some(p) if p.token.value == MathToken::OParen => break p,
+                            Some(p) => reduce(&mut out, floor(&stack), p.token)?,
+                            None => return Err(ParseError::MissingOParen(span)),
+                        }
+                    };
+                    let call = matches!(stack.last(),
+                                        Some(Pending { token: Spanned { value: MathToken::Function(..), .. }, .. }));
+                    if token.value == MathToken::Comma {
+                        if !call {
+                            return Err(ParseError::MisplacedComma(span));
                         }
-                        out.push(stack.pop().unwrap());
+                        // Keep track of function arity based on number of commas
+                        check_operands(&out, paren.mark, paren.commas + 1, span)?;
+                        stack.push(Pending { commas: paren.commas + 1, ..paren });
+                        want_operand = true;
+                        continue;
+                    }
+                    if call {
+                        // Popped everything up to OParen. Build the fn call
+                        let function = stack.pop().unwrap();
+                        // no args at all is fine too, eg: f()
+                        if paren.commas > 0 || out.len() > paren.mark {
+                            check_operands(&out, paren.mark, paren.commas + 1, span)?;
+                        }
+                        let args = out.split_off(paren.mark);
+                        let fname = match function.token.value {
+                            MathToken::Function(fname, _) => fname,
+                            _ => unreachable!(),
+                        };
+                        let span = join(function.token.span, span);
+                        let kind = match (fname.as_str(), <[Expr; 3]>::try_from(args)) {
+                            // if(c, a, b) is the same as c ? a : b
+                            ("if", Ok([cond, then, otherwise])) =>
+                                ExprKind::Cond(Box::new(cond), Box::new(then), Box::new(otherwise)),
+                            (_, Ok(args)) => ExprKind::Function(fname, args.into()),
+                            (_, Err(args)) => ExprKind::Function(fname, args),
+                        };
+                        out.push(Expr::new(kind, span));
+                    } else {
+                        check_operands(&out, paren.mark, 1, span)?;
+                        // parens are part of the expression's span
+                        let expr = out.last_mut().unwrap();
+                        expr.span = join(expr.span, join(paren.token.span, span));
                     }
-                    stack.push(token);
+                    want_operand = false;
                 }
-                MathToken::Quantity(_, _, _) => return Err("Can't handle quantities".to_string()),
-                MathToken::Unknown(lexeme) => return Err(format!("Bad token: {}", lexeme)),
+                other => return Err(ParseError::BadToken(other, span)),
             }
         }
         while let Some(top) = stack.pop() {
-            match top {
-                MathToken::OParen => return Err("Missing Closing Paren".to_string()),
-                token => out.push(token),
+            match top.token.value {
+                MathToken::OParen => return Err(ParseError::MissingCParen(top.token.span)),
+                _ => reduce(&mut out, floor(&stack), top.token)?,
             }
         }
-        Ok(RPNExpr(out))
+        match out.len() {
+            0 => Err(ParseError::Empty),
+            1 => Ok(out.pop().unwrap()),
+            _ => Err(ParseError::ExtraOperand(out[1].span)),
+        }
     }
 }
@@ -1,9 +1,21 @@
-use crate::parser::{RPNExpr, ShuntingParser};
-use lexers::MathToken;
+use crate::expr::{Expr, ExprKind};
+use crate::parser::{ParseError, RPNExpr, ShuntingParser};
+use lexers::{MathToken, Position, Span};
+
+// Parse and lower to RPN
+fn parse_rpn(expr: &str) -> Result<RPNExpr, ParseError> {
+    ShuntingParser::parse_str(expr).map(|expr| expr.rpn())
+}
+
+// Span within the first line
+fn span(start: usize, end: usize) -> Span {
+    let pos = |column: usize| Position { line: 1, column, offset: column - 1 };
+    Span { start: pos(start), end: pos(end) }
+}
 
 #[test]
 fn test_associativity() {
-    let rpn = ShuntingParser::parse_str("2^3^4");
+    let rpn = parse_rpn("2^3^4");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -12,7 +24,7 @@ fn test_associativity() {
         MathToken::BOp("^".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("2*3*4");
+    let rpn = parse_rpn("2*3*4");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -25,7 +37,7 @@ fn test_associativity() {
 
 #[test]
 fn test_precedence() {
-    let rpn = ShuntingParser::parse_str("2+3*4");
+    let rpn = parse_rpn("2+3*4");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -34,7 +46,7 @@ fn test_precedence() {
         MathToken::BOp("+".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("2*3+4");
+    let rpn = parse_rpn("2*3+4");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -43,7 +55,7 @@ fn test_precedence() {
         MathToken::BOp("+".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("2+3*4^5");
+    let rpn = parse_rpn("2+3*4^5");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -54,7 +66,7 @@ fn test_precedence() {
         MathToken::BOp("+".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("2^3+4*5");
+    let rpn = parse_rpn("2^3+4*5");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -69,7 +81,7 @@ fn test_precedence() {
 
 #[test]
 fn test_unary_ops() {
-    let rpn = ShuntingParser::parse_str("2/-1");
+    let rpn = parse_rpn("2/-1");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(1.0),
@@ -77,7 +89,7 @@ fn test_unary_ops() {
         MathToken::BOp("/".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("-2/1");
+    let rpn = parse_rpn("-2/1");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::UOp("-".to_string()),
@@ -85,14 +97,14 @@ fn test_unary_ops() {
         MathToken::BOp("/".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("-2!");
+    let rpn = parse_rpn("-2!");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::UOp("!".to_string()),
         MathToken::UOp("-".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("-2^3");
+    let rpn = parse_rpn("-2^3");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::UOp("-".to_string()),
@@ -100,7 +112,7 @@ fn test_unary_ops() {
         MathToken::BOp("^".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("2^-3");
+    let rpn = parse_rpn("2^-3");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -108,7 +120,7 @@ fn test_unary_ops() {
         MathToken::BOp("^".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("2^3!");
+    let rpn = parse_rpn("2^3!");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -116,7 +128,7 @@ fn test_unary_ops() {
         MathToken::BOp("^".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("(-2)^3");
+    let rpn = parse_rpn("(-2)^3");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::UOp("-".to_string()),
@@ -124,7 +136,7 @@ fn test_unary_ops() {
         MathToken::BOp("^".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("-(1-5)");
+    let rpn = parse_rpn("-(1-5)");
     let expect = vec![
         MathToken::Number(1.0),
         MathToken::Number(5.0),
@@ -136,7 +148,7 @@ fn test_unary_ops() {
 
 #[test]
 fn test_parens() {
-    let rpn = ShuntingParser::parse_str("(2+3)*4");
+    let rpn = parse_rpn("(2+3)*4");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -145,7 +157,7 @@ fn test_parens() {
         MathToken::BOp("*".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("2*(3*4)");
+    let rpn = parse_rpn("2*(3*4)");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -154,7 +166,7 @@ fn test_parens() {
         MathToken::BOp("*".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("(2^3)^4");
+    let rpn = parse_rpn("(2^3)^4");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -163,7 +175,7 @@ fn test_parens() {
         MathToken::BOp("^".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("((2+3)*4)^5");
+    let rpn = parse_rpn("((2+3)*4)^5");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Number(3.0),
@@ -178,7 +190,7 @@ fn test_parens() {
 
 #[test]
 fn test_mixed_ops() {
-    let rpn = ShuntingParser::parse_str("3+4*2/-(1-5)^2^3");
+    let rpn = parse_rpn("3+4*2/-(1-5)^2^3");
     let expect = vec![
         MathToken::Number(3.0),
         MathToken::Number(4.0),
@@ -196,7 +208,7 @@ fn test_mixed_ops() {
         MathToken::BOp("+".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("3.4e-2 * sin(x)/(7! % -4) * max(2, x)");
+    let rpn = parse_rpn("3.4e-2 * sin(x)/(7! % -4) * max(2, x)");
     let expect = vec![
         MathToken::Number(3.4e-2),
         MathToken::Variable("x".to_string()),
@@ -214,7 +226,7 @@ fn test_mixed_ops() {
         MathToken::BOp("*".to_string()),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("sqrt(-(1-x^2) / (1 + x^2))");
+    let rpn = parse_rpn("sqrt(-(1-x^2) / (1 + x^2))");
     let expect = vec![
         MathToken::Number(1.0),
         MathToken::Variable("x".to_string()),
@@ -235,34 +247,100 @@ fn test_mixed_ops() {
 
 #[test]
 fn bad_parse() {
-    let rpn = ShuntingParser::parse_str("1-x^2)");
-    assert_eq!(rpn, Err("Missing Opening Paren".to_string()));
-    let rpn = ShuntingParser::parse_str("max 4, 6, 4)");
-    assert_eq!(rpn, Err("Missing Opening Paren".to_string()));
-    let rpn = ShuntingParser::parse_str("sqrt(-(1-x^2)");
-    assert_eq!(rpn, Err("Missing Closing Paren".to_string()));
-    let rpn = ShuntingParser::parse_str("(2, 3)");
-    assert_eq!(rpn, Err("Comma outside function arglist".to_string()));
-    let rpn = ShuntingParser::parse_str("3 # 4");
-    assert_eq!(rpn, Err("Bad token: #".to_string()));
+    let err = ShuntingParser::parse_str("1-x^2)");
+    assert_eq!(err, Err(ParseError::MissingOParen(span(6, 7))));
+    assert_eq!(err.unwrap_err().to_string(), "Missing Opening Paren at 1:6");
+    let err = ShuntingParser::parse_str("max 4, 6, 4)");
+    assert_eq!(err, Err(ParseError::ExtraOperand(span(5, 6))));
+    let err = ShuntingParser::parse_str("4, 6");
+    assert_eq!(err, Err(ParseError::MissingOParen(span(2, 3))));
+    let err = ShuntingParser::parse_str("3 + * 4");
+    assert_eq!(err, Err(ParseError::MissingOperand(span(5, 6))));
+    let err = ShuntingParser::parse_str("sqrt(-(1-x^2)");
+    assert_eq!(err, Err(ParseError::MissingCParen(span(5, 6))));
+    let err = ShuntingParser::parse_str("(2, 3)");
+    assert_eq!(err, Err(ParseError::MisplacedComma(span(3, 4))));
+    let err = ShuntingParser::parse_str("3 $ 4");
+    assert_eq!(err.unwrap_err().to_string(), "Bad token: $ at 1:3");
+    let err = ShuntingParser::parse_str("2 := 3");
+    assert_eq!(err, Err(ParseError::UnknownOp(":=".to_string(), span(3, 5))));
+    let err = ShuntingParser::parse_str("3 * (4 +)");
+    assert_eq!(err, Err(ParseError::MissingOperand(span(8, 9))));
+    let err = ShuntingParser::parse_str("max(1,, 2)");
+    assert_eq!(err, Err(ParseError::MissingOperand(span(7, 8))));
+    let err = ShuntingParser::parse_str("2 + 3 4");
+    assert_eq!(err, Err(ParseError::ExtraOperand(span(7, 8))));
+    let err = ShuntingParser::parse_str("()");
+    assert_eq!(err, Err(ParseError::MissingOperand(span(2, 3))));
+    assert_eq!(ShuntingParser::parse_str(" # nothing"), Err(ParseError::Empty));
+}
+
+#[test]
+fn test_conditionals() {
+    let rpn = parse_rpn("x > 0 && y != 1 || z").unwrap();
+    assert_eq!(rpn.to_string(), "x 0 > y 1 != && z ||");
+    let rpn = parse_rpn("a + 1 < b ? c : d * 2").unwrap();
+    assert_eq!(rpn.to_string(), "a 1 + b < c d 2 * if/3");
+    // right associative, the else branch nests
+    let rpn = parse_rpn("a ? b : c ? d : e").unwrap();
+    assert_eq!(rpn.to_string(), "a b c d e if/3 if/3");
+    let rpn = parse_rpn("a ? b ? c : d : e").unwrap();
+    assert_eq!(rpn.to_string(), "a b c d if/3 e if/3");
+    let rpn = parse_rpn("f(a ? b : c, (d ? e : 1) + 2)").unwrap();
+    assert_eq!(rpn.to_string(), "a b c if/3 d e 1 if/3 2 + f/2");
+    let expr = ShuntingParser::parse_str("if(a, 1, 2)").unwrap();
+    assert!(matches!(expr.kind, ExprKind::Cond(_, _, _)));
+    assert_eq!(expr.span, span(1, 12));
+    assert_eq!(expr.to_string(), "\u{252c}?:\n\u{251c}\u{2500}a\n\u{251c}\u{2500}1\n\u{2570}\u{2500}2\n");
+    let err = ShuntingParser::parse_str("a ? b");
+    assert_eq!(err, Err(ParseError::MissingColon(span(3, 4))));
+    assert_eq!(err.unwrap_err().to_string(), "Missing ':' for '?' at 1:3");
+    let err = ShuntingParser::parse_str("a : b");
+    assert_eq!(err, Err(ParseError::MisplacedColon(span(3, 4))));
+    let err = ShuntingParser::parse_str("(a ? b) : c");
+    assert_eq!(err, Err(ParseError::MissingColon(span(4, 5))));
+    let err = ShuntingParser::parse_str("a ? (b : c)");
+    assert_eq!(err, Err(ParseError::MisplacedColon(span(8, 9))));
+    let err = ShuntingParser::parse_str("a ? : c");
+    assert_eq!(err, Err(ParseError::MissingOperand(span(5, 6))));
+}
+
+#[test]
+fn test_expr() {
+    let num = |n, start, end| Expr::new(ExprKind::Number(n), span(start, end));
+    let expr = ShuntingParser::parse_str("(1 + x)*f()!").unwrap();
+    let sum = Expr::new(ExprKind::BOp(
+        "+".to_string(),
+        Box::new(num(1.0, 2, 3)),
+        Box::new(Expr::new(ExprKind::Variable("x".to_string()), span(6, 7)))),
+        span(1, 8));
+    let call = Expr::new(ExprKind::Function("f".to_string(), vec![]), span(9, 12));
+    let fact = Expr::new(ExprKind::UOp("!".to_string(), Box::new(call)), span(9, 13));
+    let expect = Expr::new(ExprKind::BOp("*".to_string(), Box::new(sum), Box::new(fact)), span(1, 13));
+    assert_eq!(expr, expect);
+    assert_eq!(expr.rpn().to_string(), "1 x + f/0 ! *");
+    assert_eq!(expr.to_string(), "\u{252c}*\n\u{251c}\u{252c}+\n\u{2502}\u{251c}\u{2500}1\n\u{2502}\u{2570}\u{2500}x\n\u{2570}\u{252c}!\n \u{2570}\u{2500}f()\n");
+    let expr = ShuntingParser::parse_str("-max(2, x)").unwrap();
+    assert_eq!(expr.span, span(1, 11));
+    assert_eq!(expr.children()[0].children()[1].span, span(9, 10));
 }
 
 #[test]
 fn test_functions() {
-    let rpn = ShuntingParser::parse_str("sin(pi)");
+    let rpn = parse_rpn("sin(pi)");
     let expect = vec![
         MathToken::Variable("pi".to_string()),
         MathToken::Function("sin".to_string(), 1),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("max(2, x)");
+    let rpn = parse_rpn("max(2, x)");
     let expect = vec![
         MathToken::Number(2.0),
         MathToken::Variable("x".to_string()),
         MathToken::Function("max".to_string(), 2),
     ];
     assert_eq!(rpn, Ok(RPNExpr(expect)));
-    let rpn = ShuntingParser::parse_str("sum(i , 0, gcd(24, 8))");
+    let rpn = parse_rpn("sum(i , 0, gcd(24, 8))");
     let expect = vec![
         MathToken::Variable("i".to_string()),
         MathToken::Number(0.0),
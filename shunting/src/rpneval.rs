@@ -1,5 +1,7 @@
-use crate::parser::RPNExpr;
-use lexers::MathToken;
+use crate::expr::{Expr, ExprKind};
+use crate::mathlib;
+use crate::parser::{Assoc, Fixity, Operators, ParseError, ShuntingParser};
+use lexers::{MathToken, MathTokenizer, Spanned};
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::cell::RefCell;
@@ -32,9 +34,18 @@ pub struct Histogram<const BUCKETS: usize> {
 }
 
 impl MathOp {
+    // Like eval, but errors of dynamic ops are returned
+    fn try_eval(&self) -> Result<f64, String> {
+        match self {
+            MathOp::Number(n) => Ok(*n),
+            MathOp::Dynamic(f) => f(),
+        }
+    }
+
+    // Samples that fail to evaluate are left out of the histogram
     pub fn histogram<const BUCKETS: usize>(&self, samples: usize) -> Histogram<BUCKETS> {
         // collect samples from random variable
-        let data: Vec<_> = (0..samples).map(|_| self.eval()).collect();
+        let data: Vec<_> = (0..samples).filter_map(|_| self.try_eval().ok()).collect();
         // extract info from data to build histogram
         let (min, max) = data.iter().fold((f64::MAX, f64::MIN), |(min, max), &x| {
             (min.min(x), max.max(x))
@@ -49,175 +60,313 @@ impl MathOp {
     }
 }
 
-pub struct MathContext(Rc<RefCell<HashMap<String, MathOp>>>);
+// Native functions and operators get their operands in order
+pub type NativeFn = Rc<dyn Fn(&[f64]) -> Result<f64, String>>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arity {
+    Fixed(usize),
+    // at least this many args
+    Variadic(usize),
+    // between min and max args
+    Range(usize, usize),
+}
+
+impl Arity {
+    fn check(&self, fname: &str, args: usize) -> Result<(), String> {
+        match *self {
+            Arity::Fixed(n) if n != args =>
+                Err(format!("Function {} takes {} args, got {}", fname, n, args)),
+            Arity::Variadic(n) if args < n =>
+                Err(format!("Function {} takes at least {} args, got {}", fname, n, args)),
+            Arity::Range(min, max) if args < min || args > max =>
+                Err(format!("Function {} takes {} to {} args, got {}", fname, min, max, args)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Function {
+    Native(Arity, NativeFn),
+    // defined from an expression, eg: f(x, y) := x^2 + y
+    User(Rc<[String]>, Rc<Expr>),
+}
+
+// How deep user functions may call each other
+const MAX_DEPTH: usize = 256;
+
+struct Context {
+    vars: HashMap<String, MathOp>,
+    funcs: HashMap<String, Function>,
+    ops: Operators,
+    opfns: HashMap<(String, Fixity), NativeFn>,
+    depth: usize,
+}
+
+/// Variables, functions and operators to parse and evaluate expressions
+/// with. Clones share the same context.
+#[derive(Clone)]
+pub struct MathContext(Rc<RefCell<Context>>);
 
 impl MathContext {
     pub fn new() -> MathContext {
         use std::f64::consts;
-        let mut cx = HashMap::new();
-        cx.insert("pi".to_string(), MathOp::Number(consts::PI));
-        cx.insert("e".to_string(), MathOp::Number(consts::E));
-        MathContext(Rc::new(RefCell::new(cx)))
+        let cx = MathContext(Rc::new(RefCell::new(Context {
+            vars: HashMap::new(),
+            funcs: HashMap::new(),
+            ops: Operators::default(),
+            opfns: HashMap::new(),
+            depth: 0,
+        })));
+        cx.setvar("pi", MathOp::Number(consts::PI));
+        cx.setvar("e", MathOp::Number(consts::E));
+        builtins(&cx);
+        cx
     }
 
     pub fn setvar(&self, name: &str, value: MathOp) {
-        self.0.borrow_mut().insert(name.to_string(), value);
+        self.0.borrow_mut().vars.insert(name.to_string(), value);
     }
 
-    pub fn eval(&self, rpn: &RPNExpr) -> Result<f64, String> {
-        let mut operands = Vec::new();
+    // Add or replace a native function
+    pub fn setfn(&self, name: &str, arity: Arity,
+                 f: impl Fn(&[f64]) -> Result<f64, String> + 'static) {
+        self.0.borrow_mut().funcs.insert(name.to_string(), Function::Native(arity, Rc::new(f)));
+    }
 
-        for token in &rpn.0 {
-            match token {
-                MathToken::Number(num) => operands.push(*num),
-                MathToken::Variable(ref v) => operands.push(
-                    match self.0.borrow().get(v) {
-                        Some(mathop) => mathop.eval(),
-                        None => return Err(format!("Unknown Variable: {}", v)),
-                    }
-                ),
-                MathToken::BOp(op) => {
-                    let rhs = operands.pop().ok_or("Missing operands")?;
-                    let lhs = operands.pop().ok_or("Missing operands")?;
-                    operands.push(match &op[..] {
-                        "+" => lhs + rhs,
-                        "-" => lhs - rhs,
-                        "*" => lhs * rhs,
-                        "/" => lhs / rhs,
-                        "%" => lhs % rhs,
-                        "^" | "**" => lhs.powf(rhs),
-                        _ => return Err(format!("Unknown BOp: {}", op)),
-                    });
-                }
-                MathToken::UOp(op) => {
-                    let arg = operands.pop().ok_or("Missing operands")?;
-                    operands.push(match &op[..] {
-                        "-" => -arg,
-                        "!" => libm::tgamma(arg + 1.0),
-                        _ => return Err(format!("Unknown UOp: {}", op)),
-                    });
+    // Add or replace a function defined by an expression over 'params'
+    pub fn deffn(&self, name: &str, params: Vec<String>, body: Expr) {
+        let function = Function::User(params.into(), Rc::new(body));
+        self.0.borrow_mut().funcs.insert(name.to_string(), function);
+    }
+
+    // Add or replace an operator, see Operators. 'f' gets one operand for
+    // prefix and postfix operators and two for infix ones
+    pub fn setop(&self, op: &str, fixity: Fixity, prec: usize, assoc: Assoc,
+                 f: impl Fn(&[f64]) -> Result<f64, String> + 'static) {
+        let mut cx = self.0.borrow_mut();
+        cx.ops.set(op, fixity, prec, assoc);
+        let unary = [Fixity::Prefix, Fixity::Postfix];
+        if unary.contains(&fixity) {
+            unary.iter().for_each(|f| { cx.opfns.remove(&(op.to_string(), *f)); });
+        }
+        cx.opfns.insert((op.to_string(), fixity), Rc::new(f));
+    }
+
+    // Parse with this context's operators
+    pub fn parse_str(&self, expr: &str) -> Result<Expr, ParseError> {
+        self.parse(&mut MathTokenizer::new(expr.chars()).with_spans())
+    }
+
+    pub fn parse(&self, lexer: &mut impl Iterator<Item = Spanned<MathToken>>) -> Result<Expr, ParseError> {
+        ShuntingParser::parse_with(lexer, &self.0.borrow().ops)
+    }
+
+    pub fn eval(&self, expr: &Expr) -> Result<f64, String> {
+        self.eval_in(expr, &HashMap::new())
+    }
+
+    // Evaluate with the args of a user function in 'locals'
+    fn eval_in(&self, expr: &Expr, locals: &HashMap<&str, f64>) -> Result<f64, String> {
+        match &expr.kind {
+            ExprKind::Number(num) => Ok(*num),
+            ExprKind::Variable(v) => match locals.get(v.as_str()) {
+                Some(value) => Ok(*value),
+                None => match self.var(v) {
+                    Some(mathop) => mathop.try_eval(),
+                    None => Err(format!("Unknown variable: {}", v)),
+                },
+            },
+            // short-circuit, the rhs is evaluated only if needed
+            ExprKind::BOp(op, lhs, rhs) if op == "&&" || op == "||" => {
+                let lhs = truth(self.eval_in(lhs, locals)?);
+                match lhs == (op == "||") {
+                    true => Ok(number(lhs)),
+                    false => Ok(number(truth(self.eval_in(rhs, locals)?))),
                 }
-                MathToken::Function(fname, arity) => {
-                    if *arity > operands.len() {
-                        return Err(format!("Missing args for function {}", fname));
+            }
+            ExprKind::BOp(op, lhs, rhs) => {
+                let f = self.opfn(op, &[Fixity::Infix])?;
+                f(&[self.eval_in(lhs, locals)?, self.eval_in(rhs, locals)?])
+            }
+            ExprKind::UOp(op, arg) => {
+                let f = self.opfn(op, &[Fixity::Prefix, Fixity::Postfix])?;
+                f(&[self.eval_in(arg, locals)?])
+            }
+            ExprKind::Function(fname, args) => {
+                let args = args.iter()
+                    .map(|arg| self.eval_in(arg, locals))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call(fname, &args)
+            }
+            ExprKind::Cond(cond, then, otherwise) => match truth(self.eval_in(cond, locals)?) {
+                true => self.eval_in(then, locals),
+                false => self.eval_in(otherwise, locals),
+            },
+        }
+    }
+
+    // Build a MathOp, constant sub-expressions are folded and variables are
+    // bound to their current value. User functions are looked up when called
+    // so they may be redefined or recursive
+    pub fn compile(&self, expr: &Expr) -> Result<MathOp, String> {
+        Ok(match &expr.kind {
+            ExprKind::Number(n) => MathOp::Number(*n),
+            ExprKind::Variable(v) => self.var(v).ok_or(format!("Unknown variable: {}", v))?,
+            // short-circuit, branches not taken are compiled lazily
+            ExprKind::BOp(op, lhs, rhs) if op == "&&" || op == "||" => {
+                let or = op == "||";
+                match self.compile(lhs)? {
+                    MathOp::Number(n) if truth(n) == or => MathOp::Number(number(or)),
+                    MathOp::Number(_) => fold(vec![self.compile(rhs)?], Rc::new(|a| Ok(number(truth(a[0])))))?,
+                    lhs => {
+                        let rhs = self.compile_lazy(rhs);
+                        MathOp::Dynamic(Rc::new(move || match truth(lhs.try_eval()?) == or {
+                            true => Ok(number(or)),
+                            false => Ok(number(truth(rhs.try_eval()?))),
+                        }))
                     }
-                    let args: Vec<_> = operands.split_off(operands.len() - arity);
-                    operands.push(
-                        eval_fn(fname, &args)?
-                    );
                 }
-                _ => return Err(format!("Unexpected token for RPN eval: {:?}", token)),
             }
-        }
-        operands.pop().ok_or(format!("Failed to eval RPN: {:?}", rpn))
-    }
-
-    pub fn compile(&self, rpn: &RPNExpr) -> Result<MathOp, String> {
-        let mut stack = Vec::new();
-        for token in &rpn.0 {
-            match token {
-                MathToken::Number(n) => stack.push(MathOp::Number(*n)),
-                MathToken::Variable(v) => stack.push(
-                    self.0.borrow().get(v).ok_or(format!("Unknown variable: {}", v))?.clone()),
-                MathToken::BOp(op) => {
-                    let rhs = stack.pop().ok_or(format!("Missing operands for {}", op))?;
-                    let lhs = stack.pop().ok_or(format!("Missing operands for {}", op))?;
-                    let dynamic = !(
-                        matches!(rhs, MathOp::Number(_)) && matches!(lhs, MathOp::Number(_)));
-                    let op = op.clone();
-                    let eval = move || {
-                        Ok(match op.as_str() {
-                            "+" => lhs.eval() + rhs.eval(),
-                            "-" => lhs.eval() - rhs.eval(),
-                            "*" => lhs.eval() * rhs.eval(),
-                            "/" => lhs.eval() / rhs.eval(),
-                            "%" => lhs.eval() % rhs.eval(),
-                            "^" | "**" => lhs.eval().powf(rhs.eval()),
-                            _ => return Err(format!("Unknown BOp: {}", op)),
-                        })
-                    };
-                    stack.push(if dynamic {
-                        MathOp::Dynamic(Rc::new(eval))
-                    } else {
-                        MathOp::Number(eval()?)
-                    });
+            ExprKind::BOp(op, lhs, rhs) => {
+                let f = self.opfn(op, &[Fixity::Infix])?;
+                fold(vec![self.compile(lhs)?, self.compile(rhs)?], f)?
+            }
+            ExprKind::UOp(op, arg) => {
+                let f = self.opfn(op, &[Fixity::Prefix, Fixity::Postfix])?;
+                fold(vec![self.compile(arg)?], f)?
+            }
+            ExprKind::Function(fname, args) => {
+                let args = args.iter()
+                    .map(|arg| self.compile(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match self.function(fname)? {
+                    Function::Native(arity, f) => {
+                        arity.check(fname, args.len())?;
+                        fold(args, f)?
+                    }
+                    Function::User(params, _) => {
+                        Arity::Fixed(params.len()).check(fname, args.len())?;
+                        // a weak ref, the op may be stored in this context
+                        let cx = Rc::downgrade(&self.0);
+                        let fname = fname.clone();
+                        fold(args, Rc::new(move |args: &[f64]| match cx.upgrade() {
+                            Some(cx) => MathContext(cx).call(&fname, args),
+                            None => Err(format!("No context to call {}", fname)),
+                        }))?
+                    }
                 }
-                MathToken::UOp(op) => {
-                    let arg = stack.pop().ok_or(format!("Missing operands for {}", op))?;
-                    let dynamic = !matches!(arg, MathOp::Number(_));
-                    let op = op.clone();
-                    let eval = move || {
-                        Ok(match op.as_str() {
-                            "-" => -arg.eval(),
-                            "!" => libm::tgamma(arg.eval() + 1.0),
-                            _ => return Err(format!("Unknown UOp: {}", op)),
-                        })
-                    };
-                    stack.push(if dynamic {
-                        MathOp::Dynamic(Rc::new(eval))
-                    } else {
-                        MathOp::Number(eval()?)
-                    });
+            }
+            ExprKind::Cond(cond, then, otherwise) => match self.compile(cond)? {
+                MathOp::Number(n) => self.compile(if truth(n) { then } else { otherwise })?,
+                cond => {
+                    let (then, otherwise) = (self.compile_lazy(then), self.compile_lazy(otherwise));
+                    MathOp::Dynamic(Rc::new(move || match truth(cond.try_eval()?) {
+                        true => then.try_eval(),
+                        false => otherwise.try_eval(),
+                    }))
                 }
-                MathToken::Function(fname, arity) => {
-                    if *arity > stack.len() {
-                        return Err(format!("Missing args for {}", fname));
-                    }
-                    let args: Vec<_> = stack.split_off(stack.len() - arity);
-                    let dynamic = !args.iter().all(|arg| matches!(arg, MathOp::Number(_)));
-                    let fname = fname.clone();
-                    let eval = move || -> Result<MathOp, String> {
-                        let args: Vec<_> = args.iter().map(|v| v.eval()).collect();
-                        Ok(
-                            MathOp::Number(eval_fn(&fname, &args)?)
-                        )
-                    };
-                    stack.push(if dynamic {
-                        MathOp::Dynamic(Rc::new(move || eval().map(|v| v.eval())))
-                    } else {
-                        eval()?
-                    });
+            },
+        })
+    }
+
+    // Compile a branch that may not be taken, errors are deferred until it is
+    fn compile_lazy(&self, expr: &Expr) -> MathOp {
+        self.compile(expr).unwrap_or_else(|e| MathOp::Dynamic(Rc::new(move || Err(e.clone()))))
+    }
+
+    fn var(&self, name: &str) -> Option<MathOp> {
+        self.0.borrow().vars.get(name).cloned()
+    }
+
+    fn function(&self, fname: &str) -> Result<Function, String> {
+        self.0.borrow().funcs.get(fname).cloned()
+            .ok_or(format!("Unknown Function: {}", fname))
+    }
+
+    // Implementation of 'op' with the first fixity that has one
+    fn opfn(&self, op: &str, fixities: &[Fixity]) -> Result<NativeFn, String> {
+        let cx = self.0.borrow();
+        fixities.iter()
+            .find_map(|fixity| cx.opfns.get(&(op.to_string(), *fixity)).cloned())
+            .ok_or(match fixities {
+                [Fixity::Infix] => format!("Unknown BOp: {}", op),
+                _ => format!("Unknown UOp: {}", op),
+            })
+    }
+
+    fn call(&self, fname: &str, args: &[f64]) -> Result<f64, String> {
+        match self.function(fname)? {
+            Function::Native(arity, f) => {
+                arity.check(fname, args.len())?;
+                f(args)
+            }
+            Function::User(params, body) => {
+                Arity::Fixed(params.len()).check(fname, args.len())?;
+                if self.0.borrow().depth >= MAX_DEPTH {
+                    return Err(format!("Too much recursion in {}", fname));
                 }
-                _ => return Err(format!("Unexpected token for RPN compile: {:?}", token)),
+                let locals = params.iter().map(|p| p.as_str()).zip(args.iter().copied()).collect();
+                self.0.borrow_mut().depth += 1;
+                let result = self.eval_in(&body, &locals);
+                self.0.borrow_mut().depth -= 1;
+                result
             }
         }
-        assert_eq!(stack.len(), 1);
-        Ok(stack.pop().ok_or("Failed to compile RPNExpr")?)
     }
 }
 
-fn eval_fn(fname: &str, args: &[f64]) -> Result<f64, String> {
-    Ok(match fname {
-        "abs" if args.len() == 1 => args[0].abs(),
-        "atan2" if args.len() == 2 => args[0].atan2(args[1]),
-        "cos" if args.len() == 1 => args[0].cos(),
-        "log" if args.len() == 1 => args[0].log10(),
-        "max" if !args.is_empty() => args.iter().fold(args[0], |a, &b| a.max(b)),
-        "min" if !args.is_empty() => args.iter().fold(args[0], |a, &b| a.min(b)),
-        // Order not important
-        "nCr" if args.len() == 2 => funcs::combinations(args[0], args[1])?,
-        "nMCr" if args.len() == 2 => funcs::multicombinations(args[0], args[1])?,
-        // Order is important
-        "nMPr" if args.len() == 2 => args[0].powf(args[1]),
-        "nPr" if args.len() == 2 => funcs::permutations(args[0], args[1])?,
-        "sin" if args.len() == 1 => args[0].sin(),
-        _ => return Err(format!("Unknown Function: {} with {} args", fname, args.len()))
-    })
+// Nonzero numbers are true, comparisons and logical operators give 1 or 0
+fn truth(x: f64) -> bool {
+    x != 0.0
 }
 
-mod funcs {
-    pub fn combinations(n: f64, r: f64) -> Result<f64, String> {
-        use libm::tgamma;
-        Ok(tgamma(n + 1.0) / tgamma(r + 1.0) / tgamma(n - r + 1.0))
-    }
+fn number(b: bool) -> f64 {
+    if b { 1.0 } else { 0.0 }
+}
 
-    pub fn multicombinations(n: f64, r: f64) -> Result<f64, String> {
-        use libm::tgamma;
-        Ok(tgamma(n + r) / tgamma(r + 1.0) / tgamma(n))
-    }
+// Evaluate 'f' now if all args are constant, else defer it
+fn fold(args: Vec<MathOp>, f: NativeFn) -> Result<MathOp, String> {
+    let dynamic = !args.iter().all(|arg| matches!(arg, MathOp::Number(_)));
+    let eval = move || f(&args.iter().map(|v| v.try_eval()).collect::<Result<Vec<_>, _>>()?);
+    Ok(if dynamic {
+        MathOp::Dynamic(Rc::new(eval))
+    } else {
+        MathOp::Number(eval()?)
+    })
+}
 
-    pub fn permutations(n: f64, r: f64) -> Result<f64, String> {
-        use libm::tgamma;
-        Ok(tgamma(n + 1.0) / tgamma(n - r + 1.0))
+// Arithmetic and comparison operators and standard functions of every MathContext, see mathlib
+fn builtins(cx: &MathContext) {
+    type Op = fn(&[f64]) -> f64;
+    let ops: [(&str, Fixity, Op); 14] = [
+        ("==", Fixity::Infix, |a| number(a[0] == a[1])),
+        ("!=", Fixity::Infix, |a| number(a[0] != a[1])),
+        ("<", Fixity::Infix, |a| number(a[0] < a[1])),
+        (">", Fixity::Infix, |a| number(a[0] > a[1])),
+        ("<=", Fixity::Infix, |a| number(a[0] <= a[1])),
+        (">=", Fixity::Infix, |a| number(a[0] >= a[1])),
+        ("+", Fixity::Infix, |a| a[0] + a[1]),
+        ("-", Fixity::Infix, |a| a[0] - a[1]),
+        ("*", Fixity::Infix, |a| a[0] * a[1]),
+        ("/", Fixity::Infix, |a| a[0] / a[1]),
+        ("%", Fixity::Infix, |a| a[0] % a[1]),
+        ("^", Fixity::Infix, |a| a[0].powf(a[1])),
+        ("**", Fixity::Infix, |a| a[0].powf(a[1])),
+        ("-", Fixity::Prefix, |a| -a[0]),
+    ];
+    for (op, fixity, f) in ops {
+        let f: NativeFn = Rc::new(move |args| Ok(f(args)));
+        cx.0.borrow_mut().opfns.insert((op.to_string(), fixity), f);
     }
+    // factorial, gamma(x + 1) for non integers
+    let fact: NativeFn = Rc::new(|a| match a[0] < 0.0 && a[0].fract() == 0.0 {
+        true => Err(format!("Domain error: ({})! needs x not a negative integer", a[0])),
+        false => Ok(libm::tgamma(a[0] + 1.0)),
+    });
+    cx.0.borrow_mut().opfns.insert(("!".to_string(), Fixity::Postfix), fact);
+    // if(c, a, b) calls are parsed as conditionals, this only checks arity
+    cx.setfn("if", Arity::Fixed(3), |a| Ok(if truth(a[0]) { a[1] } else { a[2] }));
+    mathlib::register(cx);
 }
@@ -1,5 +1,12 @@
-use crate::parser::ShuntingParser;
-use crate::rpneval::MathContext;
+use crate::parser::{Assoc, Fixity, ParseError, ShuntingParser};
+use crate::rpneval::{Arity, MathContext, MathOp, RandomVariable};
+use lexers::{Position, Span};
+
+// Span within the first line
+fn span(start: usize, end: usize) -> Span {
+    let pos = |column: usize| Position { line: 1, column, offset: column - 1 };
+    Span { start: pos(start), end: pos(end) }
+}
 
 macro_rules! fuzzy_eq {
     ($lhs:expr, $rhs:expr) => {
@@ -63,3 +70,122 @@ fn test_eval8() {
     let expr = ShuntingParser::parse_str("-2^-3").unwrap();
     fuzzy_eq!(MathContext::new().eval(&expr).unwrap(), -0.125);
 }
+
+#[test]
+fn test_functions() {
+    let cx = MathContext::new();
+    cx.setfn("hypot", Arity::Fixed(2), |a| Ok(a[0].hypot(a[1])));
+    cx.setfn("sum", Arity::Variadic(0), |a| Ok(a.iter().sum()));
+    let eval = |expr: &str| cx.eval(&cx.parse_str(expr).unwrap());
+    fuzzy_eq!(eval("hypot(3, 4)").unwrap(), 5.0);
+    fuzzy_eq!(eval("sum(1, 2, 3) + sum()").unwrap(), 6.0);
+    fuzzy_eq!(eval("max(2, -7, 9, 1)").unwrap(), 9.0);
+    assert_eq!(eval("hypot(3)"), Err("Function hypot takes 2 args, got 1".to_string()));
+    assert_eq!(eval("max()"), Err("Function max takes at least 1 args, got 0".to_string()));
+    assert_eq!(eval("nope(1)"), Err("Unknown Function: nope".to_string()));
+}
+
+#[test]
+fn test_operators() {
+    let cx = MathContext::new();
+    cx.setop("~", Fixity::Prefix, 9, Assoc::Right, |a| Ok(1.0 - a[0]));
+    cx.setop("$", Fixity::Infix, 8, Assoc::Left, |a| Ok(a[0] - a[1]));
+    cx.setop("@", Fixity::Postfix, 10, Assoc::Left, |a| Ok(a[0] * 100.0));
+    let eval = |expr: &str| cx.eval(&cx.parse_str(expr).unwrap());
+    fuzzy_eq!(eval("~(3 < 2) * 2").unwrap(), 2.0);
+    // left associative, unlike ^
+    fuzzy_eq!(eval("10 $ 3 $ 2").unwrap(), 5.0);
+    fuzzy_eq!(eval("-0.5@ + 1").unwrap(), -49.0);
+    assert!(matches!(ShuntingParser::parse_str("1 $ 2"), Err(ParseError::BadToken(_, _))));
+    assert_eq!(cx.parse_str("2 ~ 3"), Err(ParseError::ExtraOperand(span(3, 4))));
+    // compiled ops are folded or deferred too
+    let x = cx.compile(&cx.parse_str("~(1 < 2) + 0.5@").unwrap()).unwrap();
+    assert!(matches!(x, MathOp::Number(n) if n == 50.0));
+}
+
+#[test]
+fn test_comparisons() {
+    let cx = MathContext::new();
+    let eval = |expr: &str| cx.eval(&cx.parse_str(expr).unwrap());
+    fuzzy_eq!(eval("1 + 2 < 4").unwrap(), 1.0);
+    fuzzy_eq!(eval("2 >= 3 || 3 <= 3").unwrap(), 1.0);
+    fuzzy_eq!(eval("1 < 2 == 2 < 1").unwrap(), 0.0);
+    fuzzy_eq!(eval("2 != 2 || 4 > 3 && 0").unwrap(), 0.0);
+    fuzzy_eq!(eval("0.5 && -2").unwrap(), 1.0);
+    // the rhs isn't evaluated if the lhs decides
+    fuzzy_eq!(eval("0 && sqrt(-1)").unwrap(), 0.0);
+    fuzzy_eq!(eval("3 || sqrt(-1)").unwrap(), 1.0);
+    assert!(eval("1 && sqrt(-1)").is_err());
+}
+
+#[test]
+fn test_conditionals() {
+    let cx = MathContext::new();
+    cx.setvar("x", MathOp::Number(5.0));
+    let eval = |expr: &str| cx.eval(&cx.parse_str(expr).unwrap());
+    fuzzy_eq!(eval("max(0, x) > 3 ? 1 : 0").unwrap(), 1.0);
+    fuzzy_eq!(eval("x < 0 ? -1 : x > 0 ? 1 : 0").unwrap(), 1.0);
+    fuzzy_eq!(eval("x > 0 ? x < 3 ? 1 : 2 : 3").unwrap(), 2.0);
+    fuzzy_eq!(eval("(x > 9 ? 1 : 2) * 10").unwrap(), 20.0);
+    fuzzy_eq!(eval("if(x == 5, 10, sqrt(-1)) + 1").unwrap(), 11.0);
+    assert_eq!(eval("if(1, 2)"), Err("Function if takes 3 args, got 2".to_string()));
+    cx.deffn("fact", vec!["n".to_string()], cx.parse_str("n < 1 ? 1 : n * fact(n - 1)").unwrap());
+    fuzzy_eq!(eval("fact(5)").unwrap(), 120.0);
+    // compiled branches are only evaluated when taken
+    cx.setvar("r", MathOp::Dynamic(std::rc::Rc::new(|| Ok(-4.0))));
+    let compile = |expr: &str| cx.compile(&cx.parse_str(expr).unwrap());
+    fuzzy_eq!(compile("r < 0 ? fact(3) : sqrt(r)").unwrap().eval(), 6.0);
+    fuzzy_eq!(compile("r > 0 && sqrt(r)").unwrap().eval(), 0.0);
+    fuzzy_eq!(compile("r < 0 || y").unwrap().eval(), 1.0);
+    assert!(matches!(compile("x > 3 ? 1 : y"), Ok(MathOp::Number(n)) if n == 1.0));
+    assert_eq!(compile("x > 3 ? y : 1").err(), Some("Unknown variable: y".to_string()));
+}
+
+#[test]
+fn test_user_functions() {
+    let cx = MathContext::new();
+    cx.deffn("f", vec!["x".to_string(), "y".to_string()], cx.parse_str("x^2 + y").unwrap());
+    cx.deffn("g", vec!["x".to_string()], cx.parse_str("f(x, x) * k").unwrap());
+    cx.setvar("k", MathOp::Number(2.0));
+    cx.setvar("x", MathOp::Number(100.0));
+    let expr = cx.parse_str("g(3) + x").unwrap();
+    fuzzy_eq!(cx.eval(&expr).unwrap(), 124.0);
+    let compiled = cx.compile(&expr).unwrap();
+    fuzzy_eq!(compiled.eval(), 124.0);
+    // args that aren't constant defer the call
+    cx.setvar("r", MathOp::Dynamic(std::rc::Rc::new(|| Ok(1.0))));
+    let compiled = cx.compile(&cx.parse_str("f(r, r)").unwrap()).unwrap();
+    assert!(matches!(compiled, MathOp::Dynamic(_)));
+    fuzzy_eq!(compiled.eval(), 2.0);
+    assert_eq!(cx.eval(&cx.parse_str("f(1)").unwrap()),
+               Err("Function f takes 2 args, got 1".to_string()));
+    cx.deffn("loop", vec![], cx.parse_str("loop()").unwrap());
+    assert_eq!(cx.eval(&cx.parse_str("loop()").unwrap()),
+               Err("Too much recursion in loop".to_string()));
+}
+
+#[test]
+fn test_failing_variables() {
+    let cx = MathContext::new();
+    cx.setvar("r", MathOp::Dynamic(std::rc::Rc::new(|| Ok(-1.0))));
+    cx.setvar("y", cx.compile(&cx.parse_str("sqrt(r)").unwrap()).unwrap());
+    let eval = |expr: &str| cx.eval(&cx.parse_str(expr).unwrap());
+    assert!(eval("y + 1").is_err());
+    assert_eq!(eval("z + 1"), Err("Unknown variable: z".to_string()));
+    // failed samples are skipped
+    let n = std::rc::Rc::new(std::cell::Cell::new(0));
+    let counter = n.clone();
+    let odd = MathOp::Dynamic(std::rc::Rc::new(move || {
+        counter.set(counter.get() + 1);
+        match counter.get() % 2 {
+            0 => Err("even".to_string()),
+            _ => Ok(counter.get() as f64),
+        }
+    }));
+    let histogram = odd.histogram::<4>(100);
+    assert_eq!(n.get(), 100);
+    assert_eq!(histogram.buckets.iter().sum::<u32>(), 50);
+    fuzzy_eq!(histogram.max, 99.0);
+    let histogram = cx.compile(&cx.parse_str("y").unwrap()).unwrap().histogram::<4>(10);
+    assert_eq!(histogram.buckets, [0; 4]);
+}